@@ -1,14 +1,69 @@
 ﻿use crate::span::*;
 use crate::tokenizer::*;
+use crate::ty::IntKind;
 
 pub type NodeId = usize;
 
 pub struct Crate {
-    pub fns: Vec<Fn>,
+    pub items: Vec<Item>,
 }
 
-pub enum Ty {
-    Int,
+/// A top-level declaration, in source order.
+pub enum Item {
+    Fn(Fn),
+    /// A declaration without a body: tags, typedefs and function
+    /// prototypes.
+    Decl(DeclSpec, Vec<VarDecl>),
+}
+
+/// The type named by the type specifiers of a declaration.
+pub enum TypeSpec {
+    Void,
+    Int(IntKind),
+    Record(RecordSpec),
+    Enum(EnumSpec),
+    /// A typedef name.
+    Typedef(Symbol),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecordKind {
+    Struct,
+    Union,
+}
+
+/// `struct tag { ... }` or `union tag { ... }`. `members` is `None` when
+/// no body is written, so the specifier refers to (or forward-declares) a
+/// tag.
+pub struct RecordSpec {
+    pub kind: RecordKind,
+    pub tag: Option<Symbol>,
+    pub members: Option<Vec<MemberDecl>>,
+    /// Span of the `struct`/`union` keyword.
+    pub span: Span,
+}
+
+/// `enum tag { A, B = 2 }`; `enumerators` is `None` when no body is
+/// written.
+pub struct EnumSpec {
+    pub tag: Option<Symbol>,
+    pub enumerators: Option<Vec<Enumerator>>,
+    /// Span of the `enum` keyword.
+    pub span: Span,
+}
+
+pub struct Enumerator {
+    pub name: Symbol,
+    pub value: Option<Box<Expr>>,
+    pub span: Span,
+}
+
+/// One member declaration, `int x, *y;`. A nested struct or union written
+/// without declarators is an anonymous member whose fields belong to the
+/// enclosing record.
+pub struct MemberDecl {
+    pub spec: DeclSpec,
+    pub declarators: Vec<Declarator>,
 }
 
 pub struct Fn {
@@ -18,10 +73,50 @@ pub struct Fn {
 }
 
 pub struct DeclSpec{
+    pub ty: TypeSpec,
+    pub quals: TypeQuals,
+    /// `typedef`: the declarators name types instead of objects.
+    pub is_typedef: bool,
+    pub storage: Option<StorageClass>,
+    pub is_inline: bool,
+    /// `_Thread_local`: one object per thread.
+    pub is_thread_local: bool,
+}
 
+/// The storage-class specifiers other than `typedef` and `_Thread_local`,
+/// of which a declaration takes at most one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageClass {
+    Static,
+    Extern,
+    Register,
+    Auto,
 }
 
+impl StorageClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StorageClass::Static => "static",
+            StorageClass::Extern => "extern",
+            StorageClass::Register => "register",
+            StorageClass::Auto => "auto",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TypeQuals {
+    pub is_const: bool,
+    pub is_volatile: bool,
+    pub is_restrict: bool,
+    pub is_atomic: bool,
+}
+
+/// One `*` of a declarator with the qualifiers written after it. `inner`
+/// holds the `*`s that follow, so in `int *const *p` the outermost
+/// `PointerDecl` is the `*const`.
 pub struct PointerDecl{
+    pub quals: TypeQuals,
     pub inner: Option<Box<PointerDecl>>,
 }
 
@@ -32,11 +127,19 @@ pub struct ParamDecl {
 
 pub enum DirectDeclarator{
     Ident(Symbol),
+    /// The place of the name in an abstract declarator, as in the type
+    /// name `int (*)[3]` or an unnamed parameter.
+    Abstract,
     Paren(Box<Declarator>),
 
     Func {
         inner: Box<DirectDeclarator>,
         params: Vec<ParamDecl>,   
+        /// The list ends in `...`.
+        variadic: bool,
+        /// The parentheses are not empty; `(void)` lists no parameters
+        /// but still makes a prototype.
+        prototyped: bool,
     },
 
     Array {
@@ -49,27 +152,73 @@ pub struct Declarator{
     pub ptr: Option<Box<PointerDecl>>,
     pub direct: DirectDeclarator,
     pub id: NodeId,
+    /// Span of the declared name, or of where it would be written in an
+    /// abstract declarator.
+    pub span: Span,
+}
+
+/// A type written on its own, as in casts, `sizeof` and `_Alignof`:
+/// specifiers followed by an abstract declarator.
+pub struct TypeName {
+    pub spec: DeclSpec,
+    pub declarator: Declarator,
 }
 
 pub struct VarDecl{
     pub declarator: Declarator,
-    pub init: Option<Box<Expr>>,
+    pub init: Option<Initializer>,
+}
+
+pub enum Initializer {
+    Expr(Box<Expr>),
+    /// `{ 1, .x = 2, [3] = { 4 } }`; the span is the `{`'s.
+    List(Vec<InitItem>, Span),
+}
+
+/// One element of a brace-enclosed initializer with the designators
+/// written before it.
+pub struct InitItem {
+    pub designators: Vec<Designator>,
+    pub init: Initializer,
+}
+
+pub enum Designator {
+    /// `[index]`
+    Index(Box<Expr>),
+    /// `.member`
+    Member(Symbol, Span),
 }
 
 pub enum StmtKind {
     Block(Vec<Stmt>),
     ExprStmt(Box<Expr>),
-    Return(Box<Expr>),
+    Return(Option<Box<Expr>>),
     If(Box<Expr>, Box<Stmt>, Option<Box<Stmt>>),
     While(Box<Expr>, Box<Stmt>),
+    DoWhile(Box<Stmt>, Box<Expr>),
+    /// `for (init; cond; incr) body`; the init is an expression statement
+    /// or a declaration whose scope is the loop.
     For(
-        Option<Box<Expr>>,
+        Option<Box<Stmt>>,
         Option<Box<Expr>>,
         Option<Box<Expr>>,
         Box<Stmt>,
     ),
     Decl(DeclSpec, Vec<VarDecl>),
+    Switch(Box<Expr>, Box<Stmt>),
+    /// `case value: stmt`, labelling a statement of the enclosing switch.
+    Case(Box<Expr>, Box<Stmt>),
+    /// `default: stmt`; the span is the keyword's.
+    Default(Span, Box<Stmt>),
+    /// The span is the keyword's.
+    Break(Span),
+    Continue(Span),
+    Goto(Symbol, Span),
+    /// `name: stmt`; labels live in a namespace of their own per function.
+    Label(Symbol, Span, Box<Stmt>),
     Null,
+    /// A statement that failed to parse; the error is already reported.
+    Error,
 }
 
 pub struct Stmt{
@@ -77,25 +226,41 @@ pub struct Stmt{
     pub id: NodeId,
 }
 
+#[derive(Clone, Copy, PartialEq)]
 pub enum BinaryOpKind {
     Add,
     Sub,
     Mul,
     Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
     EqEq,
     Ne,
     Le,
     Lt,
     Ge,
     Gt,
+    LogAnd,
+    LogOr,
+    Comma,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum UnaryOpKind {
     Pos,
     Neg,
     AddrOf,
     Deref,
+    Not,
+    BitNot,
+    PreInc,
+    PreDec,
+    PostInc,
+    PostDec,
 }
 
 pub enum ExprKind {
@@ -103,8 +268,31 @@ pub enum ExprKind {
     Binary(BinaryOpKind, Box<Expr>, Box<Expr>),
     Unary(UnaryOpKind, Box<Expr>),
     Assign(Box<Expr>, Box<Expr>),
+    /// Compound assignment such as `a += b`; the lvalue is evaluated once.
+    AssignOp(BinaryOpKind, Box<Expr>, Box<Expr>),
+    /// `cond ? then : else`
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
     Var(Symbol),
-    FnCall(Symbol, Vec<Expr>),
+    /// `callee(args)`; the callee is a function or a pointer to one.
+    FnCall(Box<Expr>, Vec<Expr>),
+    /// `base.member`; `p->member` is parsed as `(*p).member`.
+    Member(Box<Expr>, Symbol),
+    /// `(type) expr`
+    Cast(Box<TypeName>, Box<Expr>),
+    /// `sizeof expr`; the operand is never evaluated.
+    SizeofExpr(Box<Expr>),
+    /// `sizeof(type)`
+    SizeofType(Box<TypeName>),
+    /// `_Alignof(type)`
+    Alignof(Box<TypeName>),
+    /// `va_start(ap, last)`; `last` names the last named parameter.
+    VaStart(Box<Expr>, Box<Expr>),
+    /// `va_arg(ap, type)`
+    VaArg(Box<Expr>, Box<TypeName>),
+    /// `va_end(ap)`
+    VaEnd(Box<Expr>),
+    /// `va_copy(dest, src)`
+    VaCopy(Box<Expr>, Box<Expr>),
     Error,
 }
 
@@ -119,13 +307,70 @@ impl BinaryOpKind {
         use BinaryOpKind::*;
         matches!(&self, Ne | EqEq | Ge | Gt | Le | Lt)
     }
+
+    pub fn is_logical(&self) -> bool {
+        use BinaryOpKind::*;
+        matches!(&self, LogAnd | LogOr)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        use BinaryOpKind::*;
+        match self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Mod => "%",
+            BitAnd => "&",
+            BitOr => "|",
+            BitXor => "^",
+            Shl => "<<",
+            Shr => ">>",
+            EqEq => "==",
+            Ne => "!=",
+            Le => "<=",
+            Lt => "<",
+            Ge => ">=",
+            Gt => ">",
+            LogAnd => "&&",
+            LogOr => "||",
+            Comma => ",",
+        }
+    }
+}
+
+impl Initializer {
+    pub fn span(&self) -> Span {
+        match self {
+            Initializer::Expr(expr) => expr.span,
+            Initializer::List(_, span) => *span,
+        }
+    }
+
+    /// The initializing expressions in source order, designators left out.
+    pub fn exprs(&self) -> Vec<&Expr> {
+        match self {
+            Initializer::Expr(expr) => vec![expr],
+            Initializer::List(items, _) => items.iter().flat_map(|item| item.init.exprs()).collect(),
+        }
+    }
+}
+
+impl Designator {
+    pub fn span(&self) -> Span {
+        match self {
+            Designator::Index(index) => index.span,
+            Designator::Member(_, span) => *span,
+        }
+    }
 }
 
 impl Declarator {
-    pub fn function_name(&self) -> Option<&Symbol> {
-        fn peel(d: &DirectDeclarator) -> Option<&Symbol> {
+    pub fn function_name(&self) -> Option<Symbol> {
+        fn peel(d: &DirectDeclarator) -> Option<Symbol> {
             match d {
-                DirectDeclarator::Ident(sym) => Some(sym),
+                DirectDeclarator::Ident(sym) => Some(*sym),
+                DirectDeclarator::Abstract => None,
 
                 DirectDeclarator::Paren(inner_decl) => {
                     peel(&inner_decl.direct)
@@ -143,4 +388,38 @@ impl Declarator {
 
         peel(&self.direct)
     }
+
+    /// Whether the declarator declares a function, i.e. whether the
+    /// derivation closest to the name is a parameter list: true for
+    /// `f(int)` and `*f(void)`, false for `(*fp)(int)`.
+    pub fn is_function(&self) -> bool {
+        fn closest(d: &Declarator) -> Option<bool> {
+            closest_direct(&d.direct).or(d.ptr.as_ref().map(|_| false))
+        }
+        fn closest_direct(d: &DirectDeclarator) -> Option<bool> {
+            match d {
+                DirectDeclarator::Ident(_) | DirectDeclarator::Abstract => None,
+                DirectDeclarator::Paren(inner) => closest(inner),
+                DirectDeclarator::Func { inner, .. } => closest_direct(inner).or(Some(true)),
+                DirectDeclarator::Array { inner, .. } => closest_direct(inner).or(Some(false)),
+            }
+        }
+
+        closest(self) == Some(true)
+    }
+
+    /// The parameter list closest to the name, which for a function
+    /// definition such as `int (*f(int i))(long)` is the one naming `i`.
+    pub fn params(&self) -> Option<&[ParamDecl]> {
+        fn peel(d: &DirectDeclarator) -> Option<&[ParamDecl]> {
+            match d {
+                DirectDeclarator::Ident(_) | DirectDeclarator::Abstract => None,
+                DirectDeclarator::Paren(inner_decl) => peel(&inner_decl.direct),
+                DirectDeclarator::Func { inner, params, .. } => peel(inner).or(Some(params)),
+                DirectDeclarator::Array { inner, .. } => peel(inner),
+            }
+        }
+
+        peel(&self.direct)
+    }
 }
@@ -1,5 +1,5 @@
 pub use super::{ast, hir};
 
-pub fn ast_to_hir(ast: ast::Crate) -> hir::Crate{
+pub fn ast_to_hir(_ast: ast::Crate) -> hir::Crate{
     todo!("ast to hir")
 }
\ No newline at end of file
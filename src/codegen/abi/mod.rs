@@ -1,6 +1,47 @@
+use std::fmt;
+use std::str::FromStr;
+
 pub mod sysv;
 pub mod win64;
 
+/// Calling convention selectable from the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetAbi {
+    SysV,
+    Win64,
+}
+
+impl TargetAbi {
+    pub fn host() -> TargetAbi {
+        if cfg!(windows) {
+            TargetAbi::Win64
+        } else {
+            TargetAbi::SysV
+        }
+    }
+}
+
+impl FromStr for TargetAbi {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TargetAbi, String> {
+        match s {
+            "sysv" => Ok(TargetAbi::SysV),
+            "win64" => Ok(TargetAbi::Win64),
+            _ => Err(format!("unknown abi `{s}`, expected `sysv` or `win64`")),
+        }
+    }
+}
+
+impl fmt::Display for TargetAbi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetAbi::SysV => f.write_str("sysv"),
+            TargetAbi::Win64 => f.write_str("win64"),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Reg {
     Rax,
@@ -42,6 +83,34 @@ impl Reg {
             Reg::Rsp => "rsp",
         }
     }
+
+    /// The name of the low `size` bytes of the register.
+    pub fn asm_sized(&self, size: usize) -> &'static str {
+        let names: [&'static str; 4] = match self {
+            Reg::Rax => ["al", "ax", "eax", "rax"],
+            Reg::Rbx => ["bl", "bx", "ebx", "rbx"],
+            Reg::Rcx => ["cl", "cx", "ecx", "rcx"],
+            Reg::Rdx => ["dl", "dx", "edx", "rdx"],
+            Reg::Rsi => ["sil", "si", "esi", "rsi"],
+            Reg::Rdi => ["dil", "di", "edi", "rdi"],
+            Reg::R8 => ["r8b", "r8w", "r8d", "r8"],
+            Reg::R9 => ["r9b", "r9w", "r9d", "r9"],
+            Reg::R10 => ["r10b", "r10w", "r10d", "r10"],
+            Reg::R11 => ["r11b", "r11w", "r11d", "r11"],
+            Reg::R12 => ["r12b", "r12w", "r12d", "r12"],
+            Reg::R13 => ["r13b", "r13w", "r13d", "r13"],
+            Reg::R14 => ["r14b", "r14w", "r14d", "r14"],
+            Reg::R15 => ["r15b", "r15w", "r15d", "r15"],
+            Reg::Rbp => ["bpl", "bp", "ebp", "rbp"],
+            Reg::Rsp => ["spl", "sp", "esp", "rsp"],
+        };
+        match size {
+            1 => names[0],
+            2 => names[1],
+            4 => names[2],
+            _ => names[3],
+        }
+    }
 }
 
 pub trait Abi {
@@ -50,4 +119,22 @@ pub trait Abi {
     fn stack_align(&self) -> usize;
     fn shadow_space_size(&self) -> usize;
     fn stack_param_base(&self) -> usize;
+    fn wchar_size(&self) -> usize;
+    /// Instructions leaving the address of the thread-local `label` in
+    /// rax, or `None` if thread-local storage is not supported.
+    fn tls_address(&self, label: &str) -> Option<Vec<String>>;
+    /// Whether a call to a variadic function passes in `al` how many
+    /// vector registers hold arguments.
+    fn counts_vector_args(&self) -> bool;
+    /// Instructions at the entry of a variadic function storing the
+    /// argument registers where `va_arg` reads them: in the register save
+    /// area at `[rbp - area]` if the frame has one.
+    fn save_va_regs(&self, area: Option<usize>) -> Vec<String>;
+    /// Instructions for `va_start` in a function with `named` named
+    /// parameters, with the address of the `va_list` state in rax.
+    fn va_start(&self, named: usize, area: Option<usize>) -> Vec<String>;
+    /// Instructions for `va_arg` with the address of the `va_list` state
+    /// in rax: leave the address of the next argument in rax and step the
+    /// state past it. `label` is unique to this `va_arg`.
+    fn va_arg(&self, label: &str) -> Vec<String>;
 }
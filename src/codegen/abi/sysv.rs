@@ -22,4 +22,61 @@ impl Abi for SysVAbi {
     fn stack_param_base(&self) -> usize {
         16
     }
+
+    fn wchar_size(&self) -> usize {
+        4
+    }
+
+    fn tls_address(&self, label: &str) -> Option<Vec<String>> {
+        // local-exec model: the block sits at a fixed offset from fs:0
+        Some(vec![
+            "mov rax, QWORD PTR fs:0".to_string(),
+            format!("add rax, OFFSET FLAT:{label}@tpoff"),
+        ])
+    }
+
+    fn counts_vector_args(&self) -> bool {
+        true
+    }
+
+    fn save_va_regs(&self, area: Option<usize>) -> Vec<String> {
+        // 6 general-purpose registers, then 8 vector registers of 16 bytes
+        let area = area.expect("a variadic frame has a register save area");
+        let gp = SYSV_PARAMS.iter().enumerate().map(|(i, reg)| format!("mov [rbp - {}], {}", area - 8 * i, reg.asm()));
+        let fp = (0..8).map(|i| format!("movsd [rbp - {}], xmm{i}", area - 48 - 16 * i));
+        gp.chain(fp).collect()
+    }
+
+    fn va_start(&self, named: usize, area: Option<usize>) -> Vec<String> {
+        let area = area.expect("a variadic frame has a register save area");
+        let in_regs = named.min(SYSV_PARAMS.len());
+        let on_stack = named - in_regs;
+        vec![
+            format!("mov dword ptr [rax], {}", 8 * in_regs),
+            // no vector register holds a named argument
+            "mov dword ptr [rax + 4], 48".to_string(),
+            format!("lea rdx, [rbp + {}]", self.stack_param_base() + 8 * on_stack),
+            "mov [rax + 8], rdx".to_string(),
+            format!("lea rdx, [rbp - {area}]"),
+            "mov [rax + 16], rdx".to_string(),
+        ]
+    }
+
+    fn va_arg(&self, label: &str) -> Vec<String> {
+        // from the save area while general-purpose registers are left,
+        // then from the stack
+        vec![
+            "mov edx, dword ptr [rax]".to_string(),
+            "cmp edx, 48".to_string(),
+            format!("jae {label}.stack"),
+            "add rdx, [rax + 16]".to_string(),
+            "add dword ptr [rax], 8".to_string(),
+            format!("jmp {label}.end"),
+            format!("{label}.stack:"),
+            "mov rdx, [rax + 8]".to_string(),
+            "add qword ptr [rax + 8], 8".to_string(),
+            format!("{label}.end:"),
+            "mov rax, rdx".to_string(),
+        ]
+    }
 }
@@ -22,4 +22,41 @@ impl Abi for Win64Abi {
     fn stack_param_base(&self) -> usize {
         48
     }
-}
\ No newline at end of file
+
+    fn wchar_size(&self) -> usize {
+        2
+    }
+
+    fn tls_address(&self, _label: &str) -> Option<Vec<String>> {
+        // reaching the TLS block needs `_tls_index` and the TEB's slot
+        // array, which we do not emit
+        None
+    }
+
+    fn counts_vector_args(&self) -> bool {
+        false
+    }
+
+    fn save_va_regs(&self, _area: Option<usize>) -> Vec<String> {
+        // the caller's shadow space sits right below the stack arguments,
+        // so spilling the registers there lines up all the arguments
+        WIN64_PARAMS
+            .iter()
+            .enumerate()
+            .map(|(i, reg)| format!("mov [rbp + {}], {}", 16 + 8 * i, reg.asm()))
+            .collect()
+    }
+
+    fn va_start(&self, named: usize, _area: Option<usize>) -> Vec<String> {
+        // `va_list` is a pointer to the next argument
+        vec![format!("lea rdx, [rbp + {}]", 16 + 8 * named), "mov [rax], rdx".to_string()]
+    }
+
+    fn va_arg(&self, _label: &str) -> Vec<String> {
+        vec![
+            "mov rdx, [rax]".to_string(),
+            "add qword ptr [rax], 8".to_string(),
+            "mov rax, rdx".to_string(),
+        ]
+    }
+}
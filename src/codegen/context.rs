@@ -8,14 +8,37 @@
 //     }
 // }
 
+use crate::resolver::ObjId;
+use crate::tokenizer::Symbol;
+use crate::ty::Ty;
+
 pub struct FnContext {
-    pub name: String,
+    pub name: Symbol,
+    pub fn_id: ObjId,
+    /// Return type; `return` converts its value to it.
+    pub ret: Ty,
     pub label_cnt: usize,
+    /// Where `break` jumps to, for the innermost loop or switch last.
+    pub break_labels: Vec<String>,
+    /// Where `continue` jumps to, for the innermost loop last.
+    pub continue_labels: Vec<String>,
 }
 
 impl FnContext {
-    pub fn new(name: String) -> FnContext {
-        FnContext { name, label_cnt: 0 }
+    pub fn new(name: Symbol, fn_id: ObjId, ret: Ty) -> FnContext {
+        FnContext {
+            name,
+            fn_id,
+            ret,
+            label_cnt: 0,
+            break_labels: vec![],
+            continue_labels: vec![],
+        }
+    }
+
+    /// A label local to the function: `.L.<fn id>.<kind>.<n>`.
+    pub fn label(&self, kind: &str, n: usize) -> String {
+        format!(".L.{}.{kind}.{n}", self.fn_id)
     }
 
     pub fn apply(&mut self) -> usize {
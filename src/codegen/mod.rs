@@ -1,13 +1,19 @@
 ﻿use super::resolver::*;
 use abi::{Abi, Reg};
-use core::panic;
+use std::collections::BTreeSet;
 use std::io::{self, Write};
 
 use crate::{
     ast::*,
     codegen::context::FnContext,
+    consteval::{AddrBase, ConstAddr},
+    error_handler::{Diagnostic, ErrorCode},
     frame_layout::{FrameLayout, FrameLayouts},
     resolver::ResolvedCrate,
+    span::Span,
+    tokenizer::{Encoding, Lit, LitKind, stored_units, unescape_str},
+    ty::{IntKind, Ty},
+    typeck::TypeckResults,
 };
 
 mod context;
@@ -18,7 +24,15 @@ pub struct CodeGen<W: Write, ABI: Abi + Default> {
     writer: W,
     abi: ABI,
     resolved: ResolvedCrate,
+    tys: TypeckResults,
     layouts: FrameLayouts,
+    /// Number of 8-byte values currently pushed on the evaluation stack.
+    depth: usize,
+    /// String literals to be emitted into `.rodata`, indexed by label number.
+    strings: Vec<(Encoding, Vec<u32>)>,
+    /// Symbols referred to through an alias, see `operand_of`.
+    aliases: BTreeSet<String>,
+    pub errors: Vec<Diagnostic>,
 }
 
 impl<W: Write, ABI: Abi + Default> Write for CodeGen<W, ABI> {
@@ -32,117 +46,468 @@ impl<W: Write, ABI: Abi + Default> Write for CodeGen<W, ABI> {
 }
 
 impl<W: Write, ABI: Abi + Default> CodeGen<W, ABI> {
-    pub fn new(writer: W, resolved: ResolvedCrate, layouts: FrameLayouts) -> CodeGen<W, ABI> {
+    pub fn new(writer: W, resolved: ResolvedCrate, tys: TypeckResults, layouts: FrameLayouts) -> CodeGen<W, ABI> {
         CodeGen {
             writer,
             abi: ABI::default(),
             resolved,
+            tys,
             layouts,
+            depth: 0,
+            strings: vec![],
+            aliases: BTreeSet::new(),
+            errors: vec![],
         }
     }
 
     pub fn push(&mut self, reg: &Reg) -> Result<(), io::Error> {
+        self.depth += 1;
         writeln!(self, "  push {}\n", reg.asm())
     }
 
     pub fn pop(&mut self, reg: &Reg) -> Result<(), io::Error> {
+        self.depth -= 1;
         writeln!(self, "  pop {}\n", reg.asm())
     }
 
-    pub fn gen_expr(&mut self, expr: &Expr, fn_layout: &FrameLayout) -> Result<(), io::Error> {
+    /// Emits `rax = rax <op> rdi` for every non-short-circuiting operator.
+    /// Both operands already have type `ty`, which decides between the
+    /// signed and unsigned forms of comparisons, division and `>>`.
+    fn gen_binop(&mut self, op: BinaryOpKind, ty: &Ty) -> Result<(), io::Error> {
+        let unsigned = ty.is_unsigned();
+        match op {
+            cmp @ (BinaryOpKind::EqEq
+            | BinaryOpKind::Ne
+            | BinaryOpKind::Ge
+            | BinaryOpKind::Gt
+            | BinaryOpKind::Le
+            | BinaryOpKind::Lt) => {
+                writeln!(self, "  cmp rax, rdi\n")?;
+                let set = match (cmp, unsigned) {
+                    (BinaryOpKind::EqEq, _) => "sete",
+                    (BinaryOpKind::Ne, _) => "setne",
+                    (BinaryOpKind::Ge, false) => "setge",
+                    (BinaryOpKind::Gt, false) => "setg",
+                    (BinaryOpKind::Le, false) => "setle",
+                    (BinaryOpKind::Lt, false) => "setl",
+                    (BinaryOpKind::Ge, true) => "setae",
+                    (BinaryOpKind::Gt, true) => "seta",
+                    (BinaryOpKind::Le, true) => "setbe",
+                    (BinaryOpKind::Lt, true) => "setb",
+                    _ => unreachable!(),
+                };
+                writeln!(self, "  {set} al\n")?;
+                writeln!(self, "  movzx rax, al\n")?;
+            }
+            BinaryOpKind::Add => {
+                writeln!(self, "  add rax, rdi\n")?;
+            }
+            BinaryOpKind::Sub => {
+                writeln!(self, "  sub rax, rdi\n")?;
+            }
+            BinaryOpKind::Mul => {
+                writeln!(self, "  imul rax, rdi\n")?;
+            }
+            BinaryOpKind::Div | BinaryOpKind::Mod => {
+                if unsigned {
+                    writeln!(self, "  xor edx, edx\n")?;
+                    writeln!(self, "  div rdi\n")?;
+                } else {
+                    writeln!(self, "  cqo\n")?;
+                    writeln!(self, "  idiv rdi\n")?;
+                }
+                if op == BinaryOpKind::Mod {
+                    writeln!(self, "  mov rax, rdx\n")?;
+                }
+            }
+            BinaryOpKind::BitAnd => {
+                writeln!(self, "  and rax, rdi\n")?;
+            }
+            BinaryOpKind::BitOr => {
+                writeln!(self, "  or rax, rdi\n")?;
+            }
+            BinaryOpKind::BitXor => {
+                writeln!(self, "  xor rax, rdi\n")?;
+            }
+            BinaryOpKind::Shl => {
+                writeln!(self, "  mov rcx, rdi\n")?;
+                writeln!(self, "  shl rax, cl\n")?;
+            }
+            BinaryOpKind::Shr => {
+                writeln!(self, "  mov rcx, rdi\n")?;
+                if unsigned {
+                    writeln!(self, "  shr rax, cl\n")?;
+                } else {
+                    writeln!(self, "  sar rax, cl\n")?;
+                }
+            }
+            BinaryOpKind::LogAnd | BinaryOpKind::LogOr | BinaryOpKind::Comma => {
+                unreachable!("handled in gen_expr")
+            }
+        }
+        Ok(())
+    }
+
+    /// The type both operands of `lhs <op> rhs` are converted to before the
+    /// operation; `None` for pointer arithmetic and shifts, whose operands
+    /// are converted separately.
+    fn operand_ty(&self, op: BinaryOpKind, lhs: &Ty, rhs: &Ty) -> Option<Ty> {
+        if !lhs.is_integer() || !rhs.is_integer() || matches!(op, BinaryOpKind::Shl | BinaryOpKind::Shr) {
+            return None;
+        }
+        Some(self.resolved.tcx.usual_arith(lhs, rhs))
+    }
+
+    fn ty_of(&self, expr: &Expr) -> &Ty {
+        &self.tys.expr_tys[&expr.id]
+    }
+
+    /// The object a `Var` refers to.
+    fn obj_of(&self, expr: &Expr) -> &Obj {
+        &self.resolved.objs[self.resolved.expr_resolutions[&expr.id]]
+    }
+
+    /// The assembler label of a function or static object. Block-scope
+    /// statics get a local label made unique by their id.
+    fn label_of(&self, id: ObjId) -> String {
+        let obj = &self.resolved.objs[id];
+        match obj.linkage {
+            Linkage::None => format!(".L.{}.{}", obj.name, obj.id),
+            Linkage::Internal | Linkage::External => obj.name.to_string(),
+        }
+    }
+
+    /// The label of `id` as an operand. A name the assembler would read as
+    /// a register or an operator there, such as `rax`, is referred to
+    /// through a local alias that `gen_aliases` defines.
+    fn operand_of(&mut self, id: ObjId) -> String {
+        let label = self.label_of(id);
+        if !is_asm_reserved(&label) {
+            return label;
+        }
+        let alias = format!(".L.sym.{label}");
+        self.aliases.insert(label);
+        alias
+    }
+
+    fn size_of(&self, ty: &Ty) -> usize {
+        self.resolved.tcx.size_of(ty)
+    }
+
+    /// Replaces the address in `rax` by the value stored there, sign- or
+    /// zero-extended to 64 bits. An array or function is left as its
+    /// address, which is what it decays to.
+    fn load(&mut self, ty: &Ty) -> Result<(), io::Error> {
+        if !ty.is_scalar() {
+            return Ok(());
+        }
+        let extend = if ty.is_unsigned() { "movzx" } else { "movsx" };
+        match self.size_of(ty) {
+            1 => writeln!(self, "  {extend} rax, byte ptr [rax]\n"),
+            2 => writeln!(self, "  {extend} rax, word ptr [rax]\n"),
+            4 if ty.is_unsigned() => writeln!(self, "  mov eax, dword ptr [rax]\n"),
+            4 => writeln!(self, "  movsxd rax, dword ptr [rax]\n"),
+            _ => writeln!(self, "  mov rax, [rax]\n"),
+        }
+    }
+
+    /// Writes `rax` to the address in `rdi`, keeping as many low bytes as
+    /// `ty` is wide. A struct or union is copied byte by byte from the
+    /// address in `rax`.
+    fn store(&mut self, ty: &Ty) -> Result<(), io::Error> {
+        let size = self.size_of(ty);
+        if let Ty::Struct(_) | Ty::Union(_) = ty {
+            for i in 0..size {
+                writeln!(self, "  mov r8b, [rax + {i}]\n")?;
+                writeln!(self, "  mov [rdi + {i}], r8b\n")?;
+            }
+            return Ok(());
+        }
+        writeln!(self, "  mov [rdi], {}\n", Reg::Rax.asm_sized(size))
+    }
+
+    /// Sign- or zero-extends the low bytes of `rax` that hold a value of
+    /// `ty`, as left by a call returning `ty`.
+    fn extend(&mut self, ty: &Ty) -> Result<(), io::Error> {
+        if !ty.is_integer() {
+            return Ok(());
+        }
+        let extend = if ty.is_unsigned() { "movzx" } else { "movsx" };
+        match self.size_of(ty) {
+            1 => writeln!(self, "  {extend} rax, al\n"),
+            2 => writeln!(self, "  {extend} rax, ax\n"),
+            4 if ty.is_unsigned() => writeln!(self, "  mov eax, eax\n"),
+            4 => writeln!(self, "  movsxd rax, eax\n"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Converts the value of type `from` in `rax` to type `to` (C11 6.3.1).
+    /// Values in `rax` are always extended from their type's width, so an
+    /// integer conversion is a truncation followed by a new extension.
+    fn cast(&mut self, from: &Ty, to: &Ty) -> Result<(), io::Error> {
+        if from == to {
+            return Ok(());
+        }
+        if *to == Ty::Int(IntKind::Bool) {
+            writeln!(self, "  cmp rax, 0\n")?;
+            writeln!(self, "  setne al\n")?;
+            writeln!(self, "  movzx rax, al\n")?;
+            return Ok(());
+        }
+        self.extend(to)
+    }
+
+    /// Scales the integer operand of pointer `+`/`-` by the pointee size,
+    /// and turns a pointer difference into an element count. Operands are in
+    /// `rax` (lhs) and `rdi` (rhs), already converted to `ty` unless a
+    /// pointer is involved; `gen_binop` does the rest.
+    fn gen_ptr_arith(&mut self, op: BinaryOpKind, lhs: &Ty, rhs: &Ty, ty: &Ty) -> Result<(), io::Error> {
+        match (op, lhs.pointee(), rhs.pointee()) {
+            (BinaryOpKind::Sub, Some(base), Some(_)) => {
+                writeln!(self, "  sub rax, rdi\n")?;
+                writeln!(self, "  mov rdi, {}\n", self.size_of(base))?;
+                writeln!(self, "  cqo\n")?;
+                writeln!(self, "  idiv rdi\n")?;
+                return Ok(());
+            }
+            (BinaryOpKind::Add | BinaryOpKind::Sub, Some(base), None) => {
+                writeln!(self, "  imul rdi, {}\n", self.size_of(base))?;
+            }
+            (BinaryOpKind::Add, None, Some(base)) => {
+                writeln!(self, "  imul rax, {}\n", self.size_of(base))?;
+            }
+            _ => {}
+        }
+        self.gen_binop(op, ty)
+    }
+
+    pub fn gen_expr(
+        &mut self,
+        expr: &Expr,
+        ctx: &mut FnContext,
+        fn_layout: &FrameLayout,
+    ) -> Result<(), io::Error> {
         match &expr.kind {
-            ExprKind::Binary(ops, lhs, rhs) => {
-                self.gen_expr(rhs, fn_layout)?;
+            ExprKind::Binary(BinaryOpKind::Comma, lhs, rhs) => {
+                self.gen_expr(lhs, ctx, fn_layout)?;
+                self.gen_expr(rhs, ctx, fn_layout)?;
+            }
+            ExprKind::Binary(op @ (BinaryOpKind::LogAnd | BinaryOpKind::LogOr), lhs, rhs) => {
+                // `&&` jumps out as soon as an operand is 0, `||` as soon as one is not
+                let cnt = ctx.apply();
+                let (jump, short, full) = match op {
+                    BinaryOpKind::LogAnd => ("je", 0, 1),
+                    _ => ("jne", 1, 0),
+                };
+                self.gen_expr(lhs, ctx, fn_layout)?;
+                writeln!(self, "  cmp rax, 0\n")?;
+                writeln!(self, "  {jump} .L.{}.short.{}\n", ctx.fn_id, cnt)?;
+                self.gen_expr(rhs, ctx, fn_layout)?;
+                writeln!(self, "  cmp rax, 0\n")?;
+                writeln!(self, "  {jump} .L.{}.short.{}\n", ctx.fn_id, cnt)?;
+                writeln!(self, "  mov rax, {full}\n")?;
+                writeln!(self, "  jmp .L.{}.end.{}\n", ctx.fn_id, cnt)?;
+                writeln!(self, ".L.{}.short.{}:\n", ctx.fn_id, cnt)?;
+                writeln!(self, "  mov rax, {short}\n")?;
+                writeln!(self, ".L.{}.end.{}:\n", ctx.fn_id, cnt)?;
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (lhs_ty, rhs_ty) = (self.ty_of(lhs).clone().decay(), self.ty_of(rhs).clone().decay());
+                let operand_ty = self.operand_ty(*op, &lhs_ty, &rhs_ty);
+                self.gen_expr(rhs, ctx, fn_layout)?;
+                if let Some(ty) = &operand_ty {
+                    self.cast(&rhs_ty, ty)?;
+                }
                 self.push(&Reg::Rax)?;
-                self.gen_expr(lhs, fn_layout)?;
+                self.gen_expr(lhs, ctx, fn_layout)?;
+                let ty = operand_ty.unwrap_or_else(|| self.resolved.tcx.promote(&lhs_ty));
+                self.cast(&lhs_ty, &ty)?;
                 self.pop(&Reg::Rdi)?;
-                match ops {
-                    cmp @ (BinaryOpKind::EqEq
-                    | BinaryOpKind::Ne
-                    | BinaryOpKind::Ge
-                    | BinaryOpKind::Gt
-                    | BinaryOpKind::Le
-                    | BinaryOpKind::Lt) => {
-                        writeln!(self, "  cmp rax, rdi\n")?;
-                        match cmp {
-                            BinaryOpKind::EqEq => {
-                                writeln!(self, "  sete al\n")?;
-                            }
-                            BinaryOpKind::Ne => {
-                                writeln!(self, "  setne al\n")?;
-                            }
-                            BinaryOpKind::Ge => {
-                                writeln!(self, "  setge al\n")?;
-                            }
-                            BinaryOpKind::Gt => {
-                                writeln!(self, "  setg al\n")?;
-                            }
-                            BinaryOpKind::Le => {
-                                writeln!(self, "  setle al\n")?;
-                            }
-                            BinaryOpKind::Lt => {
-                                writeln!(self, "  setl al\n")?;
-                            }
-                            _ => unreachable!(),
-                        }
-                        writeln!(self, "  movzx rax, al\n")?;
-                    }
-                    BinaryOpKind::Add => {
-                        writeln!(self, "  add rax, rdi\n")?;
-                    }
-                    BinaryOpKind::Sub => {
-                        writeln!(self, "  sub rax, rdi\n")?;
+                self.gen_ptr_arith(*op, &lhs_ty, &rhs_ty, &ty)?;
+                let result = self.ty_of(expr).clone();
+                self.extend(&result)?;
+            }
+            ExprKind::Unary(UnaryOpKind::AddrOf, operand) => {
+                self.gen_var(operand, ctx, fn_layout)?;
+            }
+            ExprKind::Unary(
+                op @ (UnaryOpKind::PreInc
+                | UnaryOpKind::PreDec
+                | UnaryOpKind::PostInc
+                | UnaryOpKind::PostDec),
+                operand,
+            ) => {
+                self.gen_var(operand, ctx, fn_layout)?;
+                writeln!(self, "  mov rdi, rax\n")?;
+                let step = match op {
+                    UnaryOpKind::PreInc | UnaryOpKind::PostInc => "add",
+                    _ => "sub",
+                };
+                let ty = self.ty_of(operand).clone();
+                let amount = ty.pointee().map_or(1, |base| self.size_of(base));
+                let width = match self.size_of(&ty) {
+                    1 => "byte",
+                    2 => "word",
+                    4 => "dword",
+                    _ => "qword",
+                };
+                let post = matches!(op, UnaryOpKind::PostInc | UnaryOpKind::PostDec);
+                if ty == Ty::Int(IntKind::Bool) {
+                    // `b++` is `b = (_Bool)(b + 1)`, which never wraps
+                    self.load(&ty)?;
+                    writeln!(self, "  mov rsi, rax\n")?;
+                    writeln!(self, "  {step} rax, 1\n")?;
+                    self.cast(&Ty::INT, &ty)?;
+                    self.store(&ty)?;
+                    if post {
+                        writeln!(self, "  mov rax, rsi\n")?;
                     }
-                    BinaryOpKind::Mul => {
-                        writeln!(self, "  imul rax, rdi\n")?;
+                } else {
+                    if post {
+                        self.load(&ty)?;
                     }
-                    BinaryOpKind::Div => {
-                        writeln!(self, "  cqo\n")?;
-                        writeln!(self, "  idiv rdi\n")?;
+                    writeln!(self, "  {step} {width} ptr [rdi], {amount}\n")?;
+                    if !post {
+                        writeln!(self, "  mov rax, rdi\n")?;
+                        self.load(&ty)?;
                     }
                 }
             }
             ExprKind::Unary(op, operand) => {
-                self.gen_expr(operand, fn_layout)?; // Generate code for operand (pushes result)
+                self.gen_expr(operand, ctx, fn_layout)?;
+                let result = self.ty_of(expr).clone();
+                if matches!(op, UnaryOpKind::Pos | UnaryOpKind::Neg | UnaryOpKind::BitNot) {
+                    let ty = self.ty_of(operand).clone();
+                    self.cast(&ty, &result)?;
+                }
 
                 match op {
                     UnaryOpKind::Pos => {
-                        // Unary '+' does nothing - value already on stack
-                        // No code needed!
+                        // Unary '+' does nothing - value already in rax
                     }
                     UnaryOpKind::Neg => {
-                        // Negate the value on top of stack
-                        writeln!(self, "  neg rax\n")?; // Negate it (rax = -rax)
-                    }
-                    UnaryOpKind::AddrOf => {
-                        self.gen_var(operand, fn_layout)?;
+                        writeln!(self, "  neg rax\n")?;
+                        self.extend(&result)?;
                     }
                     UnaryOpKind::Deref => {
-                        writeln!(self, "  mov rax, [rax]")?;
+                        self.load(&result)?;
                     }
+                    UnaryOpKind::Not => {
+                        writeln!(self, "  cmp rax, 0\n")?;
+                        writeln!(self, "  sete al\n")?;
+                        writeln!(self, "  movzx rax, al\n")?;
+                    }
+                    UnaryOpKind::BitNot => {
+                        writeln!(self, "  not rax\n")?;
+                        self.extend(&result)?;
+                    }
+                    _ => unreachable!("handled above"),
                 }
             }
-            ExprKind::Literal(text) => {
-                writeln!(self, "  mov rax, {}\n", text.symbol)?;
+            ExprKind::Literal(Lit {
+                kind: LitKind::Str(_),
+                symbol,
+                ..
+            }) => {
+                let label = self.strings.len();
+                self.strings.push(unescape_str(symbol.as_str()));
+                writeln!(self, "  lea rax, [rip + .L.str.{label}]\n")?;
             }
-            ExprKind::Var(_) => {
-                self.gen_var(expr, fn_layout)?;
-                writeln!(self, "  mov rax, [rax]\n")?;
+            ExprKind::Literal(lit) => {
+                writeln!(self, "  mov rax, {}\n", lit.value as i64)?;
+            }
+            ExprKind::Var(_) if let ObjKind::EnumConst(value) = self.obj_of(expr).kind => {
+                writeln!(self, "  mov rax, {value}\n")?;
+            }
+            ExprKind::Var(_) | ExprKind::Member(..) => {
+                self.gen_var(expr, ctx, fn_layout)?;
+                let ty = self.ty_of(expr).clone();
+                self.load(&ty)?;
             }
             ExprKind::Assign(lhs, rhs) => {
-                self.gen_var(lhs, fn_layout)?;
+                self.gen_var(lhs, ctx, fn_layout)?;
                 self.push(&Reg::Rax)?;
-                self.gen_expr(rhs, fn_layout)?;
+                self.gen_expr(rhs, ctx, fn_layout)?;
+                let (ty, rhs_ty) = (self.ty_of(lhs).clone(), self.ty_of(rhs).clone().decay());
+                self.cast(&rhs_ty, &ty)?;
                 self.pop(&Reg::Rdi)?;
-                writeln!(self, "  mov [rdi], rax\n")?;
+                self.store(&ty)?;
             }
-            ExprKind::FnCall(sym, exprs) => {
-                for expr in exprs.iter().rev() {
-                    self.gen_expr(expr, fn_layout)?;
-                    self.push(&Reg::Rax)?;
+            ExprKind::AssignOp(op, lhs, rhs) => {
+                self.gen_var(lhs, ctx, fn_layout)?;
+                self.push(&Reg::Rax)?;
+                let (ty, rhs_ty) = (self.ty_of(lhs).clone(), self.ty_of(rhs).clone().decay());
+                let operand_ty = self.operand_ty(*op, &ty, &rhs_ty);
+                self.gen_expr(rhs, ctx, fn_layout)?;
+                if let Some(operand_ty) = &operand_ty {
+                    self.cast(&rhs_ty, operand_ty)?;
                 }
+                writeln!(self, "  mov rdi, rax\n")?;
+                writeln!(self, "  mov rax, [rsp]\n")?;
+                self.load(&ty)?;
+                // `a op= b` is `a = (A)(a op b)`, computed in the common type
+                let operand_ty = operand_ty.unwrap_or_else(|| self.resolved.tcx.promote(&ty));
+                self.cast(&ty, &operand_ty)?;
+                self.gen_ptr_arith(*op, &ty, &rhs_ty, &operand_ty)?;
+                self.extend(&operand_ty)?;
+                self.cast(&operand_ty, &ty)?;
+                self.pop(&Reg::Rdi)?;
+                self.store(&ty)?;
+            }
+            ExprKind::Cond(cond, then, els) => {
+                let cnt = ctx.apply();
+                self.gen_expr(cond, ctx, fn_layout)?;
+                writeln!(self, "  cmp rax, 0\n")?;
+                writeln!(self, "  je .L.{}.else.{}\n", ctx.fn_id, cnt)?;
+                let ty = self.ty_of(expr).clone();
+                self.gen_expr(then, ctx, fn_layout)?;
+                let then_ty = self.ty_of(then).clone().decay();
+                self.cast(&then_ty, &ty)?;
+                writeln!(self, "  jmp .L.{}.end.{}\n", ctx.fn_id, cnt)?;
+                writeln!(self, ".L.{}.else.{}:\n", ctx.fn_id, cnt)?;
+                self.gen_expr(els, ctx, fn_layout)?;
+                let els_ty = self.ty_of(els).clone().decay();
+                self.cast(&els_ty, &ty)?;
+                writeln!(self, ".L.{}.end.{}:\n", ctx.fn_id, cnt)?;
+            }
+            ExprKind::FnCall(callee, exprs) => {
                 let regs = self.abi.int_arg_regs();
                 let nreg = exprs.len().min(regs.len());
+                let stack_args = exprs.len() - nreg;
+                // rsp must be 16-byte aligned at the call, with the stack
+                // arguments sitting right above the return address
+                let pad = if (self.depth + stack_args) % 2 == 1 { 8 } else { 0 };
+                if pad > 0 {
+                    writeln!(self, "  sub rsp, {pad}\n")?;
+                    self.depth += 1;
+                }
+                let fn_ty = self.ty_of(callee).as_fn().cloned();
+                // arguments convert to the parameter types when these are known
+                let params = fn_ty.as_ref().map_or(vec![], |func| func.params.clone());
+                for (i, arg) in exprs.iter().enumerate().rev() {
+                    let ty = self.ty_of(arg).clone();
+                    self.check_by_value("passing", &ty, arg.span);
+                    self.gen_expr(arg, ctx, fn_layout)?;
+                    if let Some(param) = params.get(i) {
+                        let ty = self.ty_of(arg).clone().decay();
+                        self.cast(&ty, param)?;
+                    }
+                    self.push(&Reg::Rax)?;
+                }
+                // a function named directly is called by its label; any
+                // other callee is evaluated into a scratch register that no
+                // argument is passed in
+                let direct = match &callee.kind {
+                    ExprKind::Var(_) if let ObjKind::Func = self.obj_of(callee).kind => {
+                        Some(self.obj_of(callee).id)
+                    }
+                    _ => None,
+                };
+                if direct.is_none() {
+                    self.gen_expr(callee, ctx, fn_layout)?;
+                    writeln!(self, "  mov r10, rax\n")?;
+                }
                 for reg in self.abi.int_arg_regs().iter().take(nreg) {
                     self.pop(reg)?;
                 }
@@ -150,33 +515,171 @@ impl<W: Write, ABI: Abi + Default> CodeGen<W, ABI> {
                 if shadow > 0 {
                     writeln!(self, "  sub rsp, {shadow}\n")?;
                 }
-                writeln!(self, "  call {sym}\n")?;
-                let collect = shadow + exprs.len().saturating_sub(nreg) * 8;
+                // no argument is ever passed in a vector register; an
+                // unprototyped declaration such as `int printf();` may
+                // also name a variadic function
+                let variadic = fn_ty.as_ref().is_none_or(|func| func.variadic || !func.prototyped);
+                if variadic && self.abi.counts_vector_args() {
+                    writeln!(self, "  mov eax, 0\n")?;
+                }
+                match direct {
+                    Some(id) => {
+                        let label = self.operand_of(id);
+                        writeln!(self, "  call {label}\n")?;
+                    }
+                    None => writeln!(self, "  call r10\n")?,
+                }
+                let collect = shadow + stack_args * 8 + pad;
                 if collect > 0 {
                     writeln!(self, "  add rsp, {collect}\n")?;
                 }
+                self.depth -= stack_args + pad / 8;
+                let ret = self.ty_of(expr).clone();
+                self.check_by_value("returning", &ret, expr.span);
+                self.extend(&ret)?;
+            }
+            ExprKind::Cast(_, operand) => {
+                self.gen_expr(operand, ctx, fn_layout)?;
+                let (from, to) = (self.ty_of(operand).clone().decay(), self.ty_of(expr).clone());
+                if to != Ty::Void {
+                    self.cast(&from, &to)?;
+                }
+            }
+            ExprKind::SizeofExpr(operand) => {
+                let size = self.size_of(self.ty_of(operand));
+                writeln!(self, "  mov rax, {size}\n")?;
+            }
+            ExprKind::SizeofType(name) => {
+                let size = self.size_of(&self.resolved.type_names[&name.declarator.id]);
+                writeln!(self, "  mov rax, {size}\n")?;
+            }
+            ExprKind::Alignof(name) => {
+                let align = self.resolved.tcx.align_of(&self.resolved.type_names[&name.declarator.id]);
+                writeln!(self, "  mov rax, {align}\n")?;
+            }
+            ExprKind::VaStart(ap, _) => {
+                let named = match &self.resolved.objs[ctx.fn_id].ty {
+                    Ty::Func(func) => func.params.len(),
+                    _ => 0,
+                };
+                self.gen_va_state(ap, ctx, fn_layout)?;
+                for insn in self.abi.va_start(named, fn_layout.va_area) {
+                    writeln!(self, "  {insn}\n")?;
+                }
+            }
+            ExprKind::VaArg(ap, _) => {
+                self.gen_va_state(ap, ctx, fn_layout)?;
+                let cnt = ctx.apply();
+                let label = ctx.label("va_arg", cnt);
+                for insn in self.abi.va_arg(&label) {
+                    writeln!(self, "  {insn}\n")?;
+                }
+                let ty = self.ty_of(expr).clone();
+                self.load(&ty)?;
+            }
+            ExprKind::VaEnd(ap) => self.gen_expr(ap, ctx, fn_layout)?,
+            ExprKind::VaCopy(dest, src) => {
+                self.gen_va_state(dest, ctx, fn_layout)?;
+                self.push(&Reg::Rax)?;
+                let state = self.gen_va_state(src, ctx, fn_layout)?;
+                self.load(&state)?;
+                self.pop(&Reg::Rdi)?;
+                self.store(&state)?;
             }
             ExprKind::Error => {}
         };
         Ok(())
     }
 
-    pub fn gen_var(&mut self, var: &Expr, fn_layout: &FrameLayout) -> Result<(), io::Error> {
+    /// Leaves in rax the address of the state `ap` stands for and returns
+    /// its type: the struct a SysV `va_list` is an array of, or the
+    /// pointer a Win64 `va_list` is.
+    fn gen_va_state(&mut self, ap: &Expr, ctx: &mut FnContext, fn_layout: &FrameLayout) -> Result<Ty, io::Error> {
+        match self.resolved.tcx.va_list.clone() {
+            Ty::Array(state, _) => {
+                self.gen_expr(ap, ctx, fn_layout)?;
+                Ok(*state)
+            }
+            state => {
+                self.gen_var(ap, ctx, fn_layout)?;
+                Ok(state)
+            }
+        }
+    }
+
+    pub fn gen_var(
+        &mut self,
+        var: &Expr,
+        ctx: &mut FnContext,
+        fn_layout: &FrameLayout,
+    ) -> Result<(), io::Error> {
         match &var.kind {
-            ExprKind::Var(_) => {
-                let obj_id = self.resolved.expr_resolutions[&var.id];
-                let offset = fn_layout.slots[&obj_id];
-                writeln!(self, "  lea rax, [rbp - {offset}]\n")?;
+            ExprKind::Var(_) if let ObjKind::EnumConst(_) = self.obj_of(var).kind => {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::InvalidLValue, "expression is not assignable")
+                        .with_primary(var.span, "an enumeration constant is not an lvalue"),
+                );
+            }
+            ExprKind::Var(_) => self.gen_obj_addr(var, fn_layout)?,
+            ExprKind::Unary(UnaryOpKind::Deref, inner) => {
+                self.gen_expr(inner, ctx, fn_layout)?;
+            }
+            ExprKind::Member(base, name) => {
+                self.gen_var(base, ctx, fn_layout)?;
+                let base_ty = self.ty_of(base);
+                let offset = self.resolved.tcx.member(base_ty, *name).expect("member checked by typeck").offset;
+                writeln!(self, "  add rax, {offset}\n")?;
+            }
+            ExprKind::Error => {}
+            _ => {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::InvalidLValue, "expression is not assignable")
+                        .with_primary(var.span, "not an lvalue")
+                        .with_note("only variables and dereferenced pointers can be assigned or have their address taken"),
+                );
             }
-            ExprKind::Unary(op, inner) => {
-                if op == &UnaryOpKind::Deref {
-                    self.gen_expr(inner, fn_layout)?;
+        }
+        Ok(())
+    }
+
+    /// Reports a struct or union passed to or returned from a function,
+    /// which only ever travels by address here.
+    fn check_by_value(&mut self, what: &str, ty: &Ty, span: Span) {
+        if ty.is_record() {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::Unsupported, format!("{what} a struct or union by value is not supported"))
+                    .with_primary(span, format!("this has type `{ty}`"))
+                    .with_help("use a pointer to it instead"),
+            );
+        }
+    }
+
+    /// Puts the address of the object `expr` resolves to in rax.
+    fn gen_obj_addr(&mut self, expr: &Expr, fn_layout: &FrameLayout) -> Result<(), io::Error> {
+        let id = self.obj_of(expr).id;
+        match self.obj_of(expr).kind {
+            ObjKind::Func => {
+                let label = self.operand_of(id);
+                writeln!(self, "  lea rax, [rip + {label}]\n")?;
+            }
+            ObjKind::Global => {
+                let label = self.operand_of(id);
+                if !self.resolved.globals.iter().any(|g| g.id == id && g.is_thread_local) {
+                    writeln!(self, "  lea rax, [rip + {label}]\n")?;
+                } else if let Some(insns) = self.abi.tls_address(&label) {
+                    for insn in insns {
+                        writeln!(self, "  {insn}\n")?;
+                    }
                 } else {
-                    unreachable!("should not call gen_var on non-LValue");
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::Unsupported, "thread-local storage is not supported on this target")
+                            .with_primary(expr.span, ""),
+                    );
                 }
             }
             _ => {
-                unreachable!("should not call gen_var on non-LValue");
+                let offset = fn_layout.slots[&id];
+                writeln!(self, "  lea rax, [rbp - {offset}]\n")?;
             }
         }
         Ok(())
@@ -196,73 +699,222 @@ impl<W: Write, ABI: Abi + Default> CodeGen<W, ABI> {
                 }
             }
             StmtKind::ExprStmt(expr) => {
-                self.gen_expr(expr, fn_layout)?;
+                self.gen_expr(expr, prog_context, fn_layout)?;
             }
             StmtKind::Return(expr) => {
-                self.gen_expr(expr, fn_layout)?;
+                if let Some(expr) = expr {
+                    self.gen_expr(expr, prog_context, fn_layout)?;
+                    let ty = self.ty_of(expr).clone().decay();
+                    let ret = prog_context.ret.clone();
+                    self.cast(&ty, &ret)?;
+                }
                 writeln!(self, "  jmp .L.{}.return\n", fn_info.fn_id)?;
             }
             StmtKind::If(condition, ops, else_ops) => {
-                self.gen_expr(condition, fn_layout)?;
+                self.gen_expr(condition, prog_context, fn_layout)?;
                 let cnt = prog_context.apply();
+                let (els, end) = (prog_context.label("else", cnt), prog_context.label("end", cnt));
                 writeln!(self, "  cmp rax, 0\n")?;
-                writeln!(self, "  je .L.{}.else.{}\n", fn_info.fn_id, cnt)?;
+                writeln!(self, "  je {els}\n")?;
                 self.gen_stmt(ops, prog_context, fn_info, fn_layout)?;
-                writeln!(self, "  jmp .L.{}.end.{}\n", fn_info.fn_id, cnt)?;
-                writeln!(self, ".L.{}.else.{}:\n", fn_info.fn_id, cnt)?;
+                writeln!(self, "  jmp {end}\n")?;
+                writeln!(self, "{els}:\n")?;
                 if let Some(else_ops) = else_ops {
                     self.gen_stmt(else_ops, prog_context, fn_info, fn_layout)?;
                 }
-                writeln!(self, ".L.{}.end.{}:\n", fn_info.fn_id, cnt)?;
+                writeln!(self, "{end}:\n")?;
             }
             StmtKind::For(init, cond, incr, ops) => {
                 let cnt = prog_context.apply();
-                if let Some(expr) = init {
-                    self.gen_expr(expr, fn_layout)?;
+                let (begin, brk, cont) = (
+                    prog_context.label("begin", cnt),
+                    prog_context.label("break", cnt),
+                    prog_context.label("continue", cnt),
+                );
+                if let Some(init) = init {
+                    self.gen_stmt(init, prog_context, fn_info, fn_layout)?;
                 }
-                writeln!(self, ".L.{}.begin.{}:\n", fn_info.fn_id, cnt)?;
+                writeln!(self, "{begin}:\n")?;
                 if let Some(expr) = cond {
-                    self.gen_expr(expr, fn_layout)?;
+                    self.gen_expr(expr, prog_context, fn_layout)?;
                     writeln!(self, "  cmp rax, 0\n")?;
-                    writeln!(self, "  je  .L.{}.end.{}\n", fn_info.fn_id, cnt)?;
+                    writeln!(self, "  je  {brk}\n")?;
                 }
-                self.gen_stmt(ops, prog_context, fn_info, fn_layout)?;
+                self.gen_loop_body(ops, &brk, &cont, prog_context, fn_info, fn_layout)?;
+                writeln!(self, "{cont}:\n")?;
                 if let Some(expr) = incr {
-                    self.gen_expr(expr, fn_layout)?;
+                    self.gen_expr(expr, prog_context, fn_layout)?;
                 }
-                writeln!(self, "  jmp .L.{}.begin.{}\n", fn_info.fn_id, cnt)?;
-                writeln!(self, ".L.{}.end.{}:\n", fn_info.fn_id, cnt)?;
+                writeln!(self, "  jmp {begin}\n")?;
+                writeln!(self, "{brk}:\n")?;
             }
             StmtKind::While(cond, ops) => {
                 let cnt = prog_context.apply();
-                writeln!(self, ".L.{}.begin.{}:\n", fn_info.fn_id, cnt)?;
-                self.gen_expr(cond, fn_layout)?;
+                let (cont, brk) = (prog_context.label("continue", cnt), prog_context.label("break", cnt));
+                writeln!(self, "{cont}:\n")?;
+                self.gen_expr(cond, prog_context, fn_layout)?;
                 writeln!(self, "  cmp rax, 0\n")?;
-                writeln!(self, "  je  .L.{}.end.{}\n", fn_info.fn_id, cnt)?;
-                self.gen_stmt(ops, prog_context, fn_info, fn_layout)?;
-                writeln!(self, "  jmp .L.{}.begin.{}\n", fn_info.fn_id, cnt)?;
-                writeln!(self, ".L.{}.end.{}:\n", fn_info.fn_id, cnt)?;
+                writeln!(self, "  je  {brk}\n")?;
+                self.gen_loop_body(ops, &brk, &cont, prog_context, fn_info, fn_layout)?;
+                writeln!(self, "  jmp {cont}\n")?;
+                writeln!(self, "{brk}:\n")?;
+            }
+            StmtKind::DoWhile(ops, cond) => {
+                let cnt = prog_context.apply();
+                let (begin, brk, cont) = (
+                    prog_context.label("begin", cnt),
+                    prog_context.label("break", cnt),
+                    prog_context.label("continue", cnt),
+                );
+                writeln!(self, "{begin}:\n")?;
+                self.gen_loop_body(ops, &brk, &cont, prog_context, fn_info, fn_layout)?;
+                writeln!(self, "{cont}:\n")?;
+                self.gen_expr(cond, prog_context, fn_layout)?;
+                writeln!(self, "  cmp rax, 0\n")?;
+                writeln!(self, "  jne {begin}\n")?;
+                writeln!(self, "{brk}:\n")?;
+            }
+            StmtKind::Switch(value, body) => {
+                let cnt = prog_context.apply();
+                let brk = prog_context.label("break", cnt);
+                self.gen_expr(value, prog_context, fn_layout)?;
+                // the value is compared in its promoted type, to which each
+                // case constant is converted
+                let value_ty = self.ty_of(value).clone();
+                let ty = self.resolved.tcx.promote(&value_ty);
+                self.cast(&value_ty, &ty)?;
+                let cases = self.resolved.switches.remove(&stmt.id).unwrap_or_default();
+                for (case, id) in &cases.cases {
+                    writeln!(self, "  mov rdi, {}\n", self.resolved.tcx.wrap_int(*case, &ty))?;
+                    writeln!(self, "  cmp rax, rdi\n")?;
+                    writeln!(self, "  je {}\n", prog_context.label("case", *id))?;
+                }
+                match cases.default {
+                    Some(id) => writeln!(self, "  jmp {}\n", prog_context.label("case", id))?,
+                    None => writeln!(self, "  jmp {brk}\n")?,
+                }
+                prog_context.break_labels.push(brk.clone());
+                self.gen_stmt(body, prog_context, fn_info, fn_layout)?;
+                prog_context.break_labels.pop();
+                writeln!(self, "{brk}:\n")?;
+            }
+            StmtKind::Case(_, body) | StmtKind::Default(_, body) => {
+                writeln!(self, "{}:\n", prog_context.label("case", stmt.id))?;
+                self.gen_stmt(body, prog_context, fn_info, fn_layout)?;
+            }
+            StmtKind::Break(_) => {
+                let label = prog_context.break_labels.last().expect("checked by the resolver");
+                writeln!(self, "  jmp {label}\n")?;
+            }
+            StmtKind::Continue(_) => {
+                let label = prog_context.continue_labels.last().expect("checked by the resolver");
+                writeln!(self, "  jmp {label}\n")?;
+            }
+            StmtKind::Goto(name, _) => {
+                writeln!(self, "  jmp .L.{}.label.{name}\n", fn_info.fn_id)?;
+            }
+            StmtKind::Label(name, _, body) => {
+                writeln!(self, ".L.{}.label.{name}:\n", fn_info.fn_id)?;
+                self.gen_stmt(body, prog_context, fn_info, fn_layout)?;
             }
             StmtKind::Decl(_, var_decls) => {
                 for var in var_decls.iter() {
                     let Some(init) = var.init.as_ref() else { continue };
 
                     let obj_id = self.resolved.expr_resolutions[&var.declarator.id];
-                    let offset = fn_layout.slots[&obj_id];
+                    // static objects are initialized in their data section
+                    let Some(&offset) = fn_layout.slots.get(&obj_id) else { continue };
 
-                    self.gen_expr(init, fn_layout)?;
-                    writeln!(self, "  mov [rbp - {}], rax", offset)?;
+                    if let Initializer::List(..) = init {
+                        // the elements not initialized explicitly are zero
+                        let size = self.size_of(&self.resolved.objs[obj_id].ty);
+                        writeln!(self, "  lea rdi, [rbp - {offset}]\n")?;
+                        writeln!(self, "  mov rcx, {size}\n")?;
+                        writeln!(self, "  mov al, 0\n")?;
+                        writeln!(self, "  rep stosb\n")?;
+                    }
+                    for expr in init.exprs() {
+                        let Some((field, ty)) = self.resolved.init_targets.get(&expr.id).cloned() else { continue };
+                        self.gen_init(expr, &ty, offset - field, prog_context, fn_layout)?;
+                    }
                 }
             }
-            StmtKind::Null => {}
+            StmtKind::Null | StmtKind::Error => {}
         }
         Ok(())
     }
 
+    /// Stores the initializer `expr` of an element of type `ty` that
+    /// lives at `rbp - offset`.
+    fn gen_init(
+        &mut self,
+        expr: &Expr,
+        ty: &Ty,
+        offset: usize,
+        prog_context: &mut FnContext,
+        fn_layout: &FrameLayout,
+    ) -> Result<(), io::Error> {
+        if let Some(units) = str_array_init(&self.resolved.tcx, &mut ty.clone(), expr) {
+            // a char array is filled unit by unit, the tail zeroed
+            let elem = ty.pointee().expect("array type").clone();
+            let size = self.size_of(&elem);
+            for (i, unit) in units.iter().enumerate() {
+                writeln!(self, "  mov rax, {unit}\n")?;
+                writeln!(self, "  lea rdi, [rbp - {}]\n", offset - i * size)?;
+                self.store(&elem)?;
+            }
+            return Ok(());
+        }
+        self.gen_expr(expr, prog_context, fn_layout)?;
+        let init_ty = self.ty_of(expr).clone().decay();
+        self.cast(&init_ty, ty)?;
+        writeln!(self, "  lea rdi, [rbp - {offset}]\n")?;
+        self.store(ty)
+    }
+
+    /// Emits a loop body with `break` and `continue` bound to the given
+    /// labels.
+    fn gen_loop_body(
+        &mut self,
+        body: &Stmt,
+        brk: &str,
+        cont: &str,
+        prog_context: &mut FnContext,
+        fn_info: &FnInfo,
+        fn_layout: &FrameLayout,
+    ) -> Result<(), io::Error> {
+        prog_context.break_labels.push(brk.to_string());
+        prog_context.continue_labels.push(cont.to_string());
+        self.gen_stmt(body, prog_context, fn_info, fn_layout)?;
+        prog_context.break_labels.pop();
+        prog_context.continue_labels.pop();
+        Ok(())
+    }
+
     pub fn gen_fn(&mut self, func: Fn) -> Result<(), io::Error> {
-        let mut context = FnContext::new(func.declarator.function_name().expect("invalid fn def").clone());
-        let fn_info = self.resolved.fn_info.remove(&context.name).unwrap();
-        let fn_layout = self.layouts.fns.remove(&fn_info.fn_id).unwrap();
+        let name = func.declarator.function_name().expect("invalid fn def");
+        // a body the resolver rejected has no info to generate from
+        let Some(fn_info) = self.resolved.fn_info.remove(&name) else {
+            return Ok(());
+        };
+        let ret = match &self.resolved.objs[fn_info.fn_id].ty {
+            Ty::Func(func) => func.ret.clone(),
+            _ => Ty::INT,
+        };
+        self.check_by_value("returning", &ret, func.declarator.span);
+        for (param, id) in func.declarator.params().unwrap_or_default().iter().zip(&fn_info.params) {
+            let span = param.declarator.as_ref().map_or(func.declarator.span, |d| d.span);
+            let ty = self.resolved.objs[*id].ty.clone();
+            self.check_by_value("passing", &ty, span);
+        }
+        let mut context = FnContext::new(name, fn_info.fn_id, ret);
+        let Some(fn_layout) = self.layouts.fns.remove(&fn_info.fn_id) else {
+            return Ok(());
+        };
+        self.depth = 0;
+        if self.resolved.objs[fn_info.fn_id].linkage == Linkage::External {
+            writeln!(self, ".globl {}\n", context.name)?;
+        }
         writeln!(self, "{}:\n", context.name)?;
         writeln!(self, "  push rbp\n")?;
         writeln!(self, "  mov rbp, rsp\n")?;
@@ -270,19 +922,29 @@ impl<W: Write, ABI: Abi + Default> CodeGen<W, ABI> {
 
         let pair = self.abi.int_arg_regs().iter().zip(fn_info.params.iter());
         for (reg, param) in pair {
+            let size = self.size_of(&self.resolved.objs[*param].ty);
             writeln!(
                 self,
                 "  mov [rbp - {}], {}\n",
                 fn_layout.slots[param],
-                reg.asm()
+                reg.asm_sized(size)
             )?;
         }
-        for param in self.abi.int_arg_regs().len()..fn_info.params.len() {
-            let dest_offset = fn_layout.slots[&fn_info.params[param]];
-            let src_offset = 8 * param.saturating_sub(self.abi.int_arg_regs().len())
+        for i in self.abi.int_arg_regs().len()..fn_info.params.len() {
+            let param = fn_info.params[i];
+            let dest_offset = fn_layout.slots[&param];
+            let src_offset = 8 * i.saturating_sub(self.abi.int_arg_regs().len())
                 + self.abi.stack_param_base();
-            writeln!(self, "  mov rax, [rbp + {}]", src_offset)?;
-            writeln!(self, "  mov [rbp - {}], rax", dest_offset)?;
+            let size = self.size_of(&self.resolved.objs[param].ty);
+            writeln!(self, "  mov rax, [rbp + {}]\n", src_offset)?;
+            writeln!(self, "  mov [rbp - {}], {}\n", dest_offset, Reg::Rax.asm_sized(size))?;
+        }
+        if let Ty::Func(func) = &self.resolved.objs[fn_info.fn_id].ty
+            && func.variadic
+        {
+            for insn in self.abi.save_va_regs(fn_layout.va_area) {
+                writeln!(self, "  {insn}\n")?;
+            }
         }
 
         self.gen_stmt(&func.body, &mut context, &fn_info, &fn_layout)?;
@@ -294,21 +956,183 @@ impl<W: Write, ABI: Abi + Default> CodeGen<W, ABI> {
         Ok(())
     }
 
+    /// Emits the objects with static storage: initialized ones into
+    /// `.data`, or `.rodata` if `const`, and the rest zero-filled into
+    /// `.bss`; thread-local ones into `.tdata` and `.tbss`. Only those
+    /// with external linkage are `.globl`.
+    pub fn gen_data(&mut self) -> Result<(), io::Error> {
+        let globals = std::mem::take(&mut self.resolved.globals);
+        for global in &globals {
+            let obj = &self.resolved.objs[global.id];
+            let (label, ty) = (self.label_of(global.id), obj.ty.clone());
+            if obj.linkage == Linkage::External {
+                writeln!(self, ".globl {label}\n")?;
+            }
+            let (size, align) = (self.size_of(&ty), self.resolved.tcx.align_of(&ty));
+            match (&global.init, global.read_only, global.is_thread_local) {
+                (None, _, true) => writeln!(self, "  .section .tbss,\"awT\",@nobits\n")?,
+                (Some(_), _, true) => writeln!(self, "  .section .tdata,\"awT\",@progbits\n")?,
+                (None, _, false) => writeln!(self, "  .bss\n")?,
+                (Some(_), false, false) => writeln!(self, "  .data\n")?,
+                (Some(_), true, false) => writeln!(self, "  .section .rodata\n")?,
+            }
+            writeln!(self, "  .p2align {}\n", align.trailing_zeros())?;
+            writeln!(self, "{label}:\n")?;
+            match &global.init {
+                None => writeln!(self, "  .zero {size}\n")?,
+                Some(init) => self.gen_init_data(&ty, init)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits the contents of an object of type `ty` with static storage.
+    fn gen_init_data(&mut self, ty: &Ty, init: &GlobalInit) -> Result<(), io::Error> {
+        let size = self.size_of(ty);
+        match init {
+            GlobalInit::Int(value) => {
+                let value = match ty {
+                    Ty::Int(IntKind::Bool) => i64::from(*value != 0),
+                    _ => *value,
+                };
+                self.gen_int_data(value, size)?;
+            }
+            GlobalInit::Str(units) => {
+                let unit_size = ty.pointee().map_or(1, |elem| self.size_of(elem));
+                for &unit in units {
+                    self.gen_int_data(i64::from(unit), unit_size)?;
+                }
+            }
+            GlobalInit::Addr(ConstAddr { base, offset }) => {
+                let label = match base {
+                    AddrBase::Obj(id) => self.operand_of(*id),
+                    AddrBase::Str(enc, units) => {
+                        self.strings.push((*enc, units.clone()));
+                        format!(".L.str.{}", self.strings.len() - 1)
+                    }
+                };
+                match offset {
+                    0 => writeln!(self, "  .quad {label}\n")?,
+                    offset => writeln!(self, "  .quad {label}{offset:+}\n")?,
+                }
+            }
+            GlobalInit::List(fields) => {
+                let mut end = 0;
+                for (offset, ty, value) in fields {
+                    if *offset > end {
+                        writeln!(self, "  .zero {}\n", offset - end)?;
+                    }
+                    self.gen_init_data(ty, value)?;
+                    end = offset + self.size_of(ty);
+                }
+                if size > end {
+                    writeln!(self, "  .zero {}\n", size - end)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits `value` truncated to a `size`-byte integer.
+    fn gen_int_data(&mut self, value: i64, size: usize) -> Result<(), io::Error> {
+        let (directive, value) = match size {
+            1 => (".byte", i64::from(value as u8)),
+            2 => (".short", i64::from(value as u16)),
+            4 => (".long", i64::from(value as u32)),
+            _ => (".quad", value),
+        };
+        writeln!(self, "  {directive} {value}\n")
+    }
+
+    pub fn gen_rodata(&mut self) -> Result<(), io::Error> {
+        if self.strings.is_empty() {
+            return Ok(());
+        }
+        writeln!(self, "  .section .rodata\n")?;
+        let strings = std::mem::take(&mut self.strings);
+        for (label, (enc, units)) in strings.iter().enumerate() {
+            let size = enc.unit_size(self.abi.wchar_size());
+            let mut units = stored_units(units, size);
+            units.push(0);
+            let directive = match size {
+                1 => ".byte",
+                2 => ".short",
+                _ => ".long",
+            };
+            writeln!(self, "  .p2align {}\n", size.trailing_zeros())?;
+            writeln!(self, ".L.str.{label}:\n")?;
+            for chunk in units.chunks(16) {
+                let values = chunk.iter().map(|u| u.to_string()).collect::<Vec<_>>();
+                writeln!(self, "  {directive} {}\n", values.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn gen_crate(&mut self, crat: Crate) -> Result<(), io::Error> {
         writeln!(self, ".intel_syntax noprefix\n")?;
-        writeln!(self, ".globl main\n")?;
-        for func in crat.fns {
-            self.gen_fn(func)?;
+        writeln!(self, "  .text\n")?;
+        for item in crat.items {
+            if let Item::Fn(func) = item {
+                self.gen_fn(func)?;
+            }
         }
+        self.gen_data()?;
+        self.gen_rodata()?;
+        self.gen_aliases()?;
         Ok(())
     }
+
+    /// Defines the aliases `operand_of` handed out. AT&T syntax marks
+    /// registers with `%`, so there the name is read as a symbol.
+    fn gen_aliases(&mut self) -> Result<(), io::Error> {
+        if self.aliases.is_empty() {
+            return Ok(());
+        }
+        writeln!(self, ".att_syntax\n")?;
+        for name in std::mem::take(&mut self.aliases) {
+            writeln!(self, ".set .L.sym.{name}, {name}\n")?;
+        }
+        writeln!(self, ".intel_syntax noprefix\n")
+    }
+}
+
+/// Whether an Intel-syntax operand reads `name` as a register or an
+/// operator rather than as a symbol. Register names are matched by family
+/// and in any case, so a few names that are not registers are matched too.
+fn is_asm_reserved(name: &str) -> bool {
+    const WORDS: &[&str] = &[
+        "al", "ah", "bl", "bh", "cl", "ch", "dl", "dh", "sil", "dil", "bpl", "spl", "rip", "eip", "cs", "ds", "es",
+        "fs", "gs", "ss", "st", "flat", "offset", "short", "and", "or", "xor", "not", "mod", "shl", "shr", "eq",
+        "ne", "lt", "le", "gt", "ge",
+    ];
+    let name = name.to_ascii_lowercase();
+    let wide = name.strip_prefix(['e', 'r']).unwrap_or(&name);
+    if WORDS.contains(&name.as_str()) || ["ax", "bx", "cx", "dx", "si", "di", "bp", "sp"].contains(&wide) {
+        return true;
+    }
+    // numbered registers: r8 to r15 and their halves, control, debug,
+    // vector, mask and tile registers
+    let Some(at) = name.find(|c: char| c.is_ascii_digit()) else {
+        return false;
+    };
+    let (prefix, rest) = name.split_at(at);
+    let suffix = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    match prefix {
+        "r" => matches!(suffix, "" | "d" | "w" | "b"),
+        "cr" | "dr" | "mm" | "xmm" | "ymm" | "zmm" | "k" | "bnd" | "tmm" => suffix.is_empty(),
+        _ => false,
+    }
 }
 
-pub fn gen_asm<ABI: Abi + Default>(
+pub fn gen_asm<ABI: Abi + Default, W: Write>(
+    writer: W,
     crat: Crate,
     res: ResolvedCrate,
+    tys: TypeckResults,
     layouts: FrameLayouts,
-) -> Result<(), io::Error> {
-    let mut codegen: CodeGen<io::Stdout, ABI> = CodeGen::new(io::stdout(), res, layouts);
-    codegen.gen_crate(crat)
+) -> Result<Vec<Diagnostic>, io::Error> {
+    let mut codegen: CodeGen<W, ABI> = CodeGen::new(writer, res, tys, layouts);
+    codegen.gen_crate(crat)?;
+    Ok(codegen.errors)
 }
@@ -0,0 +1,372 @@
+//! Constant expressions (C11 6.6): integer constant expressions for array
+//! sizes, case labels and enumerators, and the address constants that
+//! initialize objects with static storage.
+
+use crate::ast::*;
+use crate::resolver::{ObjId, ObjKind, ResolvedCrate};
+use crate::span::Span;
+use crate::tokenizer::{Encoding, Lit, LitKind, unescape_str};
+use crate::ty::Ty;
+use crate::typeck::TypeChecker;
+use std::collections::HashMap;
+
+/// The value of a constant expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    /// An integer or null pointer, already converted to the expression's
+    /// type.
+    Int(i64),
+    /// An address that the linker fills in.
+    Addr(ConstAddr),
+}
+
+impl ConstValue {
+    /// The integer, or the byte offset from the start of the object.
+    fn offset(&self) -> i64 {
+        match self {
+            ConstValue::Int(value) => *value,
+            ConstValue::Addr(addr) => addr.offset,
+        }
+    }
+
+    /// The value `bytes` further on.
+    fn moved(self, bytes: i64) -> ConstValue {
+        match self {
+            ConstValue::Int(value) => ConstValue::Int(value.wrapping_add(bytes)),
+            ConstValue::Addr(addr) => ConstValue::Addr(ConstAddr {
+                offset: addr.offset.wrapping_add(bytes),
+                ..addr
+            }),
+        }
+    }
+}
+
+/// The address of a static object, function or string literal plus a
+/// byte offset, as in `&arr[2]` or `"abc" + 1`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstAddr {
+    pub base: AddrBase,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddrBase {
+    /// A function or an object with static storage.
+    Obj(ObjId),
+    /// A string literal, which goes into `.rodata`.
+    Str(Encoding, Vec<u32>),
+}
+
+/// Why an expression is not constant: the first subexpression that is
+/// not, with a label saying what is wrong with it.
+#[derive(Debug)]
+pub struct NotConstant {
+    pub span: Span,
+    pub reason: &'static str,
+}
+
+impl NotConstant {
+    fn new(expr: &Expr, reason: &'static str) -> NotConstant {
+        NotConstant { span: expr.span, reason }
+    }
+}
+
+/// Evaluates an integer constant expression whose names are resolved.
+pub fn eval_int(resolved: &ResolvedCrate, expr: &Expr) -> Result<i64, NotConstant> {
+    ConstEval::new(resolved, expr).eval_int(expr)
+}
+
+/// Evaluates the initializer of an object with static storage, which may
+/// also be an address constant.
+pub fn eval(resolved: &ResolvedCrate, expr: &Expr) -> Result<ConstValue, NotConstant> {
+    ConstEval::new(resolved, expr).eval(expr)
+}
+
+/// Evaluates one expression with the C types of its operands, so that
+/// arithmetic wraps and compares the way it would at run time.
+pub struct ConstEval<'a> {
+    resolved: &'a ResolvedCrate,
+    expr_tys: HashMap<NodeId, Ty>,
+}
+
+impl<'a> ConstEval<'a> {
+    /// Types `expr` and its operands ahead of evaluating them. Type errors
+    /// are left for the type checker to report.
+    pub fn new(resolved: &'a ResolvedCrate, expr: &Expr) -> ConstEval<'a> {
+        let mut typeck = TypeChecker::new(resolved);
+        typeck.check_expr(expr);
+        ConstEval {
+            resolved,
+            expr_tys: typeck.results.expr_tys,
+        }
+    }
+
+    fn ty(&self, expr: &Expr) -> Ty {
+        self.expr_tys[&expr.id].clone().decay()
+    }
+
+    pub fn eval_int(&self, expr: &Expr) -> Result<i64, NotConstant> {
+        match self.eval(expr)? {
+            ConstValue::Int(value) => Ok(value),
+            ConstValue::Addr(_) => Err(NotConstant::new(expr, "an address is not an integer constant")),
+        }
+    }
+
+    pub fn eval(&self, expr: &Expr) -> Result<ConstValue, NotConstant> {
+        // an array or function stands for its address
+        if matches!(self.expr_tys[&expr.id], Ty::Array(..) | Ty::Func(_)) {
+            return self.eval_addr(expr);
+        }
+        let tcx = &self.resolved.tcx;
+        let ty = self.ty(expr);
+        let value = match &expr.kind {
+            ExprKind::Literal(Lit {
+                kind: LitKind::Integer(_) | LitKind::Char(_),
+                value,
+                ..
+            }) => tcx.wrap_int(*value as i64, &ty),
+            ExprKind::Var(_) => match self.obj(expr).map(|id| &self.resolved.objs[id].kind) {
+                Some(ObjKind::EnumConst(value)) => *value,
+                _ => return Err(NotConstant::new(expr, "the value of a variable is not a constant")),
+            },
+            ExprKind::Unary(UnaryOpKind::AddrOf, operand) => return self.eval_addr(operand),
+            ExprKind::Unary(UnaryOpKind::Not, operand) => i64::from(!self.truth(operand)?),
+            ExprKind::Unary(op @ (UnaryOpKind::Pos | UnaryOpKind::Neg | UnaryOpKind::BitNot), operand) => {
+                let value = tcx.wrap_int(self.eval_int(operand)?, &ty);
+                let value = match op {
+                    UnaryOpKind::Pos => value,
+                    UnaryOpKind::Neg if ty.is_unsigned() => value.wrapping_neg(),
+                    UnaryOpKind::Neg => match value.checked_neg() {
+                        Some(value) if tcx.wrap_int(value, &ty) == value => value,
+                        _ => return Err(NotConstant::new(expr, "signed integer overflow")),
+                    },
+                    _ => !value,
+                };
+                tcx.wrap_int(value, &ty)
+            }
+            ExprKind::Unary(UnaryOpKind::Deref, _) | ExprKind::Member(..) => {
+                return Err(NotConstant::new(expr, "reading an object is not allowed in a constant expression"));
+            }
+            ExprKind::Unary(..) | ExprKind::Assign(..) | ExprKind::AssignOp(..) => {
+                return Err(NotConstant::new(expr, "assignment is not allowed in a constant expression"));
+            }
+            ExprKind::FnCall(..) => {
+                return Err(NotConstant::new(expr, "a function call is not allowed in a constant expression"));
+            }
+            ExprKind::VaStart(..) | ExprKind::VaArg(..) | ExprKind::VaEnd(_) | ExprKind::VaCopy(..) => {
+                return Err(NotConstant::new(expr, "variable arguments are only known at run time"));
+            }
+            ExprKind::Binary(BinaryOpKind::Comma, ..) => {
+                return Err(NotConstant::new(expr, "the comma operator is not allowed in a constant expression"));
+            }
+            ExprKind::Binary(BinaryOpKind::LogAnd, lhs, rhs) => i64::from(self.truth(lhs)? && self.truth(rhs)?),
+            ExprKind::Binary(BinaryOpKind::LogOr, lhs, rhs) => i64::from(self.truth(lhs)? || self.truth(rhs)?),
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (lhs_ty, rhs_ty) = (self.ty(lhs), self.ty(rhs));
+                match (self.eval(lhs)?, self.eval(rhs)?) {
+                    (ConstValue::Int(a), ConstValue::Int(b)) if !lhs_ty.is_ptr() && !rhs_ty.is_ptr() => {
+                        self.arith(*op, a, b, &lhs_ty, &rhs_ty, &ty).map_err(|reason| NotConstant::new(expr, reason))?
+                    }
+                    (lhs, rhs) => {
+                        return self
+                            .addr_arith(*op, lhs, rhs, &lhs_ty, &rhs_ty, &ty)
+                            .ok_or_else(|| NotConstant::new(expr, "this address arithmetic is not constant"));
+                    }
+                }
+            }
+            ExprKind::Cond(cond, then, els) => {
+                let value = if self.truth(cond)? { self.eval(then)? } else { self.eval(els)? };
+                match value {
+                    ConstValue::Int(value) if ty.is_integer() => tcx.wrap_int(value, &ty),
+                    value => return Ok(value),
+                }
+            }
+            ExprKind::Cast(_, operand) => match self.eval(operand)? {
+                ConstValue::Int(value) if ty.is_integer() => tcx.wrap_int(value, &ty),
+                ConstValue::Int(value) if ty.is_ptr() => value,
+                // an address keeps its relocation only in a pointer or an
+                // integer wide enough to hold it
+                ConstValue::Addr(addr) if ty.is_ptr() || (ty.is_integer() && tcx.size_of(&ty) == 8) => {
+                    return Ok(ConstValue::Addr(addr));
+                }
+                ConstValue::Addr(_) if ty.is_integer() => {
+                    return Err(NotConstant::new(expr, "an address does not fit in this type"));
+                }
+                _ => return Err(NotConstant::new(expr, "this conversion is not constant")),
+            },
+            // the operand is only typed, never evaluated
+            ExprKind::SizeofExpr(operand) => tcx.size_of(&self.expr_tys[&operand.id]) as i64,
+            ExprKind::SizeofType(name) => tcx.size_of(&self.resolved.type_names[&name.declarator.id]) as i64,
+            ExprKind::Alignof(name) => tcx.align_of(&self.resolved.type_names[&name.declarator.id]) as i64,
+            ExprKind::Literal(_) => return Err(NotConstant::new(expr, "a string literal is not an integer constant")),
+            ExprKind::Error => return Err(NotConstant::new(expr, "this expression is invalid")),
+        };
+        Ok(ConstValue::Int(value))
+    }
+
+    /// Evaluates the address of the lvalue `expr`.
+    fn eval_addr(&self, expr: &Expr) -> Result<ConstValue, NotConstant> {
+        let addr = |base| Ok(ConstValue::Addr(ConstAddr { base, offset: 0 }));
+        match &expr.kind {
+            ExprKind::Var(_) => match self.obj(expr) {
+                Some(id) if self.is_static(id) => addr(AddrBase::Obj(id)),
+                _ => Err(NotConstant::new(expr, "the address of a local variable is not a constant")),
+            },
+            ExprKind::Literal(Lit {
+                kind: LitKind::Str(_),
+                symbol,
+                ..
+            }) => {
+                let (enc, units) = unescape_str(symbol.as_str());
+                addr(AddrBase::Str(enc, units))
+            }
+            ExprKind::Unary(UnaryOpKind::Deref, operand) => self.eval(operand),
+            ExprKind::Member(base, name) => {
+                let member = self.resolved.tcx.member(&self.expr_tys[&base.id], *name);
+                match member {
+                    Some(member) => Ok(self.eval_addr(base)?.moved(member.offset as i64)),
+                    None => Err(NotConstant::new(expr, "no member of this name")),
+                }
+            }
+            _ => Err(NotConstant::new(expr, "not an object with static storage")),
+        }
+    }
+
+    fn obj(&self, expr: &Expr) -> Option<ObjId> {
+        self.resolved.expr_resolutions.get(&expr.id).copied()
+    }
+
+    /// Whether the address of `id` is known at link time: a function or a
+    /// static object that is not thread-local.
+    fn is_static(&self, id: ObjId) -> bool {
+        match self.resolved.objs[id].kind {
+            ObjKind::Func => true,
+            ObjKind::Global => !self.resolved.globals.iter().any(|g| g.id == id && g.is_thread_local),
+            _ => false,
+        }
+    }
+
+    /// Whether a scalar constant compares unequal to 0. Addresses of
+    /// objects are never null.
+    fn truth(&self, expr: &Expr) -> Result<bool, NotConstant> {
+        Ok(match self.eval(expr)? {
+            ConstValue::Int(value) => value != 0,
+            ConstValue::Addr(_) => true,
+        })
+    }
+
+    /// Applies `op` to integers of types `lhs_ty` and `rhs_ty`, converted
+    /// to their common type, giving a result of type `ty`.
+    fn arith(&self, op: BinaryOpKind, a: i64, b: i64, lhs_ty: &Ty, rhs_ty: &Ty, ty: &Ty) -> Result<i64, &'static str> {
+        use BinaryOpKind::*;
+        let tcx = &self.resolved.tcx;
+        let common = match op {
+            Shl | Shr => tcx.promote(lhs_ty),
+            _ => tcx.usual_arith(lhs_ty, rhs_ty),
+        };
+        let a = tcx.wrap_int(a, &common);
+        let b = match op {
+            Shl | Shr => tcx.wrap_int(b, &tcx.promote(rhs_ty)),
+            _ => tcx.wrap_int(b, &common),
+        };
+        // values of unsigned types are kept zero-extended, so their
+        // 64-bit patterns compare and divide correctly as `u64`
+        let unsigned = common.is_unsigned();
+        let (ua, ub) = (a as u64, b as u64);
+        let value = match op {
+            Div | Mod if b == 0 => return Err("division by zero"),
+            // a shift count must lie within the promoted width (C11 6.5.7p3)
+            Shl | Shr if b < 0 => return Err("shift count is negative"),
+            Shl | Shr if b >= tcx.size_of(&common) as i64 * 8 => {
+                return Err("shift count is not less than the width of the type");
+            }
+            // a signed result must be representable in the common type
+            // (C11 6.5p5); an unsigned one wraps
+            Add | Sub | Mul | Div | Mod if !unsigned => {
+                let value = match op {
+                    Add => a.checked_add(b),
+                    Sub => a.checked_sub(b),
+                    Mul => a.checked_mul(b),
+                    Div => a.checked_div(b),
+                    _ => a.checked_rem(b),
+                };
+                match value {
+                    Some(value) if tcx.wrap_int(value, &common) == value => value,
+                    _ => return Err("signed integer overflow"),
+                }
+            }
+            Add => a.wrapping_add(b),
+            Sub => a.wrapping_sub(b),
+            Mul => a.wrapping_mul(b),
+            Div => (ua / ub) as i64,
+            Mod => (ua % ub) as i64,
+            BitAnd => a & b,
+            BitOr => a | b,
+            BitXor => a ^ b,
+            Shl => a.wrapping_shl(b as u32),
+            Shr if unsigned => ua.wrapping_shr(b as u32) as i64,
+            Shr => a.wrapping_shr(b as u32),
+            EqEq => i64::from(a == b),
+            Ne => i64::from(a != b),
+            Lt if unsigned => i64::from(ua < ub),
+            Lt => i64::from(a < b),
+            Le if unsigned => i64::from(ua <= ub),
+            Le => i64::from(a <= b),
+            Gt if unsigned => i64::from(ua > ub),
+            Gt => i64::from(a > b),
+            Ge if unsigned => i64::from(ua >= ub),
+            Ge => i64::from(a >= b),
+            LogAnd | LogOr | Comma => unreachable!("evaluated without both operands"),
+        };
+        Ok(tcx.wrap_int(value, ty))
+    }
+
+    /// Applies `op` where an operand is a pointer or an address: moving an
+    /// address by a scaled offset, or subtracting or comparing two
+    /// pointers into the same object. Returns `None` if the result is not
+    /// known before link time.
+    fn addr_arith(
+        &self,
+        op: BinaryOpKind,
+        lhs: ConstValue,
+        rhs: ConstValue,
+        lhs_ty: &Ty,
+        rhs_ty: &Ty,
+        ty: &Ty,
+    ) -> Option<ConstValue> {
+        use BinaryOpKind::*;
+        let tcx = &self.resolved.tcx;
+        // a pointer moves by whole elements; an integer holding an address
+        // by bytes
+        let scale = |ty: &Ty| ty.pointee().map_or(1, |elem| tcx.size_of(elem).max(1) as i64);
+        // two string literals need not be distinct, so only addresses of
+        // the same named object are known apart
+        let same_object = match (&lhs, &rhs) {
+            (ConstValue::Int(_), ConstValue::Int(_)) => true,
+            (ConstValue::Addr(a), ConstValue::Addr(b)) => matches!(a.base, AddrBase::Obj(_)) && a.base == b.base,
+            _ => false,
+        };
+        let (a, b) = (lhs.offset(), rhs.offset());
+        match (op, &lhs, &rhs) {
+            (Add, _, ConstValue::Int(n)) if rhs_ty.is_integer() => Some(lhs.moved(n.wrapping_mul(scale(lhs_ty)))),
+            (Add, ConstValue::Int(n), _) if lhs_ty.is_integer() => Some(rhs.moved(n.wrapping_mul(scale(rhs_ty)))),
+            (Sub, _, ConstValue::Int(n)) if rhs_ty.is_integer() => {
+                Some(lhs.moved(n.wrapping_mul(scale(lhs_ty)).wrapping_neg()))
+            }
+            (Sub, ..) if same_object => Some(ConstValue::Int(tcx.wrap_int(a.wrapping_sub(b) / scale(lhs_ty), ty))),
+            (EqEq | Ne | Lt | Le | Gt | Ge, ..) if same_object => {
+                let (a, b) = (a as u64, b as u64);
+                let holds = match op {
+                    EqEq => a == b,
+                    Ne => a != b,
+                    Lt => a < b,
+                    Le => a <= b,
+                    Gt => a > b,
+                    _ => a >= b,
+                };
+                Some(ConstValue::Int(i64::from(holds)))
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,10 +1,443 @@
 pub use crate::ast::*;
 use crate::span::source_map::SourceFile;
+use crate::span::Span;
 pub use crate::tokenizer::*;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
 
 pub static STR_NUMBER: &str = "number";
+pub static STR_EXPRESSION: &str = "expression";
+pub static STR_IDENTIFIER: &str = "identifier";
 pub static STR_RESERVE: &str = "reserve";
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+            Severity::Help => "help",
+        }
+    }
+}
+
+/// Stable identifiers for every diagnostic the compiler can emit.
+///
+/// The numeric codes are part of the output format and must never be
+/// reused or renumbered; add new variants at the end of their group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    // lexer: E00xx
+    UnknownCharacter,
+    // E0002 was `UnsupportedOperator`, retired once every C operator was
+    // lexed; the number stays reserved
+    UnterminatedComment,
+    UnterminatedLiteral,
+    InvalidEscape,
+    EmptyCharLiteral,
+    MultiCharLiteral,
+    UnknownEscape,
+    MixedStringPrefix,
+    InvalidIntLiteral,
+    IntLiteralOverflow,
+    UnsupportedFloat,
+    InvalidUcn,
+    // parser: E01xx
+    ExpectedToken,
+    WrongTokenType,
+    InvalidTypeSpec,
+    // resolver: E02xx
+    UndeclaredIdentifier,
+    ImplicitFnDecl,
+    InvalidArraySize,
+    TagMismatch,
+    Redefinition,
+    IncompleteType,
+    NotConstant,
+    TypeNameInExpr,
+    InvalidStorageClass,
+    MisplacedJump,
+    DuplicateCase,
+    InvalidInitializer,
+    ExcessInitializer,
+    MisplacedVaStart,
+    // codegen: E03xx
+    InvalidLValue,
+    Unsupported,
+    // typeck: E04xx
+    InvalidDeref,
+    InvalidOperands,
+    NotAssignable,
+    NoSuchMember,
+    NotARecord,
+    InvalidCast,
+    InvalidSizeof,
+    InvalidVaArg,
+    NotCallable,
+    ArgCountMismatch,
+    InvalidReturn,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::UnknownCharacter => "E0001",
+            // E0002 is reserved
+            ErrorCode::UnterminatedComment => "E0003",
+            ErrorCode::UnterminatedLiteral => "E0004",
+            ErrorCode::InvalidEscape => "E0005",
+            ErrorCode::EmptyCharLiteral => "E0006",
+            ErrorCode::MultiCharLiteral => "E0007",
+            ErrorCode::UnknownEscape => "E0008",
+            ErrorCode::MixedStringPrefix => "E0009",
+            ErrorCode::InvalidIntLiteral => "E0010",
+            ErrorCode::IntLiteralOverflow => "E0011",
+            ErrorCode::UnsupportedFloat => "E0012",
+            ErrorCode::InvalidUcn => "E0013",
+            ErrorCode::ExpectedToken => "E0100",
+            ErrorCode::WrongTokenType => "E0101",
+            ErrorCode::InvalidTypeSpec => "E0102",
+            ErrorCode::UndeclaredIdentifier => "E0200",
+            ErrorCode::ImplicitFnDecl => "E0201",
+            ErrorCode::InvalidArraySize => "E0202",
+            ErrorCode::TagMismatch => "E0203",
+            ErrorCode::Redefinition => "E0204",
+            ErrorCode::IncompleteType => "E0205",
+            ErrorCode::NotConstant => "E0206",
+            ErrorCode::TypeNameInExpr => "E0207",
+            ErrorCode::InvalidStorageClass => "E0208",
+            ErrorCode::MisplacedJump => "E0209",
+            ErrorCode::DuplicateCase => "E0210",
+            ErrorCode::InvalidInitializer => "E0211",
+            ErrorCode::ExcessInitializer => "E0212",
+            ErrorCode::MisplacedVaStart => "E0213",
+            ErrorCode::InvalidLValue => "E0300",
+            ErrorCode::Unsupported => "E0301",
+            ErrorCode::InvalidDeref => "E0400",
+            ErrorCode::InvalidOperands => "E0401",
+            ErrorCode::NotAssignable => "E0402",
+            ErrorCode::NoSuchMember => "E0403",
+            ErrorCode::NotARecord => "E0404",
+            ErrorCode::InvalidCast => "E0405",
+            ErrorCode::InvalidSizeof => "E0406",
+            ErrorCode::InvalidVaArg => "E0407",
+            ErrorCode::NotCallable => "E0408",
+            ErrorCode::ArgCountMismatch => "E0409",
+            ErrorCode::InvalidReturn => "E0410",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+    pub primary: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: Option<ErrorCode>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code: Some(code),
+            message: message.into(),
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+        }
+    }
+
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: ErrorCode, message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, code, message)
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: Some(message.into()).filter(|m: &String| !m.is_empty()),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: Some(message.into()).filter(|m: &String| !m.is_empty()),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help.push(help.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels.iter().find(|l| l.primary).map(|l| l.span)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ErrorFormat, String> {
+        match s {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!("unknown error format `{s}`, expected `human` or `json`")),
+        }
+    }
+}
+
+/// Renders diagnostics either as rustc-style source snippets or as one
+/// JSON object per line.
+pub struct DiagnosticEmitter<W: Write> {
+    writer: W,
+    format: ErrorFormat,
+    pub error_count: usize,
+    pub warning_count: usize,
+}
+
+impl DiagnosticEmitter<io::Stderr> {
+    pub fn stderr(format: ErrorFormat) -> DiagnosticEmitter<io::Stderr> {
+        DiagnosticEmitter::new(io::stderr(), format)
+    }
+}
+
+impl<W: Write> DiagnosticEmitter<W> {
+    pub fn new(writer: W, format: ErrorFormat) -> DiagnosticEmitter<W> {
+        DiagnosticEmitter {
+            writer,
+            format,
+            error_count: 0,
+            warning_count: 0,
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count > 0
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn emit_all(&mut self, diags: &[Diagnostic], source: &SourceFile) -> io::Result<()> {
+        for diag in diags {
+            self.emit(diag, source)?;
+        }
+        Ok(())
+    }
+
+    pub fn emit(&mut self, diag: &Diagnostic, source: &SourceFile) -> io::Result<()> {
+        match diag.severity {
+            Severity::Error => self.error_count += 1,
+            Severity::Warning => self.warning_count += 1,
+            _ => (),
+        }
+        match self.format {
+            ErrorFormat::Human => self.emit_human(diag, source),
+            ErrorFormat::Json => self.emit_json(diag, source),
+        }
+    }
+
+    fn emit_human(&mut self, diag: &Diagnostic, source: &SourceFile) -> io::Result<()> {
+        match diag.code {
+            Some(code) => writeln!(self.writer, "{}[{}]: {}", diag.severity.as_str(), code, diag.message)?,
+            None => writeln!(self.writer, "{}: {}", diag.severity.as_str(), diag.message)?,
+        }
+
+        // labels are rendered grouped by line, in source order
+        let mut labels: Vec<(usize, usize, &Label)> = diag
+            .labels
+            .iter()
+            .map(|label| {
+                let (line, col) = source.lookup_line_column(label.span.pos);
+                (line, col, label)
+            })
+            .collect();
+        labels.sort_by_key(|(line, col, _)| (*line, *col));
+
+        let gutter = labels
+            .last()
+            .map(|(line, _, _)| (line + 1).to_string().len())
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter);
+
+        if let Some(span) = diag.primary_span().or(labels.first().map(|l| l.2.span)) {
+            let (line, col) = source.lookup_line_column(span.pos);
+            writeln!(self.writer, "{pad}--> {}:{}:{}", source.name, line + 1, col + 1)?;
+        }
+
+        if !labels.is_empty() {
+            writeln!(self.writer, "{pad} |")?;
+        }
+        let mut idx = 0;
+        while idx < labels.len() {
+            let line = labels[idx].0;
+            let content = source.line_content(line).trim_end_matches(['\n', '\r']);
+            writeln!(self.writer, "{:>gutter$} | {}", line + 1, content)?;
+            while idx < labels.len() && labels[idx].0 == line {
+                let (_, col, label) = labels[idx];
+                // reuse tabs from the source line so the marker stays aligned
+                let indent: String = content
+                    .bytes()
+                    .take(col)
+                    .map(|b| if b == b'\t' { '\t' } else { ' ' })
+                    .collect();
+                let width = label.span.len.min(content.len().saturating_sub(col)).max(1);
+                let mark = if label.primary { "^" } else { "-" }.repeat(width);
+                match &label.message {
+                    Some(msg) => writeln!(self.writer, "{pad} | {indent}{mark} {msg}")?,
+                    None => writeln!(self.writer, "{pad} | {indent}{mark}")?,
+                }
+                idx += 1;
+            }
+        }
+        if !labels.is_empty() {
+            writeln!(self.writer, "{pad} |")?;
+        }
+
+        for note in &diag.notes {
+            writeln!(self.writer, "{pad} = note: {note}")?;
+        }
+        for help in &diag.help {
+            writeln!(self.writer, "{pad} = help: {help}")?;
+        }
+        writeln!(self.writer)?;
+        Ok(())
+    }
+
+    fn emit_json(&mut self, diag: &Diagnostic, source: &SourceFile) -> io::Result<()> {
+        let file = json_str(&source.name.to_string());
+        let code = diag
+            .code
+            .map(|c| json_str(c.as_str()))
+            .unwrap_or_else(|| "null".to_string());
+        let spans = diag
+            .labels
+            .iter()
+            .map(|label| {
+                let (line_start, column_start) = source.lookup_line_column(label.span.start());
+                let (line_end, column_end) = source.lookup_line_column(label.span.end());
+                let text = label
+                    .message
+                    .as_deref()
+                    .map(json_str)
+                    .unwrap_or_else(|| "null".to_string());
+                format!(
+                    "{{\"file_name\":{file},\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"line_end\":{},\"column_start\":{},\"column_end\":{},\"is_primary\":{},\"label\":{text}}}",
+                    label.span.start(),
+                    label.span.end(),
+                    line_start + 1,
+                    line_end + 1,
+                    column_start + 1,
+                    column_end + 1,
+                    label.primary,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let children = diag
+            .notes
+            .iter()
+            .map(|n| (Severity::Note, n))
+            .chain(diag.help.iter().map(|h| (Severity::Help, h)))
+            .map(|(severity, msg)| {
+                format!(
+                    "{{\"severity\":\"{}\",\"message\":{}}}",
+                    severity.as_str(),
+                    json_str(msg)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        writeln!(
+            self.writer,
+            "{{\"code\":{code},\"severity\":\"{}\",\"message\":{},\"file_name\":{file},\"spans\":[{spans}],\"children\":[{children}]}}",
+            diag.severity.as_str(),
+            json_str(&diag.message),
+        )
+    }
+
+    pub fn emit_summary(&mut self) -> io::Result<()> {
+        // JSON consumers get one object per diagnostic and nothing else
+        if self.format == ErrorFormat::Json {
+            return Ok(());
+        }
+        match self.error_count {
+            0 => Ok(()),
+            1 => writeln!(self.writer, "error: aborting due to 1 previous error"),
+            n => writeln!(self.writer, "error: aborting due to {n} previous errors"),
+        }
+    }
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
 #[derive(Debug, Clone)]
 pub enum NextTokenError {
     WrongType {
@@ -18,60 +451,37 @@ pub enum NextTokenError {
 }
 
 impl NextTokenError {
-    pub fn error_print(&self, source: &SourceFile) {
-        match self {
-            NextTokenError::WrongType { expected, found } => {
-                let (line, col) = source.lookup_line_column(found.span.pos);
-                // 打印行内容
-                println!(
-                    "Error: expected {}, found {:?} at line {}, column {}",
-                    expected, found, line, col
-                );
-                println!("{}", source.line_content(line));
-
-                // 打印箭头指示位置
-                let mut marker = String::new();
-                for _ in 0..found.span.pos {
-                    marker.push(' ');
-                }
-                for _ in 0..found.span.len {
-                    marker.push('^');
-                }
-                println!("{}", marker);
-            }
-            NextTokenError::ExpectedToken { expected, found } => {
-                let (line, col) = source.lookup_line_column(found.span.pos);
-                // 打印行内容
-                println!(
-                    "Error: expected {:?}, found {:?} at line {}, column {}",
-                    expected, found, line, col
-                );
-                println!("{}", source.line_content(line));
-
-                // 打印箭头指示位置
-                let mut marker = String::new();
-                for _ in 0..found.span.pos {
-                    marker.push(' ');
-                }
-                for _ in 0..found.span.len {
-                    marker.push('^');
-                }
-                println!("{}", marker);
-            }
+    pub fn span(&self) -> Span {
+        match &self {
+            NextTokenError::WrongType { found, .. } => found.span,
+            NextTokenError::ExpectedToken { found, .. } => found.span,
         }
     }
 
     pub fn gen_error_expr(&self, id: &mut usize) -> Expr {
-        let span = match &self {
-            NextTokenError::WrongType { found, .. } => found.span,
-            NextTokenError::ExpectedToken { found, .. } => found.span,
-        };
         let result = Expr {
             id: *id,
             kind: ExprKind::Error,
-            span,
+            span: self.span(),
         };
         *id += 1;
         result
     }
 }
+
+impl From<NextTokenError> for Diagnostic {
+    fn from(e: NextTokenError) -> Diagnostic {
+        match e {
+            NextTokenError::WrongType { expected, found } => Diagnostic::error(
+                ErrorCode::WrongTokenType,
+                format!("expected {}, found {}", expected, found.kind),
+            )
+            .with_primary(found.span, format!("expected {expected}")),
+            NextTokenError::ExpectedToken { expected, found } => Diagnostic::error(
+                ErrorCode::ExpectedToken,
+                format!("expected {}, found {}", expected, found.kind),
+            )
+            .with_primary(found.span, format!("expected {expected}")),
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use crate::resolver::ResolvedCrate;
 use crate::resolver::*;
+use crate::ty::{Ty, TyCtx};
 use std::collections::HashMap;
 
 pub struct FrameLayout {
     pub slots: HashMap<ObjId, usize>,
+    /// Where a variadic function saves its argument registers, if the
+    /// target needs room for them in the frame.
+    pub va_area: Option<usize>,
     pub frame_size: usize,
 }
 
@@ -23,25 +27,34 @@ impl FrameBuilder {
         let fns = crat
             .fn_info
             .values()
-            .map(|func| (func.fn_id, self.build_fn(func)))
+            .map(|func| (func.fn_id, self.build_fn(func, &crat.objs, &crat.tcx)))
             .collect();
         FrameLayouts { fns }
     }
 
-    fn build_fn(&self, func: &FnInfo) -> FrameLayout {
+    fn build_fn(&self, func: &FnInfo, objs: &[Obj], tcx: &TyCtx) -> FrameLayout {
         let mut offset = 0;
         let layout = func
             .params
             .iter()
             .chain(func.locals.iter())
             .map(|vid| {
-                offset += 8;
+                // slots grow downwards; an object starts at its lowest address
+                let ty = &objs[*vid].ty;
+                offset = (offset + tcx.size_of(ty)).next_multiple_of(tcx.align_of(ty));
                 (*vid, offset)
             })
             .collect();
+        let variadic = matches!(&objs[func.fn_id].ty, Ty::Func(f) if f.variadic);
+        let va_area = (variadic && tcx.va_save_area > 0).then(|| {
+            offset = (offset + tcx.va_save_area).next_multiple_of(16);
+            offset
+        });
         FrameLayout {
             slots: layout,
-            frame_size: offset,
+            va_area,
+            // keep rsp 16-byte aligned for calls made from this frame
+            frame_size: offset.next_multiple_of(16),
         }
     }
 }
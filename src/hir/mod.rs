@@ -1,9 +1,9 @@
 use super::ast::{UnaryOpKind, BinaryOpKind};
 use super::span::Span;
 
-pub struct SymbolId(usize);
+pub struct SymbolId(pub usize);
 
-pub struct HirId(usize);
+pub struct HirId(pub usize);
 
 pub struct Crate{
 
@@ -18,9 +18,9 @@ pub enum StmtKind{
 }
 
 pub struct Expr<'hir>{
-    span: Span,
-    kind: ExprKind<'hir>,
-    id: HirId,
+    pub span: Span,
+    pub kind: ExprKind<'hir>,
+    pub id: HirId,
 }
 
 pub enum ExprKind<'hir>{
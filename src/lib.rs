@@ -16,4 +16,10 @@ pub mod frame_layout;
 
 pub mod hir;
 
-pub mod ast_hir_lowering;
\ No newline at end of file
+pub mod ast_hir_lowering;
+
+pub mod ty;
+
+pub mod typeck;
+
+pub mod consteval;
@@ -1,12 +1,15 @@
 use std::fs;
-use std::io::Read;
+use std::io::{Read, Write};
 
-use chibicc_for_rust::codegen::abi::{win64::*};
+use chibicc_for_rust::codegen::abi::{TargetAbi, sysv::*, win64::*};
 use chibicc_for_rust::codegen::*;
+use chibicc_for_rust::error_handler::{DiagnosticEmitter, ErrorFormat};
 use chibicc_for_rust::frame_layout::*;
 use chibicc_for_rust::parser::*;
 use chibicc_for_rust::resolver::*;
 use chibicc_for_rust::span::*;
+use chibicc_for_rust::ty::TyCtx;
+use chibicc_for_rust::typeck::TypeChecker;
 use chibicc_for_rust::{span::source_map::SourceFile, tokenizer::*};
 use clap::Parser as ClapParser;
 
@@ -14,6 +17,12 @@ use clap::Parser as ClapParser;
 struct Cli {
     #[arg(value_name = "INPUT")]
     input: std::path::PathBuf,
+    /// How to render diagnostics: `human` or `json` (one object per line)
+    #[arg(long, value_name = "FORMAT", default_value = "human")]
+    error_format: ErrorFormat,
+    /// Calling convention of the generated code: `sysv` or `win64`
+    #[arg(long, value_name = "ABI", default_value_t = TargetAbi::host())]
+    abi: TargetAbi,
 }
 
 fn main() {
@@ -38,28 +47,69 @@ fn main() {
         }
     }
     let source_file = SourceFile::new(source_map::FileName::Real(arg.input), code);
-    compile(source_file);
+    let mut emitter = DiagnosticEmitter::stderr(arg.error_format);
+    let asm = compile(&source_file, arg.abi, &mut emitter);
+    let _ = emitter.emit_summary();
+    match asm {
+        Some(asm) if !emitter.has_errors() => {
+            if let Err(err) = std::io::stdout().write_all(&asm) {
+                eprintln!("error: couldn't write assembly: {err}");
+                std::process::exit(1);
+            }
+        }
+        _ => std::process::exit(1),
+    }
 }
 
-fn compile(file: SourceFile) {
-    let tokens = tokenize(file.src.as_bytes());
+fn compile<W: Write>(
+    file: &SourceFile,
+    abi: TargetAbi,
+    emitter: &mut DiagnosticEmitter<W>,
+) -> Option<Vec<u8>> {
+    let (tokens, lex_errors) = tokenize(file.src.as_bytes());
+    let _ = emitter.emit_all(&lex_errors, file);
+    if emitter.has_errors() {
+        return None;
+    }
 
-    let mut parser = Parser {
-        tokens,
-        index: 0,
-        errors: vec![],
-        node_cnt: 0,
-    };
+    let mut parser = Parser::new(tokens);
 
     let ast = parser.parse_crate();
-    if parser.errors.is_empty() {
-        let mut resolver = Resolver::new();
-        resolver.resolve(&ast);
-        let frame_builder = FrameBuilder::new();
-        let layouts = frame_builder.build(&resolver.resolved);
-        let _ = gen_asm::<Win64Abi>(ast, resolver.resolved, layouts);
+    let _ = emitter.emit_all(&parser.errors, file);
+    if emitter.has_errors() {
+        return None;
     }
-    for e in parser.errors {
-        e.error_print(&file);
+
+    let mut resolver = Resolver::new(TyCtx::new(abi));
+    resolver.resolve(&ast);
+    let _ = emitter.emit_all(&resolver.errors, file);
+    if emitter.has_errors() {
+        return None;
+    }
+
+    let mut typeck = TypeChecker::new(&resolver.resolved);
+    typeck.check(&ast);
+    let _ = emitter.emit_all(&typeck.errors, file);
+    if emitter.has_errors() {
+        return None;
+    }
+    let tys = typeck.results;
+
+    let frame_builder = FrameBuilder::new();
+    let layouts = frame_builder.build(&resolver.resolved);
+    let mut asm = vec![];
+    let generated = match abi {
+        TargetAbi::SysV => gen_asm::<SysVAbi, _>(&mut asm, ast, resolver.resolved, tys, layouts),
+        TargetAbi::Win64 => gen_asm::<Win64Abi, _>(&mut asm, ast, resolver.resolved, tys, layouts),
+    };
+    match generated {
+        Ok(errors) => {
+            let _ = emitter.emit_all(&errors, file);
+        }
+        Err(err) => {
+            eprintln!("error: couldn't generate assembly: {err}");
+            return None;
+        }
     }
+    Some(asm)
 }
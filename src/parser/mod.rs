@@ -3,23 +3,97 @@
 use crate::ast::*;
 use crate::error_handler::*;
 use crate::span::*;
-use core::panic;
+use crate::ty::IntKind;
+use std::collections::HashMap;
 
 pub struct Parser {
     pub tokens: TokenContainer,
     pub index: usize,
-    pub errors: Vec<NextTokenError>,
+    pub errors: Vec<Diagnostic>,
     pub node_cnt: usize,
+    /// Ordinary identifiers declared in each open scope, innermost last,
+    /// mapped to whether they name a typedef. Whether `T * x;` declares
+    /// `x` or multiplies depends on this (the "lexer hack").
+    pub scopes: Vec<HashMap<Symbol, bool>>,
 }
 
-fn is_typename(tk: &TokenKind)->bool{
-    match tk{
-        TokenKind::Keyword(kwk) if kwk == &KeywordKind::Int => true,
-        _ => false,
+// Each type specifier keyword adds its weight to a counter, so the sum
+// tells which combination has been written, in any order (as in chibicc).
+const VOID: u32 = 1 << 0;
+const BOOL: u32 = 1 << 2;
+const CHAR: u32 = 1 << 4;
+const SHORT: u32 = 1 << 6;
+const INT: u32 = 1 << 8;
+const LONG: u32 = 1 << 10;
+const SIGNED: u32 = 1 << 13;
+const UNSIGNED: u32 = 1 << 15;
+// struct, union and enum specifiers combine with nothing
+const OTHER: u32 = 1 << 17;
+
+fn type_spec_weight(kw: KeywordKind) -> Option<u32> {
+    match kw {
+        KeywordKind::Void => Some(VOID),
+        KeywordKind::Bool => Some(BOOL),
+        KeywordKind::Char => Some(CHAR),
+        KeywordKind::Short => Some(SHORT),
+        KeywordKind::Int => Some(INT),
+        KeywordKind::Long => Some(LONG),
+        KeywordKind::Signed => Some(SIGNED),
+        KeywordKind::Unsigned => Some(UNSIGNED),
+        KeywordKind::Struct | KeywordKind::Union | KeywordKind::Enum => Some(OTHER),
+        _ => None,
     }
 }
 
+fn type_spec_for(counter: u32) -> Option<TypeSpec> {
+    use IntKind::*;
+    let kind = match counter {
+        VOID => return Some(TypeSpec::Void),
+        BOOL => Bool,
+        CHAR => Char,
+        c if c == SIGNED + CHAR => SChar,
+        c if c == UNSIGNED + CHAR => UChar,
+        c if c == SHORT || c == SHORT + INT || c == SIGNED + SHORT || c == SIGNED + SHORT + INT => Short,
+        c if c == UNSIGNED + SHORT || c == UNSIGNED + SHORT + INT => UShort,
+        c if c == INT || c == SIGNED || c == SIGNED + INT => Int,
+        c if c == UNSIGNED || c == UNSIGNED + INT => UInt,
+        c if c == LONG || c == LONG + INT || c == SIGNED + LONG || c == SIGNED + LONG + INT => Long,
+        c if c == UNSIGNED + LONG || c == UNSIGNED + LONG + INT => ULong,
+        c if c == 2 * LONG || c == 2 * LONG + INT || c == SIGNED + 2 * LONG || c == SIGNED + 2 * LONG + INT => LongLong,
+        c if c == UNSIGNED + 2 * LONG || c == UNSIGNED + 2 * LONG + INT => ULongLong,
+        _ => return None,
+    };
+    Some(TypeSpec::Int(kind))
+}
+
+/// Whether a declarator has to declare a name: declarations need one,
+/// parameters may go without, and type names must not have one.
+#[derive(Clone, Copy, PartialEq)]
+enum DeclaratorKind {
+    Named,
+    MaybeAbstract,
+    Abstract,
+}
+
+fn is_type_qual(tk: &TokenKind) -> bool {
+    matches!(
+        tk,
+        TokenKind::Keyword(KeywordKind::Const | KeywordKind::Volatile | KeywordKind::Restrict | KeywordKind::Atomic)
+    )
+}
+
 impl Parser {
+    pub fn new(tokens: TokenContainer) -> Parser {
+        Parser {
+            tokens,
+            index: 0,
+            errors: vec![],
+            node_cnt: 0,
+            // `va_list` is predeclared, as if `<stdarg.h>` were included
+            scopes: vec![HashMap::from([(Symbol::intern("va_list"), true)])],
+        }
+    }
+
     fn peek(&self) -> &Token {
         &self.tokens[self.index]
     }
@@ -31,22 +105,102 @@ impl Parser {
         &self.peek().kind == kind
     }
 
+    pub fn is_eof(&self) -> bool {
+        self.index >= self.tokens.len() || self.peek().kind == TokenKind::Eof
+    }
+
     pub fn expect_and_eat(&mut self, kind: &TokenKind) -> bool {
         let same = self.expect(kind);
         if same {
             self.bump();
         } else {
             let e = NextTokenError::ExpectedToken {
-                expected: kind.clone(),
-                found: self.peek().clone(),
+                expected: *kind,
+                found: *self.peek(),
             };
-            self.errors.push(e);
+            self.report(e.into());
         }
         same
     }
 
+    /// Records a parse error. Only the first error at a token is kept: once
+    /// something went wrong there, the follow-up complaints are noise.
+    pub fn report(&mut self, e: Diagnostic) {
+        let last = self.errors.last().and_then(|last| last.primary_span());
+        if last.is_none() || last != e.primary_span() {
+            self.errors.push(e);
+        }
+    }
+
+    /// Expects a closing `kind`. If something else is found, reports it and
+    /// skips to the closing token, stopping early at a `;` or a brace so a
+    /// missing `)` does not swallow the statement body.
+    pub fn expect_closing(&mut self, kind: &TokenKind) -> bool {
+        if self.expect_and_eat(kind) {
+            return true;
+        }
+        let mut depth = 0usize;
+        while !self.is_eof() {
+            match self.peek().kind {
+                TokenKind::Semi | TokenKind::LBrace | TokenKind::RBrace => break,
+                k if k == *kind && depth == 0 => {
+                    self.bump();
+                    break;
+                }
+                TokenKind::LParen | TokenKind::LBracket => depth += 1,
+                TokenKind::RParen | TokenKind::RBracket => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            self.bump();
+        }
+        false
+    }
+
+    /// Skips to the end of the current statement: just past the next `;`, or
+    /// up to the `}` closing the enclosing block, skipping balanced brackets.
+    pub fn recover_stmt(&mut self) {
+        let mut depth = 0usize;
+        while !self.is_eof() {
+            match self.peek().kind {
+                TokenKind::Semi if depth == 0 => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::RBrace if depth == 0 => return,
+                TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => depth += 1,
+                TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            self.bump();
+        }
+    }
+
+    /// Skips to the start of the next top-level declaration: past a `;` at
+    /// file level or past the `}` that closes a function body.
+    pub fn recover_item(&mut self) {
+        let mut depth = 0usize;
+        while !self.is_eof() {
+            match self.peek().kind {
+                TokenKind::Semi if depth == 0 => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace if depth <= 1 => {
+                    self.bump();
+                    return;
+                }
+                TokenKind::RBrace => depth -= 1,
+                _ => (),
+            }
+            self.bump();
+        }
+    }
+
     pub fn bump(&mut self) {
-        self.index += 1
+        if self.index + 1 < self.tokens.len() {
+            self.index += 1
+        }
     }
 
     pub fn eat(&mut self, kind: &TokenKind) -> bool {
@@ -56,6 +210,51 @@ impl Parser {
         }
         same
     }
+
+    /// Whether `sym` currently names a typedef, i.e. the innermost
+    /// declaration of it is one.
+    pub fn is_typedef_name(&self, sym: Symbol) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&sym))
+            .is_some_and(|is_typedef| *is_typedef)
+    }
+
+    /// Whether the next token starts declaration specifiers.
+    pub fn is_typename(&self) -> bool {
+        self.starts_decl_spec(&self.peek().kind)
+    }
+
+    fn starts_decl_spec(&self, kind: &TokenKind) -> bool {
+        match *kind {
+            TokenKind::Keyword(
+                KeywordKind::Typedef
+                | KeywordKind::Static
+                | KeywordKind::Extern
+                | KeywordKind::Register
+                | KeywordKind::Auto
+                | KeywordKind::Inline
+                | KeywordKind::ThreadLocal,
+            ) => true,
+            TokenKind::Keyword(kw) => type_spec_weight(kw).is_some() || is_type_qual(kind),
+            TokenKind::Ident(sym) => self.is_typedef_name(sym),
+            _ => false,
+        }
+    }
+
+    /// Whether a parenthesized type name, as in a cast or `sizeof(int)`,
+    /// comes next.
+    fn is_paren_type_name(&self) -> bool {
+        self.expect(&TokenKind::LParen)
+            && self.tokens.get(self.index + 1).is_some_and(|tk| self.starts_decl_spec(&tk.kind))
+    }
+
+    /// Records that `sym` is declared in the current scope, as a typedef
+    /// name or as anything else.
+    pub fn declare_name(&mut self, sym: Symbol, is_typedef: bool) {
+        self.scopes.last_mut().expect("file scope is always open").insert(sym, is_typedef);
+    }
 }
 
 impl Parser {
@@ -78,26 +277,27 @@ impl Parser {
         stmt
     }
 
-    fn next_declarator(&mut self, ptr: Option<Box<PointerDecl>>, direct: DirectDeclarator) -> Declarator{
+    fn next_declarator(&mut self, ptr: Option<Box<PointerDecl>>, direct: DirectDeclarator, span: Span) -> Declarator{
         let decl = Declarator {
             id: self.node_cnt,
             ptr,
             direct,
+            span,
         };
         self.node_cnt += 1;
         decl
     }
 
-    fn parse_lit_num(&mut self) -> Result<(Span, Lit), NextTokenError> {
+    fn parse_lit(&mut self) -> Result<(Span, Lit), NextTokenError> {
         let (span, lit) = match &self.peek().kind {
-            TokenKind::Literal(i) if i.kind == LitKind::Integer => (self.peek().span, i.clone()),
+            TokenKind::Literal(i) => (self.peek().span, *i),
             _ => {
-                let e: Result<(Span, Lit), NextTokenError> = Err(NextTokenError::WrongType {
-                    expected: STR_NUMBER,
-                    found: self.peek().clone(),
+                // leave the token alone: it is likely a `;` or `)` the
+                // enclosing construct is waiting for
+                return Err(NextTokenError::WrongType {
+                    expected: STR_EXPRESSION,
+                    found: *self.peek(),
                 });
-                self.bump();
-                return e;
             }
         };
 
@@ -108,7 +308,7 @@ impl Parser {
     fn parse_ident(&mut self) -> Option<(Symbol, Span)> {
         let span = self.peek().span;
         let result = if let TokenKind::Ident(s) = &self.peek().kind {
-            Some((s.clone(), span))
+            Some((*s, span))
         } else {
             None
         };
@@ -124,13 +324,13 @@ impl Parser {
             return Some(result);
         }
         loop {
-            let expr = self.parse_expr();
+            let expr = self.parse_assign();
             result.push(expr);
             if !self.eat(&TokenKind::Comma) {
                 break;
             }
         }
-        if self.eat(&TokenKind::RParen) {
+        if self.expect_closing(&TokenKind::RParen) {
             Some(result)
         } else {
             None
@@ -140,38 +340,63 @@ impl Parser {
     fn parse_primary(&mut self) -> Expr {
         if self.eat(&TokenKind::LParen) {
             let mut result = self.parse_expr();
-            if !self.expect_and_eat(&TokenKind::RParen) {
+            if !self.expect_closing(&TokenKind::RParen) {
                 result.kind = ExprKind::Error;
             }
             return result;
         }
         if let Some((sym, span)) = self.parse_ident() {
-            return if self.eat(&TokenKind::LParen) {
-                let params = self.parse_params();
-                let kind = if let Some(params) = params {
-                    ExprKind::FnCall(sym, params)
-                } else {
-                    ExprKind::Error
-                };
-                self.next_expr(kind, span)
-            } else {
-                self.next_expr(ExprKind::Var(sym), span)
-            };
+            if self.expect(&TokenKind::LParen)
+                && let Some(kind) = self.parse_va_builtin(sym)
+            {
+                return self.next_expr(kind, span);
+            }
+            return self.next_expr(ExprKind::Var(sym), span);
+        }
+        if self.expect(&TokenKind::Error) {
+            // already reported by the lexer
+            let span = self.peek().span;
+            self.bump();
+            return self.next_expr(ExprKind::Error, span);
         }
-        let ex = self.parse_lit_num();
+        let ex = self.parse_lit();
         match ex {
-            Ok((span, kind)) => {
-                let kind = kind.clone();
-                self.next_expr(ExprKind::Literal(kind), span)
-            }
+            Ok((span, kind)) => self.next_expr(ExprKind::Literal(kind), span),
             Err(e) => {
                 let error_node = e.gen_error_expr(&mut self.node_cnt);
-                self.errors.push(e);
+                self.report(e.into());
                 error_node
             }
         }
     }
 
+    /// Parses the arguments of the `<stdarg.h>` macros, which are built in
+    /// since there is no preprocessor. Like the macros, their names are
+    /// only special before a `(`. Returns `None` for any other name.
+    fn parse_va_builtin(&mut self, sym: Symbol) -> Option<ExprKind> {
+        let name = sym.as_str();
+        if !matches!(name, "va_start" | "va_arg" | "va_end" | "va_copy") {
+            return None;
+        }
+        self.bump();
+        let ap = Box::new(self.parse_assign());
+        let kind = match name {
+            "va_end" => ExprKind::VaEnd(ap),
+            _ => {
+                self.expect_and_eat(&TokenKind::Comma);
+                match name {
+                    "va_arg" => ExprKind::VaArg(ap, Box::new(self.parse_type_name())),
+                    "va_start" => ExprKind::VaStart(ap, Box::new(self.parse_assign())),
+                    _ => ExprKind::VaCopy(ap, Box::new(self.parse_assign())),
+                }
+            }
+        };
+        if !self.expect_closing(&TokenKind::RParen) {
+            return Some(ExprKind::Error);
+        }
+        Some(kind)
+    }
+
     fn parse_unary(&mut self) -> Expr {
         let span = self.peek().span;
         let op = match true {
@@ -179,94 +404,257 @@ impl Parser {
             _ if self.eat(&TokenKind::Sub) => Some(UnaryOpKind::Neg),
             _ if self.eat(&TokenKind::And) => Some(UnaryOpKind::AddrOf),
             _ if self.eat(&TokenKind::Mul) => Some(UnaryOpKind::Deref),
+            _ if self.eat(&TokenKind::Bang) => Some(UnaryOpKind::Not),
+            _ if self.eat(&TokenKind::Tilde) => Some(UnaryOpKind::BitNot),
+            _ if self.eat(&TokenKind::Inc) => Some(UnaryOpKind::PreInc),
+            _ if self.eat(&TokenKind::Dec) => Some(UnaryOpKind::PreDec),
             _ => None,
         };
         if let Some(op) = op {
-            let inner = self.parse_unary();
-            self.next_expr(ExprKind::Unary(op, Box::new(inner)), span)
-        } else {
-            self.parse_primary()
+            let inner = self.parse_cast();
+            return self.next_expr(ExprKind::Unary(op, Box::new(inner)), span);
+        }
+        if self.eat(&TokenKind::Keyword(KeywordKind::Sizeof)) {
+            let kind = if self.is_paren_type_name() {
+                self.bump();
+                let ty = self.parse_type_name();
+                self.expect_closing(&TokenKind::RParen);
+                ExprKind::SizeofType(Box::new(ty))
+            } else {
+                ExprKind::SizeofExpr(Box::new(self.parse_unary()))
+            };
+            return self.next_expr(kind, span);
+        }
+        if self.eat(&TokenKind::Keyword(KeywordKind::Alignof)) {
+            self.expect_and_eat(&TokenKind::LParen);
+            let ty = self.parse_type_name();
+            self.expect_closing(&TokenKind::RParen);
+            return self.next_expr(ExprKind::Alignof(Box::new(ty)), span);
         }
+        self.parse_postfix()
     }
 
-    fn parse_mul(&mut self) -> Expr {
-        let mut node = self.parse_unary();
-        while self.index < self.tokens.len() {
-            let span = self.peek().span;
-            let op = match true {
-                _ if self.eat(&TokenKind::Mul) => BinaryOpKind::Mul,
-                _ if self.eat(&TokenKind::Div) => BinaryOpKind::Div,
-                _ => break,
-            };
-            let unary_exp = self.parse_unary();
-            node = self.next_expr(
-                ExprKind::Binary(op, Box::new(node), Box::new(unary_exp)),
-                span,
+    /// Parses a cast expression, `(type) operand`, or else a unary one.
+    fn parse_cast(&mut self) -> Expr {
+        if !self.is_paren_type_name() {
+            return self.parse_unary();
+        }
+        let span = self.peek().span;
+        self.bump();
+        let ty = self.parse_type_name();
+        self.expect_closing(&TokenKind::RParen);
+        let operand = self.parse_cast();
+        self.next_expr(ExprKind::Cast(Box::new(ty), Box::new(operand)), span)
+    }
+
+    /// Parses a type name: specifiers without a storage class, then an
+    /// abstract declarator.
+    fn parse_type_name(&mut self) -> TypeName {
+        let span = self.peek().span;
+        let spec = self.parse_decl_spec();
+        if spec.is_typedef || spec.storage.is_some() || spec.is_inline || spec.is_thread_local {
+            self.report(
+                Diagnostic::error(ErrorCode::InvalidTypeSpec, "storage class specifier is not allowed in a type name")
+                    .with_primary(span, ""),
             );
         }
-        node
+        let declarator = self.parse_declarator_of(DeclaratorKind::Abstract).expect("abstract declarators always parse");
+        TypeName { spec, declarator }
     }
 
-    pub fn parse_add(&mut self) -> Expr {
-        let mut node = self.parse_mul();
-        while self.index < self.tokens.len() {
+    fn parse_postfix(&mut self) -> Expr {
+        let mut node = self.parse_primary();
+        loop {
             let span = self.peek().span;
+            if self.eat(&TokenKind::LBracket) {
+                // `a[i]` is `*(a + i)`
+                let index = self.parse_expr();
+                self.expect_closing(&TokenKind::RBracket);
+                let sum = self.next_expr(ExprKind::Binary(BinaryOpKind::Add, Box::new(node), Box::new(index)), span);
+                node = self.next_expr(ExprKind::Unary(UnaryOpKind::Deref, Box::new(sum)), span);
+                continue;
+            }
+            if self.eat(&TokenKind::LParen) {
+                // a call is reported at its callee
+                let span = node.span;
+                let kind = match self.parse_params() {
+                    Some(args) => ExprKind::FnCall(Box::new(node), args),
+                    None => ExprKind::Error,
+                };
+                node = self.next_expr(kind, span);
+                continue;
+            }
+            if self.eat(&TokenKind::Dot) {
+                let Some((member, _)) = self.eat_member_name() else { break };
+                node = self.next_expr(ExprKind::Member(Box::new(node), member), span);
+                continue;
+            }
+            if self.eat(&TokenKind::Arrow) {
+                // `p->m` is `(*p).m`
+                let Some((member, _)) = self.eat_member_name() else { break };
+                let deref = self.next_expr(ExprKind::Unary(UnaryOpKind::Deref, Box::new(node)), span);
+                node = self.next_expr(ExprKind::Member(Box::new(deref), member), span);
+                continue;
+            }
             let op = match true {
-                _ if self.eat(&TokenKind::Add) => BinaryOpKind::Add,
-                _ if self.eat(&TokenKind::Sub) => BinaryOpKind::Sub,
+                _ if self.eat(&TokenKind::Inc) => UnaryOpKind::PostInc,
+                _ if self.eat(&TokenKind::Dec) => UnaryOpKind::PostDec,
                 _ => break,
             };
-            let mul_expr = self.parse_mul();
-            node = self.next_expr(
-                ExprKind::Binary(op, Box::new(node), Box::new(mul_expr)),
-                span,
-            );
+            node = self.next_expr(ExprKind::Unary(op, Box::new(node)), span);
         }
         node
     }
 
-    pub fn parse_rational(&mut self) -> Expr {
-        let mut node = self.parse_add();
-        while self.index < self.tokens.len() {
-            let span = self.peek().span;
-            let op = match true {
-                _ if self.eat(&TokenKind::Ge) => BinaryOpKind::Ge,
-                _ if self.eat(&TokenKind::Gt) => BinaryOpKind::Gt,
-                _ if self.eat(&TokenKind::Le) => BinaryOpKind::Le,
-                _ if self.eat(&TokenKind::Lt) => BinaryOpKind::Lt,
-                _ => break,
+    fn eat_member_name(&mut self) -> Option<(Symbol, Span)> {
+        let member = self.eat_ident();
+        if member.is_none() {
+            let e = NextTokenError::WrongType {
+                expected: STR_IDENTIFIER,
+                found: *self.peek(),
             };
-            let add_expr = self.parse_add();
-            node = self.next_expr(
-                ExprKind::Binary(op, Box::new(node), Box::new(add_expr)),
-                span,
-            );
+            self.report(e.into());
         }
-        node
+        member
     }
 
-    pub fn parse_equality(&mut self) -> Expr {
-        let mut node = self.parse_rational();
-        while self.index < self.tokens.len() {
+    /// Parses one left-associative binary precedence level: `next (op next)*`.
+    fn parse_binary_level(
+        &mut self,
+        ops: &[(TokenKind, BinaryOpKind)],
+        next: fn(&mut Parser) -> Expr,
+    ) -> Expr {
+        let mut node = next(self);
+        'outer: loop {
             let span = self.peek().span;
-            let op = match true {
-                _ if self.eat(&TokenKind::EqEq) => BinaryOpKind::EqEq,
-                _ if self.eat(&TokenKind::Ne) => BinaryOpKind::Ne,
-                _ => break,
-            };
-            let rational_expr = self.parse_rational();
-            node = self.next_expr(
-                ExprKind::Binary(op, Box::new(node), Box::new(rational_expr)),
-                span,
-            );
+            for (tk, op) in ops {
+                if self.eat(tk) {
+                    let rhs = next(self);
+                    node = self.next_expr(
+                        ExprKind::Binary(*op, Box::new(node), Box::new(rhs)),
+                        span,
+                    );
+                    continue 'outer;
+                }
+            }
+            break;
         }
         node
     }
 
+    fn parse_mul(&mut self) -> Expr {
+        self.parse_binary_level(
+            &[
+                (TokenKind::Mul, BinaryOpKind::Mul),
+                (TokenKind::Div, BinaryOpKind::Div),
+                (TokenKind::Mod, BinaryOpKind::Mod),
+            ],
+            Parser::parse_cast,
+        )
+    }
+
+    pub fn parse_add(&mut self) -> Expr {
+        self.parse_binary_level(
+            &[
+                (TokenKind::Add, BinaryOpKind::Add),
+                (TokenKind::Sub, BinaryOpKind::Sub),
+            ],
+            Parser::parse_mul,
+        )
+    }
+
+    pub fn parse_shift(&mut self) -> Expr {
+        self.parse_binary_level(
+            &[
+                (TokenKind::Shl, BinaryOpKind::Shl),
+                (TokenKind::Shr, BinaryOpKind::Shr),
+            ],
+            Parser::parse_add,
+        )
+    }
+
+    pub fn parse_rational(&mut self) -> Expr {
+        self.parse_binary_level(
+            &[
+                (TokenKind::Ge, BinaryOpKind::Ge),
+                (TokenKind::Gt, BinaryOpKind::Gt),
+                (TokenKind::Le, BinaryOpKind::Le),
+                (TokenKind::Lt, BinaryOpKind::Lt),
+            ],
+            Parser::parse_shift,
+        )
+    }
+
+    pub fn parse_equality(&mut self) -> Expr {
+        self.parse_binary_level(
+            &[
+                (TokenKind::EqEq, BinaryOpKind::EqEq),
+                (TokenKind::Ne, BinaryOpKind::Ne),
+            ],
+            Parser::parse_rational,
+        )
+    }
+
+    pub fn parse_bitand(&mut self) -> Expr {
+        self.parse_binary_level(&[(TokenKind::And, BinaryOpKind::BitAnd)], Parser::parse_equality)
+    }
+
+    pub fn parse_bitxor(&mut self) -> Expr {
+        self.parse_binary_level(&[(TokenKind::Caret, BinaryOpKind::BitXor)], Parser::parse_bitand)
+    }
+
+    pub fn parse_bitor(&mut self) -> Expr {
+        self.parse_binary_level(&[(TokenKind::Or, BinaryOpKind::BitOr)], Parser::parse_bitxor)
+    }
+
+    pub fn parse_logand(&mut self) -> Expr {
+        self.parse_binary_level(&[(TokenKind::AndAnd, BinaryOpKind::LogAnd)], Parser::parse_bitor)
+    }
+
+    pub fn parse_logor(&mut self) -> Expr {
+        self.parse_binary_level(&[(TokenKind::OrOr, BinaryOpKind::LogOr)], Parser::parse_logand)
+    }
+
+    pub fn parse_conditional(&mut self) -> Expr {
+        let cond = self.parse_logor();
+        let span = self.peek().span;
+        if !self.eat(&TokenKind::Question) {
+            return cond;
+        }
+        let then = self.parse_expr();
+        self.expect_and_eat(&TokenKind::Colon);
+        let els = self.parse_conditional();
+        self.next_expr(
+            ExprKind::Cond(Box::new(cond), Box::new(then), Box::new(els)),
+            span,
+        )
+    }
+
     pub fn parse_assign(&mut self) -> Expr {
-        let mut node = self.parse_equality();
+        let mut node = self.parse_conditional();
 
-        if self.eat(&TokenKind::Eq) {
+        let compound = match self.peek().kind {
+            TokenKind::AddEq => Some(BinaryOpKind::Add),
+            TokenKind::SubEq => Some(BinaryOpKind::Sub),
+            TokenKind::MulEq => Some(BinaryOpKind::Mul),
+            TokenKind::DivEq => Some(BinaryOpKind::Div),
+            TokenKind::ModEq => Some(BinaryOpKind::Mod),
+            TokenKind::AndEq => Some(BinaryOpKind::BitAnd),
+            TokenKind::OrEq => Some(BinaryOpKind::BitOr),
+            TokenKind::CaretEq => Some(BinaryOpKind::BitXor),
+            TokenKind::ShlEq => Some(BinaryOpKind::Shl),
+            TokenKind::ShrEq => Some(BinaryOpKind::Shr),
+            _ => None,
+        };
+        if let Some(op) = compound {
+            let span = self.peek().span;
+            self.bump();
+            let assign_expr = self.parse_assign();
+            node = self.next_expr(
+                ExprKind::AssignOp(op, Box::new(node), Box::new(assign_expr)),
+                span,
+            );
+        } else if self.eat(&TokenKind::Eq) {
             let span = self.peek().span;
             let assign_expr = self.parse_assign();
             node = self.next_expr(
@@ -278,7 +666,7 @@ impl Parser {
     }
 
     pub fn parse_expr(&mut self) -> Expr {
-        self.parse_assign()
+        self.parse_binary_level(&[(TokenKind::Comma, BinaryOpKind::Comma)], Parser::parse_assign)
     }
 
     pub fn parse_exprstmt(&mut self) -> Option<Box<Expr>> {
@@ -287,7 +675,9 @@ impl Parser {
         }
         let result = self.parse_expr();
         let result = if !self.expect_and_eat(&TokenKind::Semi) {
-            self.next_expr(ExprKind::Error, self.peek().span)
+            let span = self.peek().span;
+            self.recover_stmt();
+            self.next_expr(ExprKind::Error, span)
         } else {
             result
         };
@@ -298,51 +688,437 @@ impl Parser {
         let spec = self.parse_decl_spec();
         let mut decls = vec![];
         if !self.expect(&TokenKind::Semi){
-            decls.push(self.parse_var_decl());
-            while self.eat(&TokenKind::Comma){
-                decls.push(self.parse_var_decl());
+            loop {
+                let Some(decl) = self.parse_var_decl(spec.is_typedef) else {
+                    self.recover_stmt();
+                    return self.next_stmt(StmtKind::Error);
+                };
+                decls.push(decl);
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
             }
         }
-        self.expect_and_eat(&TokenKind::Semi);
+        if !self.expect_and_eat(&TokenKind::Semi) {
+            self.recover_stmt();
+            return self.next_stmt(StmtKind::Error);
+        }
         self.next_stmt(StmtKind::Decl(spec, decls))
     }
 
+    /// Parses declaration specifiers. Without any type specifier the type
+    /// is `int`, as in K&R C.
     pub fn parse_decl_spec(&mut self) -> DeclSpec{
-        self.eat(&TokenKind::Keyword(KeywordKind::Int));
-        DeclSpec {  }
+        let mut quals = TypeQuals::default();
+        let mut counter = 0;
+        let mut ty = TypeSpec::Int(IntKind::Int);
+        let mut is_typedef = false;
+        let mut storage = None;
+        let mut is_inline = false;
+        let mut thread_local = None;
+        loop {
+            if self.eat_type_qual(&mut quals) {
+                continue;
+            }
+            let span = self.peek().span;
+            match self.peek().kind {
+                TokenKind::Keyword(
+                    kw @ (KeywordKind::Typedef
+                    | KeywordKind::Static
+                    | KeywordKind::Extern
+                    | KeywordKind::Register
+                    | KeywordKind::Auto),
+                ) => {
+                    self.bump();
+                    if is_typedef || storage.is_some() {
+                        self.report(
+                            Diagnostic::error(ErrorCode::InvalidTypeSpec, "multiple storage classes in declaration specifiers")
+                                .with_primary(span, ""),
+                        );
+                    }
+                    match kw {
+                        KeywordKind::Typedef => is_typedef = true,
+                        KeywordKind::Static => storage = Some(StorageClass::Static),
+                        KeywordKind::Extern => storage = Some(StorageClass::Extern),
+                        KeywordKind::Register => storage = Some(StorageClass::Register),
+                        _ => storage = Some(StorageClass::Auto),
+                    }
+                    continue;
+                }
+                TokenKind::Keyword(KeywordKind::Inline) => {
+                    self.bump();
+                    is_inline = true;
+                    continue;
+                }
+                TokenKind::Keyword(KeywordKind::ThreadLocal) => {
+                    self.bump();
+                    thread_local = Some(span);
+                    continue;
+                }
+                _ => (),
+            }
+            // a typedef name is a type specifier only where no other one
+            // has been seen; otherwise it is the name being declared
+            if let TokenKind::Ident(sym) = self.peek().kind
+                && counter == 0
+                && self.is_typedef_name(sym)
+            {
+                self.bump();
+                counter = OTHER;
+                ty = TypeSpec::Typedef(sym);
+                continue;
+            }
+            let TokenKind::Keyword(kw) = self.peek().kind else { break };
+            let Some(weight) = type_spec_weight(kw) else { break };
+            let span = self.peek().span;
+            if let KeywordKind::Struct | KeywordKind::Union | KeywordKind::Enum = kw {
+                self.bump();
+                let spec = match kw {
+                    KeywordKind::Struct => TypeSpec::Record(self.parse_record_spec(RecordKind::Struct, span)),
+                    KeywordKind::Union => TypeSpec::Record(self.parse_record_spec(RecordKind::Union, span)),
+                    _ => TypeSpec::Enum(self.parse_enum_spec(span)),
+                };
+                if counter == 0 {
+                    counter = OTHER;
+                    ty = spec;
+                    continue;
+                }
+            }
+            match type_spec_for(counter + weight) {
+                Some(spec) => {
+                    counter += weight;
+                    ty = spec;
+                }
+                None => {
+                    self.report(
+                        Diagnostic::error(
+                            ErrorCode::InvalidTypeSpec,
+                            format!("cannot combine `{}` with the previous type specifiers", kw.as_str()),
+                        )
+                        .with_primary(span, ""),
+                    );
+                }
+            }
+            if span == self.peek().span {
+                self.bump();
+            }
+        }
+        // `_Thread_local` goes alone or with `static` or `extern`
+        if let Some(span) = thread_local {
+            let other = match storage {
+                _ if is_typedef => Some("typedef"),
+                Some(class @ (StorageClass::Register | StorageClass::Auto)) => Some(class.as_str()),
+                _ => None,
+            };
+            if let Some(other) = other {
+                self.report(
+                    Diagnostic::error(ErrorCode::InvalidTypeSpec, format!("`_Thread_local` cannot be combined with `{other}`"))
+                        .with_primary(span, ""),
+                );
+            }
+        }
+        DeclSpec {
+            ty,
+            quals,
+            is_typedef,
+            storage,
+            is_inline,
+            is_thread_local: thread_local.is_some(),
+        }
     }
 
-    pub fn parse_var_decl(&mut self) -> VarDecl{
-        let declarator  = self.parse_declarator();
-        let init = if self.eat(&TokenKind::Eq){
-            Some(Box::new(self.parse_expr()))
+    /// Parses what follows `enum`: a tag, an enumerator list, or both.
+    fn parse_enum_spec(&mut self, span: Span) -> EnumSpec {
+        let tag = self.eat_ident().map(|(sym, _)| sym);
+        if !self.eat(&TokenKind::LBrace) {
+            if tag.is_none() {
+                let e = NextTokenError::WrongType {
+                    expected: STR_IDENTIFIER,
+                    found: *self.peek(),
+                };
+                self.report(e.into());
+            }
+            return EnumSpec { tag, enumerators: None, span };
+        }
+        let mut enumerators = vec![];
+        // a trailing comma is allowed: `enum { A, B, }`
+        while !self.eat(&TokenKind::RBrace) {
+            let Some((name, span)) = self.eat_member_name() else {
+                self.expect_closing(&TokenKind::RBrace);
+                break;
+            };
+            let value = if self.eat(&TokenKind::Eq) {
+                Some(Box::new(self.parse_conditional()))
+            } else {
+                None
+            };
+            self.declare_name(name, false);
+            enumerators.push(Enumerator { name, value, span });
+            if !self.eat(&TokenKind::Comma) {
+                self.expect_closing(&TokenKind::RBrace);
+                break;
+            }
+        }
+        EnumSpec {
+            tag,
+            enumerators: Some(enumerators),
+            span,
+        }
+    }
+
+    /// Parses what follows `struct` or `union`: a tag, a member list, or
+    /// both. `span` is the keyword's.
+    fn parse_record_spec(&mut self, kind: RecordKind, span: Span) -> RecordSpec {
+        let tag = self.eat_ident().map(|(sym, _)| sym);
+        if !self.eat(&TokenKind::LBrace) {
+            if tag.is_none() {
+                let e = NextTokenError::WrongType {
+                    expected: STR_IDENTIFIER,
+                    found: *self.peek(),
+                };
+                self.report(e.into());
+            }
+            return RecordSpec { kind, tag, members: None, span };
+        }
+        let mut members = vec![];
+        while !self.eat(&TokenKind::RBrace) {
+            if self.is_eof() {
+                self.expect_and_eat(&TokenKind::RBrace);
+                break;
+            }
+            let spec = self.parse_decl_spec();
+            let mut declarators = vec![];
+            let mut damaged = false;
+            if !self.expect(&TokenKind::Semi) {
+                loop {
+                    match self.parse_declarator() {
+                        Some(decl) => declarators.push(decl),
+                        None => {
+                            damaged = true;
+                            break;
+                        }
+                    }
+                    if !self.eat(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+            }
+            if damaged || !self.expect_and_eat(&TokenKind::Semi) {
+                self.recover_stmt();
+            }
+            members.push(MemberDecl { spec, declarators });
+        }
+        RecordSpec {
+            kind,
+            tag,
+            members: Some(members),
+            span,
+        }
+    }
+
+    fn eat_type_qual(&mut self, quals: &mut TypeQuals) -> bool {
+        match self.peek().kind {
+            TokenKind::Keyword(KeywordKind::Const) => quals.is_const = true,
+            TokenKind::Keyword(KeywordKind::Volatile) => quals.is_volatile = true,
+            TokenKind::Keyword(KeywordKind::Restrict) => quals.is_restrict = true,
+            TokenKind::Keyword(KeywordKind::Atomic) => quals.is_atomic = true,
+            _ => return false,
+        }
+        self.bump();
+        true
+    }
+
+    /// Eats a possibly empty run of type qualifiers; repeating one is allowed.
+    pub fn parse_type_quals(&mut self) -> TypeQuals {
+        let mut quals = TypeQuals::default();
+        while self.eat_type_qual(&mut quals) {}
+        quals
+    }
+
+    /// Parses one declarator with its initializer.
+    pub fn parse_var_decl(&mut self, is_typedef: bool) -> Option<VarDecl>{
+        let declarator  = self.parse_declarator()?;
+        Some(self.finish_var_decl(declarator, is_typedef))
+    }
+
+    /// Declares the name of a parsed declarator, which is in scope from
+    /// here on, and parses its initializer. Typedefs and functions take
+    /// no initializer.
+    fn finish_var_decl(&mut self, declarator: Declarator, is_typedef: bool) -> VarDecl {
+        if let Some(name) = declarator.function_name() {
+            self.declare_name(name, is_typedef);
+        }
+        let init = if !is_typedef && !declarator.is_function() && self.eat(&TokenKind::Eq){
+            Some(self.parse_initializer())
         } else {
             None
         };
-        VarDecl { declarator, init}
+        VarDecl { declarator, init }
+    }
+
+    /// Parses an initializer: an assignment expression, or a braced list
+    /// whose items may be designated and may end with a comma.
+    fn parse_initializer(&mut self) -> Initializer {
+        let span = self.peek().span;
+        if !self.eat(&TokenKind::LBrace) {
+            return Initializer::Expr(Box::new(self.parse_assign()));
+        }
+        let mut items = vec![];
+        while !self.eat(&TokenKind::RBrace) {
+            let mut designators = vec![];
+            loop {
+                if self.eat(&TokenKind::LBracket) {
+                    designators.push(Designator::Index(Box::new(self.parse_conditional())));
+                    self.expect_closing(&TokenKind::RBracket);
+                } else if self.eat(&TokenKind::Dot) {
+                    let Some((member, span)) = self.eat_member_name() else { break };
+                    designators.push(Designator::Member(member, span));
+                } else {
+                    break;
+                }
+            }
+            if !designators.is_empty() {
+                self.expect_and_eat(&TokenKind::Eq);
+            }
+            let init = self.parse_initializer();
+            items.push(InitItem { designators, init });
+            if !self.eat(&TokenKind::Comma) {
+                self.expect_closing(&TokenKind::RBrace);
+                break;
+            }
+        }
+        Initializer::List(items, span)
     }
 
-    pub fn parse_declarator(&mut self) -> Declarator{
-        let direct = self.parse_direct_decl();
-        self.next_declarator(None, direct)
+    /// Parses a declarator; reports an error and returns `None` if there is
+    /// no identifier to declare.
+    pub fn parse_declarator(&mut self) -> Option<Declarator>{
+        self.parse_declarator_of(DeclaratorKind::Named)
     }
 
-    pub fn parse_pointer_decl(&mut self)-> PointerDecl{
-        PointerDecl { inner: None }
+    fn parse_declarator_of(&mut self, kind: DeclaratorKind) -> Option<Declarator> {
+        let ptr = self.parse_pointer_decl();
+        let (direct, span) = self.parse_direct_decl(kind)?;
+        Some(self.next_declarator(ptr, direct, span))
     }
 
-    pub fn parse_direct_decl(&mut self) -> DirectDeclarator{
-        if let Some((sym, _)) = self.eat_ident(){
-            DirectDeclarator::Ident(sym)
+    pub fn parse_pointer_decl(&mut self)-> Option<Box<PointerDecl>>{
+        if !self.eat(&TokenKind::Mul) {
+            return None;
+        }
+        let quals = self.parse_type_quals();
+        let inner = self.parse_pointer_decl();
+        Some(Box::new(PointerDecl { quals, inner }))
+    }
+
+    /// Parses the name, or a parenthesized declarator, with the array and
+    /// parameter list suffixes that follow. Returns the span of the name.
+    fn parse_direct_decl(&mut self, kind: DeclaratorKind) -> Option<(DirectDeclarator, Span)>{
+        let span = self.peek().span;
+        let (mut direct, span) = if self.is_nested_declarator(kind) {
+            self.bump();
+            let inner = self.parse_declarator_of(kind)?;
+            self.expect_closing(&TokenKind::RParen);
+            let span = inner.span;
+            (DirectDeclarator::Paren(Box::new(inner)), span)
+        } else if kind != DeclaratorKind::Abstract
+            && let Some((sym, span)) = self.eat_ident()
+        {
+            (DirectDeclarator::Ident(sym), span)
+        } else if kind != DeclaratorKind::Named {
+            (DirectDeclarator::Abstract, span)
+        } else {
+            let e = NextTokenError::WrongType {
+                expected: STR_IDENTIFIER,
+                found: *self.peek(),
+            };
+            self.report(e.into());
+            return None;
+        };
+        loop {
+            if self.eat(&TokenKind::LParen) {
+                let prototyped = !self.expect(&TokenKind::RParen);
+                let (params, variadic) = self.parse_param_list();
+                direct = DirectDeclarator::Func {
+                    inner: Box::new(direct),
+                    params,
+                    variadic,
+                    prototyped,
+                };
+            } else if self.eat(&TokenKind::LBracket) {
+                let size = if self.expect(&TokenKind::RBracket) {
+                    None
+                } else {
+                    Some(Box::new(self.parse_assign()))
+                };
+                self.expect_closing(&TokenKind::RBracket);
+                direct = DirectDeclarator::Array {
+                    inner: Box::new(direct),
+                    size,
+                };
+            } else {
+                break;
+            }
+        }
+        Some((direct, span))
+    }
+
+    /// Whether a `(` opens a nested declarator rather than a parameter
+    /// list. Where the name may be left out, `int (*)(int)` and `int
+    /// (int)` both begin with `(`: only a `*`, `(`, `[` or a name that is
+    /// not a type can start a nested one.
+    fn is_nested_declarator(&self, kind: DeclaratorKind) -> bool {
+        if !self.expect(&TokenKind::LParen) {
+            return false;
+        }
+        if kind == DeclaratorKind::Named {
+            return true;
+        }
+        match self.tokens.get(self.index + 1).map(|tk| tk.kind) {
+            Some(TokenKind::Mul | TokenKind::LParen | TokenKind::LBracket) => true,
+            Some(TokenKind::Ident(sym)) => kind == DeclaratorKind::MaybeAbstract && !self.is_typedef_name(sym),
+            _ => false,
+        }
+    }
+
+    /// Parses a parameter list after its `(`, and whether it ends in
+    /// `...`.
+    pub fn parse_param_list(&mut self) -> (Vec<ParamDecl>, bool) {
+        let mut params = vec![];
+        if self.eat(&TokenKind::RParen) {
+            return (params, false);
+        }
+        // `(void)` declares that there are no parameters
+        if self.expect(&TokenKind::Keyword(KeywordKind::Void))
+            && self.tokens.get(self.index + 1).is_some_and(|tk| tk.kind == TokenKind::RParen)
+        {
+            self.bump();
+            self.bump();
+            return (params, false);
         }
-        else{
-            panic!("no direct decl found")
+        let mut variadic = false;
+        loop {
+            if self.eat(&TokenKind::Ellipsis) {
+                variadic = true;
+                break;
+            }
+            let spec = self.parse_decl_spec();
+            let declarator = match self.peek().kind {
+                TokenKind::Comma | TokenKind::RParen => None,
+                _ => self.parse_declarator_of(DeclaratorKind::MaybeAbstract),
+            };
+            params.push(ParamDecl { spec, declarator });
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
         }
+        self.expect_closing(&TokenKind::RParen);
+        (params, variadic)
     }
 
     pub fn eat_ident(&mut self) -> Option<(Symbol, Span)>{
         let result = if let TokenKind::Ident(sym) = &self.peek().kind{
-            Some((sym.clone(), self.peek().span))
+            Some((*sym, self.peek().span))
         } else{
             None
         };
@@ -353,16 +1129,22 @@ impl Parser {
     }
 
     pub fn parse_compoundstmt(&mut self) -> Stmt {
+        self.scopes.push(HashMap::new());
         let mut stmts = Vec::new();
         while !self.eat(&TokenKind::RBrace) {
+            if self.is_eof() {
+                self.expect_and_eat(&TokenKind::RBrace);
+                break;
+            }
             stmts.push( 
-                if is_typename(&self.peek().kind){
+                if !self.is_label() && self.is_typename(){
                     self.parse_declaration()
                 } else {
                     self.parse_stmt()
                 }
             );
         }
+        self.scopes.pop();
         self.next_stmt(StmtKind::Block(stmts))
     }
 
@@ -370,19 +1152,34 @@ impl Parser {
         if self.eat(&TokenKind::LBrace) {
             self.parse_compoundstmt()
         } else if self.eat(&TokenKind::Keyword(KeywordKind::Return)) {
-            let result = StmtKind::Return(Box::new(self.parse_expr()));
-            if !self.eat(&TokenKind::Semi) {
-                panic!("missing ;");
-            }
-            self.next_stmt(result)
+            let value = if self.expect(&TokenKind::Semi) {
+                None
+            } else {
+                Some(Box::new(self.parse_expr()))
+            };
+            self.finish_stmt(StmtKind::Return(value))
+        } else if self.is_label() {
+            let (name, span) = self.eat_ident().expect("checked by is_label");
+            self.bump();
+            let stmt = self.parse_stmt();
+            self.next_stmt(StmtKind::Label(name, span, Box::new(stmt)))
+        } else if let TokenKind::Keyword(
+            kw @ (KeywordKind::Break
+            | KeywordKind::Continue
+            | KeywordKind::Goto
+            | KeywordKind::Case
+            | KeywordKind::Default
+            | KeywordKind::Do
+            | KeywordKind::Switch),
+        ) = self.peek().kind
+        {
+            let span = self.peek().span;
+            self.bump();
+            self.parse_jump_stmt(kw, span)
         } else if self.eat(&TokenKind::Keyword(KeywordKind::If)) {
-            if !self.eat(&TokenKind::LParen) {
-                panic!("missing (")
-            }
+            self.expect_and_eat(&TokenKind::LParen);
             let condition = self.parse_expr();
-            if !self.eat(&TokenKind::RParen) {
-                panic!("missing )")
-            }
+            self.expect_closing(&TokenKind::RParen);
             let ops = self.parse_stmt();
             let else_ops = if self.eat(&TokenKind::Keyword(KeywordKind::Else)) {
                 Some(Box::new(self.parse_stmt()))
@@ -391,31 +1188,29 @@ impl Parser {
             };
             self.next_stmt(StmtKind::If(Box::new(condition), Box::new(ops), else_ops))
         } else if self.eat(&TokenKind::Keyword(KeywordKind::While)) {
-            if !self.eat(&TokenKind::LParen) {
-                panic!("missing (")
-            }
+            self.expect_and_eat(&TokenKind::LParen);
             let condition = self.parse_expr();
-            if !self.eat(&TokenKind::RParen) {
-                panic!("missing )")
-            }
+            self.expect_closing(&TokenKind::RParen);
             let ops = self.parse_stmt();
             self.next_stmt(StmtKind::While(Box::new(condition), Box::new(ops)))
         } else if self.eat(&TokenKind::Keyword(KeywordKind::For)) {
-            if !self.eat(&TokenKind::LParen) {
-                panic!("missing (")
-            }
-            let ini = self.parse_exprstmt();
+            self.expect_and_eat(&TokenKind::LParen);
+            self.scopes.push(HashMap::new());
+            let ini = if self.is_typename() {
+                Some(Box::new(self.parse_declaration()))
+            } else {
+                self.parse_exprstmt().map(|expr| Box::new(self.next_stmt(StmtKind::ExprStmt(expr))))
+            };
             let cond = self.parse_exprstmt();
             let incr = if !self.eat(&TokenKind::RParen) {
                 let incr = self.parse_expr();
-                if !self.eat(&TokenKind::RParen) {
-                    panic!("missing )")
-                }
+                self.expect_closing(&TokenKind::RParen);
                 Some(Box::new(incr))
             } else {
                 None
             };
             let ops = self.parse_stmt();
+            self.scopes.pop();
             self.next_stmt(StmtKind::For(ini, cond, incr, Box::new(ops)))
         } else {
             let kind = self.parse_exprstmt()
@@ -425,23 +1220,117 @@ impl Parser {
         }
     }
 
-    pub fn parse_fn(&mut self) -> Fn {
+    /// Parses the statements introduced by `kw`, already eaten at `span`,
+    /// that jump or are jumped to: `break`, `continue`, `goto`, `switch`
+    /// with its `case` and `default` labels, and `do ... while`.
+    fn parse_jump_stmt(&mut self, kw: KeywordKind, span: Span) -> Stmt {
+        match kw {
+            KeywordKind::Break => self.finish_stmt(StmtKind::Break(span)),
+            KeywordKind::Continue => self.finish_stmt(StmtKind::Continue(span)),
+            KeywordKind::Goto => {
+                let Some((name, span)) = self.eat_ident() else {
+                    let e = NextTokenError::WrongType {
+                        expected: STR_IDENTIFIER,
+                        found: *self.peek(),
+                    };
+                    self.report(e.into());
+                    self.recover_stmt();
+                    return self.next_stmt(StmtKind::Error);
+                };
+                self.finish_stmt(StmtKind::Goto(name, span))
+            }
+            KeywordKind::Case => {
+                let value = self.parse_conditional();
+                self.expect_and_eat(&TokenKind::Colon);
+                let stmt = self.parse_stmt();
+                self.next_stmt(StmtKind::Case(Box::new(value), Box::new(stmt)))
+            }
+            KeywordKind::Default => {
+                self.expect_and_eat(&TokenKind::Colon);
+                let stmt = self.parse_stmt();
+                self.next_stmt(StmtKind::Default(span, Box::new(stmt)))
+            }
+            KeywordKind::Do => {
+                let body = self.parse_stmt();
+                self.expect_and_eat(&TokenKind::Keyword(KeywordKind::While));
+                self.expect_and_eat(&TokenKind::LParen);
+                let condition = self.parse_expr();
+                self.expect_closing(&TokenKind::RParen);
+                self.finish_stmt(StmtKind::DoWhile(Box::new(body), Box::new(condition)))
+            }
+            KeywordKind::Switch => {
+                self.expect_and_eat(&TokenKind::LParen);
+                let value = self.parse_expr();
+                self.expect_closing(&TokenKind::RParen);
+                let body = self.parse_stmt();
+                self.next_stmt(StmtKind::Switch(Box::new(value), Box::new(body)))
+            }
+            _ => unreachable!("not a jump statement keyword"),
+        }
+    }
+
+    /// Eats the `;` ending a statement of the given kind.
+    fn finish_stmt(&mut self, kind: StmtKind) -> Stmt {
+        if !self.expect_and_eat(&TokenKind::Semi) {
+            self.recover_stmt();
+            return self.next_stmt(StmtKind::Error);
+        }
+        self.next_stmt(kind)
+    }
+
+    /// Whether a labelled statement, `name:`, comes next.
+    fn is_label(&self) -> bool {
+        matches!(self.peek().kind, TokenKind::Ident(_))
+            && self.tokens.get(self.index + 1).is_some_and(|tk| tk.kind == TokenKind::Colon)
+    }
+
+    /// Parses a function definition or a declaration at file scope. On a
+    /// damaged declarator or a missing body the error is reported and
+    /// `None` returned; the caller recovers.
+    pub fn parse_item(&mut self) -> Option<Item> {
         let spec = self.parse_decl_spec();
-        let declarator = self.parse_declarator();
-        self.expect_and_eat(&TokenKind::LBrace);
+        if self.eat(&TokenKind::Semi) {
+            return Some(Item::Decl(spec, vec![]));
+        }
+        let declarator = self.parse_declarator()?;
+        if spec.is_typedef || !declarator.is_function() || !self.expect(&TokenKind::LBrace) {
+            // a list of typedefs, variables or function prototypes
+            let mut decls = vec![self.finish_var_decl(declarator, spec.is_typedef)];
+            while self.eat(&TokenKind::Comma) {
+                decls.push(self.parse_var_decl(spec.is_typedef)?);
+            }
+            if !self.expect_and_eat(&TokenKind::Semi) {
+                return None;
+            }
+            return Some(Item::Decl(spec, decls));
+        }
+        if let Some(name) = declarator.function_name() {
+            self.declare_name(name, false);
+        }
+        self.bump();
+        // parameters are in scope in the body and may hide typedef names
+        self.scopes.push(HashMap::new());
+        let params = declarator.params().unwrap_or_default();
+        for name in params.iter().filter_map(|p| p.declarator.as_ref()?.function_name()) {
+            self.declare_name(name, false);
+        }
         let body = self.parse_compoundstmt();
-        Fn {
+        self.scopes.pop();
+        Some(Item::Fn(Fn {
             spec,
             declarator,
             body,
-        }
+        }))
     }
 
     pub fn parse_crate(&mut self) -> Crate {
-        let mut fns = vec![];
-        while self.index < self.tokens.len() {
-            fns.push(self.parse_fn());
+        let mut items = vec![];
+        while !self.is_eof() {
+            match self.parse_item() {
+                Some(item) => items.push(item),
+                None => self.recover_item(),
+            }
         }
-        Crate { fns }
+        Crate { items }
     }
 }
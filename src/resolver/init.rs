@@ -0,0 +1,317 @@
+//! Initializers: which element of the declared object each initializing
+//! expression stores to (C11 6.7.9).
+
+use super::*;
+
+/// The length an array of unknown size has while its initializer is laid
+/// out; it is set to the number of elements initialized afterwards.
+const UNKNOWN_LEN: usize = usize::MAX;
+
+/// The expressions of an initializer with the byte offset and type of the
+/// element each one initializes, in source order.
+#[derive(Default)]
+struct InitLayout<'a> {
+    fields: Vec<(&'a Expr, usize, Ty)>,
+    /// How many elements of an array of unknown size are initialized.
+    len: usize,
+}
+
+/// The position in the items of one braced list.
+struct Cursor<'a> {
+    items: &'a [InitItem],
+    pos: usize,
+    /// Whether the designators of the current item have been applied.
+    designated: bool,
+}
+
+impl<'a> Cursor<'a> {
+    fn item(&self) -> Option<&'a InitItem> {
+        self.items.get(self.pos)
+    }
+
+    /// Whether the current item starts a new position with designators.
+    fn at_designator(&self) -> bool {
+        !self.designated && self.item().is_some_and(|item| !item.designators.is_empty())
+    }
+
+    fn advance(&mut self) {
+        self.pos += 1;
+        self.designated = false;
+    }
+}
+
+impl Resolver {
+    pub(super) fn resolve_init(&mut self, init: &Initializer) {
+        match init {
+            Initializer::Expr(expr) => self.resolve_expr(expr),
+            Initializer::List(items, _) => {
+                for item in items {
+                    for designator in &item.designators {
+                        if let Designator::Index(index) = designator {
+                            self.resolve_expr(index);
+                        }
+                    }
+                    self.resolve_init(&item.init);
+                }
+            }
+        }
+    }
+
+    /// Works out where each expression of `init` stores to in an object
+    /// of type `ty`, recording it in `init_targets`. An array of unknown
+    /// size takes its length from the initializer.
+    pub(super) fn layout_init<'a>(&mut self, ty: &mut Ty, init: &'a Initializer) -> Vec<(&'a Expr, usize, Ty)> {
+        let mut layout = InitLayout::default();
+        match init {
+            Initializer::List(items, _) if braced_string(ty, items).is_none() => {
+                let unknown = matches!(ty, Ty::Array(_, None));
+                if let Ty::Array(_, len) = ty
+                    && unknown
+                {
+                    *len = Some(UNKNOWN_LEN);
+                }
+                self.init_list(&mut layout, ty, 0, items);
+                if let Ty::Array(_, len) = ty
+                    && unknown
+                {
+                    *len = Some(layout.len);
+                }
+            }
+            // without braces, or braced as in `char s[] = {"abc"}`, only
+            // a string literal fills an array
+            Initializer::List(items, _) => {
+                let expr = braced_string(ty, items).expect("checked above");
+                str_array_init(&self.resolved.tcx, ty, expr);
+                layout.fields.push((expr, 0, ty.clone()));
+            }
+            Initializer::Expr(expr) => {
+                str_array_init(&self.resolved.tcx, ty, expr);
+                layout.fields.push((expr, 0, ty.clone()));
+            }
+        }
+        for (expr, offset, ty) in &layout.fields {
+            self.resolved.init_targets.insert(expr.id, (*offset, ty.clone()));
+        }
+        layout.fields
+    }
+
+    /// Initializes an object of type `ty` at `offset` from a braced list.
+    fn init_list<'a>(&mut self, layout: &mut InitLayout<'a>, ty: &Ty, offset: usize, items: &'a [InitItem]) {
+        if let Some(expr) = braced_string(ty, items) {
+            layout.fields.push((expr, offset, ty.clone()));
+            return;
+        }
+        let mut cursor = Cursor { items, pos: 0, designated: false };
+        if !matches!(ty, Ty::Array(..) | Ty::Struct(_) | Ty::Union(_)) {
+            // braces around a scalar, as in `int x = {1}`
+            if let Some(item) = cursor.item() {
+                match item.designators.first() {
+                    None => self.init_elem(layout, ty, offset, &mut cursor),
+                    Some(designator) => {
+                        self.errors.push(
+                            Diagnostic::error(
+                                ErrorCode::InvalidInitializer,
+                                format!("designator in initializer for scalar type `{ty}`"),
+                            )
+                            .with_primary(designator.span(), ""),
+                        );
+                        cursor.advance();
+                    }
+                }
+            }
+            if let Some(item) = cursor.item() {
+                self.report_excess("scalar", item);
+            }
+            return;
+        }
+        let mut next = 0;
+        let mut reported = false;
+        while let Some(item) = cursor.item() {
+            if cursor.at_designator() {
+                if let Some(after) = self.designation(layout, ty, offset, &mut cursor, 0) {
+                    next = after;
+                }
+                continue;
+            }
+            match self.next_element(layout, ty, next) {
+                Some((elem, elem_offset)) => {
+                    self.init_elem(layout, &elem, offset + elem_offset, &mut cursor);
+                    next += 1;
+                }
+                None => {
+                    if !reported {
+                        let what = ty.tag_keyword().unwrap_or("array");
+                        self.report_excess(what, item);
+                        reported = true;
+                    }
+                    cursor.advance();
+                }
+            }
+        }
+    }
+
+    /// Initializes one element of type `ty` from the current item. An
+    /// aggregate given an expression that is not a whole value for it
+    /// takes its elements from the following items, as if braced.
+    fn init_elem<'a>(&mut self, layout: &mut InitLayout<'a>, ty: &Ty, offset: usize, cursor: &mut Cursor<'a>) {
+        let Some(item) = cursor.item() else { return };
+        match &item.init {
+            Initializer::List(sub, _) => {
+                cursor.advance();
+                self.init_list(layout, ty, offset, sub);
+            }
+            Initializer::Expr(expr) if !self.is_elided(ty, expr) => {
+                cursor.advance();
+                layout.fields.push((expr, offset, ty.clone()));
+            }
+            Initializer::Expr(_) => self.init_elided(layout, ty, offset, cursor, 0),
+        }
+    }
+
+    /// Whether the braces around the elements of `ty` are left out before
+    /// `expr`, which is then the first element's initializer.
+    fn is_elided(&self, ty: &Ty, expr: &Expr) -> bool {
+        match ty {
+            Ty::Array(..) => !is_str_init(ty, expr),
+            Ty::Struct(_) | Ty::Union(_) => TypeChecker::new(&self.resolved).check_expr(expr) != *ty,
+            _ => false,
+        }
+    }
+
+    /// Initializes the elements of `ty` from element `start` on, one item
+    /// (or elided group) each, until `ty` is full or a designator starts a
+    /// new position in an enclosing list.
+    fn init_elided<'a>(&mut self, layout: &mut InitLayout<'a>, ty: &Ty, offset: usize, cursor: &mut Cursor<'a>, start: usize) {
+        let mut i = start;
+        while cursor.item().is_some() && !cursor.at_designator() {
+            let Some((elem, elem_offset)) = self.next_element(layout, ty, i) else { break };
+            self.init_elem(layout, &elem, offset + elem_offset, cursor);
+            i += 1;
+        }
+    }
+
+    /// Applies designator `d` of the current item to `ty` and initializes
+    /// the element it selects, continuing with the following elements of
+    /// the innermost designated aggregate. Returns the index of the
+    /// element after the designated one, or `None` after reporting a
+    /// designator that selects nothing.
+    fn designation<'a>(
+        &mut self,
+        layout: &mut InitLayout<'a>,
+        ty: &Ty,
+        offset: usize,
+        cursor: &mut Cursor<'a>,
+        d: usize,
+    ) -> Option<usize> {
+        let designators = &cursor.item().expect("a designated item").designators;
+        let found = match (&designators[d], ty) {
+            (Designator::Index(index), Ty::Array(..)) => match consteval::eval_int(&self.resolved, index) {
+                Ok(i) => usize::try_from(i)
+                    .ok()
+                    .and_then(|i| Some((i, self.element(layout, ty, i)?)))
+                    .ok_or_else(|| {
+                        Diagnostic::error(
+                            ErrorCode::InvalidInitializer,
+                            format!("array index `{i}` in initializer exceeds array bounds of `{ty}`"),
+                        )
+                        .with_primary(index.span, "")
+                    }),
+                Err(e) => Err(Diagnostic::error(
+                    ErrorCode::NotConstant,
+                    "array index in initializer is not an integer constant",
+                )
+                .with_primary(e.span, e.reason)),
+            },
+            (Designator::Member(name, span), Ty::Struct(r) | Ty::Union(r)) => {
+                let members = &self.resolved.tcx.records[r.id].members;
+                match members.iter().position(|m| m.name == *name) {
+                    Some(i) => Ok((i, self.element(layout, ty, i).expect("member exists"))),
+                    None => Err(Diagnostic::error(
+                        ErrorCode::InvalidInitializer,
+                        format!("no member named `{name}` in `{ty}`"),
+                    )
+                    .with_primary(*span, "")),
+                }
+            }
+            (designator @ Designator::Index(_), _) => Err(Diagnostic::error(
+                ErrorCode::InvalidInitializer,
+                format!("array designator on non-array type `{ty}`"),
+            )
+            .with_primary(designator.span(), "")),
+            (designator @ Designator::Member(..), _) => Err(Diagnostic::error(
+                ErrorCode::InvalidInitializer,
+                format!("member designator on non-record type `{ty}`"),
+            )
+            .with_primary(designator.span(), "")),
+        };
+        let (i, (elem, elem_offset)) = match found {
+            Ok(found) => found,
+            Err(e) => {
+                self.errors.push(e);
+                cursor.advance();
+                return None;
+            }
+        };
+        let offset = offset + elem_offset;
+        if d + 1 < designators.len() {
+            if let Some(next) = self.designation(layout, &elem, offset, cursor, d + 1) {
+                self.init_elided(layout, &elem, offset, cursor, next);
+            }
+        } else {
+            cursor.designated = true;
+            self.init_elem(layout, &elem, offset, cursor);
+        }
+        Some(i + 1)
+    }
+
+    /// The type and byte offset of element `i` of an array, struct or
+    /// union, or `None` past its end.
+    fn element(&self, layout: &mut InitLayout, ty: &Ty, i: usize) -> Option<(Ty, usize)> {
+        match ty {
+            Ty::Array(elem, Some(len)) if i < *len => {
+                if *len == UNKNOWN_LEN {
+                    layout.len = layout.len.max(i + 1);
+                }
+                Some(((**elem).clone(), i * self.resolved.tcx.size_of(elem)))
+            }
+            Ty::Struct(r) | Ty::Union(r) => {
+                let member = self.resolved.tcx.records[r.id].members.get(i)?;
+                Some((member.ty.clone(), member.offset))
+            }
+            _ => None,
+        }
+    }
+
+    /// Element `i` in initialization order: a union initializes only its
+    /// first member unless designated.
+    fn next_element(&self, layout: &mut InitLayout, ty: &Ty, i: usize) -> Option<(Ty, usize)> {
+        match ty {
+            Ty::Union(_) if i > 0 => None,
+            ty => self.element(layout, ty, i),
+        }
+    }
+
+    fn report_excess(&mut self, what: &str, item: &InitItem) {
+        self.errors.push(
+            Diagnostic::warning(ErrorCode::ExcessInitializer, format!("excess elements in {what} initializer"))
+                .with_primary(item.init.span(), "")
+                .with_note("the extra elements are ignored"),
+        );
+    }
+}
+
+/// Whether `expr` is a string literal initializing the char array `ty`.
+fn is_str_init(ty: &Ty, expr: &Expr) -> bool {
+    matches!(ty, Ty::Array(elem, _) if elem.is_integer())
+        && matches!(expr.kind, ExprKind::Literal(Lit { kind: LitKind::Str(_), .. }))
+}
+
+/// The string literal of `{"abc"}` initializing the char array `ty`.
+fn braced_string<'a>(ty: &Ty, items: &'a [InitItem]) -> Option<&'a Expr> {
+    match items {
+        [InitItem { designators, init: Initializer::Expr(expr) }] if designators.is_empty() && is_str_init(ty, expr) => {
+            Some(expr)
+        }
+        _ => None,
+    }
+}
@@ -1,8 +1,16 @@
 use crate::ast::*;
-use std::collections::HashMap;
+use crate::codegen::abi::TargetAbi;
+use crate::consteval::{self, ConstAddr, ConstValue};
+use crate::error_handler::{Diagnostic, ErrorCode};
+use crate::span::Span;
+use crate::ty::{FnTy, Member, RecordLayout, TagRef, Ty, TyCtx};
+use crate::typeck::TypeChecker;
+use std::collections::{HashMap, HashSet};
 
 use super::tokenizer::*;
 
+mod init;
+
 pub type ObjId = usize;
 
 #[derive(Default)]
@@ -10,12 +18,64 @@ pub struct ResolvedCrate {
     pub expr_resolutions: HashMap<NodeId, ObjId>,
     pub objs: Vec<Obj>,
     pub fn_info: HashMap<Symbol, FnInfo>,
+    /// The case labels of each `switch` statement, by statement id.
+    pub switches: HashMap<NodeId, SwitchCases>,
+    /// File-scope variables in order of first declaration.
+    pub globals: Vec<Global>,
+    /// The type each type name in a cast, `sizeof` or `_Alignof` stands
+    /// for, by the id of its declarator.
+    pub type_names: HashMap<NodeId, Ty>,
+    /// For each expression of an initializer, the byte offset within the
+    /// object and the type of the element it initializes. Excess elements
+    /// have none.
+    pub init_targets: HashMap<NodeId, (usize, Ty)>,
+    pub tcx: TyCtx,
+}
+
+/// Where a `switch` jumps: the `case` and `default` statements, by id.
+#[derive(Default)]
+pub struct SwitchCases {
+    pub cases: Vec<(i64, NodeId)>,
+    pub default: Option<NodeId>,
+}
+
+/// A statement that `break` or `continue` can leave.
+enum JumpTarget {
+    Loop,
+    /// A switch, collecting its labels.
+    Switch(SwitchCases),
+}
+
+/// A variable with static storage defined at file scope.
+pub struct Global {
+    pub id: ObjId,
+    /// `None` for a tentative definition, which is zero-filled.
+    pub init: Option<GlobalInit>,
+    /// A `const` object, placed in `.rodata`.
+    pub read_only: bool,
+    pub is_thread_local: bool,
+}
+
+/// The initial contents of a global, known at compile time.
+pub enum GlobalInit {
+    /// An integer or null pointer, stored in the object's width.
+    Int(i64),
+    /// A char array filled from a string literal: code units as stored,
+    /// truncated or zero-padded to the array length.
+    Str(Vec<u32>),
+    /// A pointer, or an integer as wide as one, holding an address that
+    /// the linker fills in.
+    Addr(ConstAddr),
+    /// A brace-enclosed initializer: the initialized elements with their
+    /// byte offsets and types, by ascending offset. The gaps are zero.
+    List(Vec<(usize, Ty, GlobalInit)>),
 }
 
 #[derive(Default)]
 pub struct ScopeFrame {
     pub ord_map: HashMap<Symbol, ObjId>,
-    pub tagged_map: HashMap<Symbol, ObjId>,
+    /// Struct, union and enum tags, mapped to the type they name.
+    pub tagged_map: HashMap<Symbol, Ty>,
 }
 
 pub enum ObjKind {
@@ -23,7 +83,10 @@ pub enum ObjKind {
     Param,
     Global,
     Func,
-    EnumConst,
+    /// An enumeration constant with its value.
+    EnumConst(i64),
+    /// A typedef name; `ty` is the type it stands for.
+    Typedef,
 }
 
 pub struct FnInfo {
@@ -46,6 +109,21 @@ pub struct Obj {
     pub id: ObjId,
     pub name: Symbol,
     pub kind: ObjKind,
+    /// Declared type; a `Ty::Func` for functions.
+    pub ty: Ty,
+    pub linkage: Linkage,
+}
+
+/// Whether the same name in other scopes or translation units refers to
+/// the same object (C11 6.2.2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Linkage {
+    /// Locals, parameters, block-scope `static`s and everything that is
+    /// not an object or function.
+    None,
+    /// `static` at file scope: private to this translation unit.
+    Internal,
+    External,
 }
 
 pub struct Resolver {
@@ -53,42 +131,155 @@ pub struct Resolver {
     pub resolved: ResolvedCrate,
     obj_cnt: usize,
     operating_fn: Option<FnInfo>,
+    /// The loops and switches around the statement being resolved,
+    /// innermost last.
+    jump_targets: Vec<JumpTarget>,
+    /// Labels defined so far in the current function.
+    labels: HashMap<Symbol, Span>,
+    /// The `goto`s of the current function, checked against `labels` at
+    /// its end.
+    gotos: Vec<(Symbol, Span)>,
+    /// Functions defined further down the file. A call may name one before
+    /// its declaration and then refers to the object the definition
+    /// declares.
+    later_fns: HashSet<Symbol>,
+    /// Functions declared by such a call, whose type is only known once
+    /// the definition is reached.
+    hoisted_fns: HashSet<ObjId>,
+    /// The functions and variables with linkage by name, including those
+    /// so far only declared in a block.
+    linked: HashMap<Symbol, ObjId>,
+    /// The enums, by record id, whose enumerators have been declared; a
+    /// forward-declared enum gets them from its first body.
+    defined_enums: HashSet<usize>,
+    pub errors: Vec<Diagnostic>,
 }
 
 impl Default for Resolver {
     fn default() -> Self {
-        Resolver::new()
+        Resolver::new(TyCtx::new(TargetAbi::host()))
     }
 }
 
 impl Resolver {
-    pub fn new() -> Resolver {
-        Resolver {
+    pub fn new(tcx: TyCtx) -> Resolver {
+        let va_list = tcx.va_list.clone();
+        let mut resolver = Resolver {
             scopes: vec![ScopeFrame::default()],
-            resolved: ResolvedCrate::default(),
+            resolved: ResolvedCrate {
+                tcx,
+                ..ResolvedCrate::default()
+            },
             obj_cnt: 0,
             operating_fn: None,
-        }
+            jump_targets: vec![],
+            labels: HashMap::new(),
+            gotos: vec![],
+            later_fns: HashSet::new(),
+            hoisted_fns: HashSet::new(),
+            linked: HashMap::new(),
+            defined_enums: HashSet::new(),
+            errors: vec![],
+        };
+        // predeclared, as if `<stdarg.h>` were included
+        resolver.declare_typedef(Symbol::intern("va_list"), va_list);
+        resolver
     }
 
     pub fn resolve(&mut self, source: &Crate) {
-        self.resolved.fn_info = source
-            .fns
+        // only function signatures are visible before their declaration;
+        // everything else is resolved in source order
+        self.later_fns = source
+            .items
             .iter()
-            .map(|func| (func.declarator.function_name().expect("invalid fn def").clone(), self.resolve_fn(func)))
-            .collect::<HashMap<_, _>>();
+            .filter_map(|item| match item {
+                Item::Fn(func) => func.declarator.function_name(),
+                Item::Decl(..) => None,
+            })
+            .collect();
+        for item in &source.items {
+            match item {
+                Item::Fn(func) => {
+                    let ty = self.spec_ty(&func.spec);
+                    let ty = self.declarator_ty(&func.declarator, ty);
+                    let name = func.declarator.function_name().expect("invalid fn def");
+                    self.check_storage(&func.spec, name, &ty, func.declarator.span, true);
+                    let is_static = func.spec.storage == Some(StorageClass::Static);
+                    let id = self.declare_fn(name, ty, is_static, func.declarator.span);
+                    if self.resolved.fn_info.contains_key(&name) {
+                        self.errors.push(
+                            Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of `{name}`"))
+                                .with_primary(func.declarator.span, ""),
+                        );
+                        continue;
+                    }
+                    let info = self.resolve_fn(func, id);
+                    self.resolved.fn_info.insert(name, info);
+                }
+                Item::Decl(spec, decls) => {
+                    let base = self.decl_spec_ty(spec, decls.is_empty());
+                    for decl in decls {
+                        let name = decl.declarator.function_name().expect("declarator without a name");
+                        let ty = self.declarator_ty(&decl.declarator, base.clone());
+                        self.check_storage(spec, name, &ty, decl.declarator.span, true);
+                        let id = if spec.is_typedef {
+                            self.declare_typedef(name, ty)
+                        } else if let Ty::Func(_) = ty {
+                            let is_static = spec.storage == Some(StorageClass::Static);
+                            self.declare_fn(name, ty, is_static, decl.declarator.span)
+                        } else {
+                            self.declare_global(decl, name, ty, spec)
+                        };
+                        self.resolved.expr_resolutions.insert(decl.declarator.id, id);
+                    }
+                }
+            }
+        }
+        // a tentative array definition never completed has one element
+        // (C11 6.9.2p5)
+        for global in &self.resolved.globals {
+            let obj = &mut self.resolved.objs[global.id];
+            if global.init.is_none()
+                && let Ty::Array(elem, None) = &obj.ty
+            {
+                obj.ty = Ty::Array(elem.clone(), Some(1));
+            }
+        }
     }
 
-    pub fn resolve_fn(&mut self, func: &Fn) -> FnInfo {
-        let id = self.declare_fn(func.declarator.function_name().expect("invalid fn def"));
+    pub fn resolve_fn(&mut self, func: &Fn, id: ObjId) -> FnInfo {
         self.operating_fn = Some(FnInfo::new(id));
         let fn_frame = ScopeFrame::default();
         self.scopes.push(fn_frame);
-        for (name, ty) in &func.params {
-            self.declare_param(name);
+        let param_tys = match &self.resolved.objs[id].ty {
+            Ty::Func(fn_ty) => fn_ty.params.clone(),
+            _ => vec![],
+        };
+        for (param, ty) in func.declarator.params().unwrap_or_default().iter().zip(param_tys) {
+            // an unnamed parameter still takes its register or stack slot
+            let name = param
+                .declarator
+                .as_ref()
+                .and_then(|d| d.function_name())
+                .unwrap_or_else(|| Symbol::intern(""));
+            let span = param.declarator.as_ref().map_or(func.declarator.span, |d| d.span);
+            self.declare_param(name, ty, span);
         }
 
-        self.resolve_stmt(&func.body);
+        // the parameters are in the scope of the body's outermost block
+        match &func.body.kind {
+            StmtKind::Block(stmts) => stmts.iter().for_each(|stmt| self.resolve_stmt(stmt)),
+            _ => self.resolve_stmt(&func.body),
+        }
+        for (name, span) in std::mem::take(&mut self.gotos) {
+            if !self.labels.contains_key(&name) {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::UndeclaredIdentifier, format!("use of undeclared label `{name}`"))
+                        .with_primary(span, ""),
+                );
+            }
+        }
+        self.labels.clear();
 
         self.scopes.pop();
         self.operating_fn.take().unwrap()
@@ -107,23 +298,104 @@ impl Resolver {
                 self.resolve_expr(expr.as_ref());
             }
             StmtKind::Return(expr) => {
-                self.resolve_expr(expr.as_ref());
+                expr.as_ref().inspect(|expr| self.resolve_expr(expr));
             }
             StmtKind::For(init, cond, incr, stmt) => {
-                init.as_ref()
-                    .as_ref()
-                    .inspect(|expr| self.resolve_expr(expr));
+                // a declaration in the init is visible in the loop only
+                self.scopes.push(ScopeFrame::default());
+                init.as_ref().inspect(|init| self.resolve_stmt(init));
                 cond.as_ref()
                     .as_ref()
-                    .inspect(|expr| self.resolve_expr(expr));
+                    .inspect(|expr| {
+                        self.resolve_expr(expr);
+                    });
                 incr.as_ref()
                     .as_ref()
-                    .inspect(|expr| self.resolve_expr(expr));
-                self.resolve_stmt(stmt);
+                    .inspect(|expr| {
+                        self.resolve_expr(expr);
+                    });
+                self.resolve_loop_body(stmt);
+                self.scopes.pop();
             }
             StmtKind::While(cond, stmt) => {
                 self.resolve_expr(cond.as_ref());
-                self.resolve_stmt(stmt);
+                self.resolve_loop_body(stmt);
+            }
+            StmtKind::DoWhile(stmt, cond) => {
+                self.resolve_loop_body(stmt);
+                self.resolve_expr(cond);
+            }
+            StmtKind::Switch(value, body) => {
+                self.resolve_expr(value);
+                self.jump_targets.push(JumpTarget::Switch(SwitchCases::default()));
+                self.resolve_stmt(body);
+                let Some(JumpTarget::Switch(cases)) = self.jump_targets.pop() else {
+                    unreachable!("jump targets are balanced")
+                };
+                self.resolved.switches.insert(stmt.id, cases);
+            }
+            StmtKind::Case(value, body) => {
+                self.resolve_expr(value);
+                let span = value.span;
+                let value = consteval::eval_int(&self.resolved, value).map_err(|e| {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::NotConstant, "case value is not an integer constant")
+                            .with_primary(e.span, e.reason),
+                    );
+                });
+                match (self.innermost_switch(), value) {
+                    (None, _) => self.errors.push(
+                        Diagnostic::error(ErrorCode::MisplacedJump, "`case` label not within a switch statement")
+                            .with_primary(span, ""),
+                    ),
+                    (Some(cases), Ok(value)) if cases.cases.iter().any(|(v, _)| *v == value) => self.errors.push(
+                        Diagnostic::error(ErrorCode::DuplicateCase, format!("duplicate case value `{value}`"))
+                            .with_primary(span, ""),
+                    ),
+                    (Some(cases), Ok(value)) => cases.cases.push((value, stmt.id)),
+                    (Some(_), Err(())) => (),
+                }
+                self.resolve_stmt(body);
+            }
+            StmtKind::Default(span, body) => {
+                match self.innermost_switch() {
+                    None => self.errors.push(
+                        Diagnostic::error(ErrorCode::MisplacedJump, "`default` label not within a switch statement")
+                            .with_primary(*span, ""),
+                    ),
+                    Some(SwitchCases { default: Some(_), .. }) => self.errors.push(
+                        Diagnostic::error(ErrorCode::DuplicateCase, "multiple `default` labels in one switch")
+                            .with_primary(*span, ""),
+                    ),
+                    Some(cases) => cases.default = Some(stmt.id),
+                }
+                self.resolve_stmt(body);
+            }
+            StmtKind::Break(span) => {
+                if self.jump_targets.is_empty() {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::MisplacedJump, "`break` statement not within a loop or switch")
+                            .with_primary(*span, ""),
+                    );
+                }
+            }
+            StmtKind::Continue(span) => {
+                if !self.jump_targets.iter().any(|target| matches!(target, JumpTarget::Loop)) {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::MisplacedJump, "`continue` statement not within a loop")
+                            .with_primary(*span, ""),
+                    );
+                }
+            }
+            StmtKind::Goto(name, span) => self.gotos.push((*name, *span)),
+            StmtKind::Label(name, span, body) => {
+                if self.labels.insert(*name, *span).is_some() {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of label `{name}`"))
+                            .with_primary(*span, ""),
+                    );
+                }
+                self.resolve_stmt(body);
             }
             StmtKind::If(cond, if_stmt, else_stmt) => {
                 self.resolve_expr(cond.as_ref());
@@ -133,111 +405,898 @@ impl Resolver {
                     .as_ref()
                     .inspect(|stmt| self.resolve_stmt(stmt));
             }
-            StmtKind::Decl(_, var_decls)=>{
+            StmtKind::Decl(spec, var_decls)=>{
+                let base = self.decl_spec_ty(spec, var_decls.is_empty());
                 for var in var_decls{
-                    let name = match &var.declarator.direct{
-                        DirectDeclarator::Ident(sym) => sym,
-                        _ => todo!("not implemented further decls")
+                    let name = var.declarator.function_name().expect("declarator without a name");
+                    let mut ty = self.declarator_ty(&var.declarator, base.clone());
+                    if spec.is_typedef {
+                        let id = self.declare_typedef(name, ty);
+                        self.resolved.expr_resolutions.insert(var.declarator.id, id);
+                        continue;
+                    }
+                    self.check_storage(spec, name, &ty, var.declarator.span, false);
+                    // functions and `extern` variables declared in a block
+                    // are the file-scope ones, visible only in the block
+                    let linked = match spec.storage {
+                        _ if matches!(ty, Ty::Func(_)) => {
+                            Some(self.declare_fn(name, ty.clone(), false, var.declarator.span))
+                        }
+                        Some(StorageClass::Extern) => {
+                            if let Some(init) = &var.init {
+                                self.errors.push(
+                                    Diagnostic::error(
+                                        ErrorCode::InvalidInitializer,
+                                        format!("`extern` variable `{name}` in a block cannot be initialized"),
+                                    )
+                                    .with_primary(init.span(), ""),
+                                );
+                            }
+                            Some(self.declare_global(var, name, ty.clone(), spec))
+                        }
+                        Some(StorageClass::Static) => {
+                            let id = self.declare_static_local(var, name, ty.clone(), spec);
+                            self.resolved.expr_resolutions.insert(var.declarator.id, id);
+                            continue;
+                        }
+                        _ => None,
                     };
-                    let id = self.declare_local(name);
+                    if let Some(id) = linked {
+                        let scope = self.scopes.last_mut().expect("internal declare must in scope");
+                        scope.ord_map.insert(name, id);
+                        self.resolved.expr_resolutions.insert(var.declarator.id, id);
+                        continue;
+                    }
+                    // the name is in scope in its own initializer
+                    let id = self.declare_local(name, ty.clone(), var.declarator.span);
                     self.resolved.expr_resolutions.insert(var.declarator.id, id);
-                    var.init
-                        .as_ref()
-                        .inspect(|expr| self.resolve_expr(expr)); 
+                    if let Some(init) = &var.init {
+                        self.resolve_init(init);
+                        self.layout_init(&mut ty, init);
+                    }
+                    self.check_complete(name, &ty, var.declarator.span);
+                    self.resolved.objs[id].ty = ty;
+                }
+            }
+            StmtKind::Null | StmtKind::Error => (),
+        }
+    }
+
+    fn resolve_loop_body(&mut self, body: &Stmt) {
+        self.jump_targets.push(JumpTarget::Loop);
+        self.resolve_stmt(body);
+        self.jump_targets.pop();
+    }
+
+    /// The labels of the switch a `case` or `default` belongs to.
+    fn innermost_switch(&mut self) -> Option<&mut SwitchCases> {
+        self.jump_targets.iter_mut().rev().find_map(|target| match target {
+            JumpTarget::Switch(cases) => Some(cases),
+            JumpTarget::Loop => None,
+        })
+    }
+
+    /// Reports a storage class that cannot apply to the declared name:
+    /// `auto` and `register` outside a block, anything but `extern` on a
+    /// function in a block, and `_Thread_local` on a block-scope variable
+    /// without static storage or on a function.
+    fn check_storage(&mut self, spec: &DeclSpec, name: Symbol, ty: &Ty, span: Span, file_scope: bool) {
+        if spec.is_typedef {
+            return;
+        }
+        let is_fn = matches!(ty, Ty::Func(_));
+        let invalid = match spec.storage {
+            Some(class @ (StorageClass::Auto | StorageClass::Register)) if file_scope || is_fn => Some(class.as_str()),
+            Some(StorageClass::Static) if is_fn && !file_scope => Some("static"),
+            _ if spec.is_thread_local && (is_fn || !file_scope && spec.storage.is_none()) => Some("_Thread_local"),
+            _ => None,
+        };
+        if let Some(class) = invalid {
+            let what = match (is_fn, file_scope) {
+                (true, _) => "function",
+                (false, true) => "file-scope variable",
+                (false, false) => "block-scope variable",
+            };
+            self.errors.push(
+                Diagnostic::error(
+                    ErrorCode::InvalidStorageClass,
+                    format!("storage class `{class}` is not allowed on {what} `{name}`"),
+                )
+                .with_primary(span, ""),
+            );
+        }
+    }
+
+    /// The type named by the specifiers of a declaration. A lone
+    /// `struct tag;` (`bare`, with no declarators) always declares a new
+    /// tag in the current scope, hiding any outer one.
+    fn decl_spec_ty(&mut self, spec: &DeclSpec, bare: bool) -> Ty {
+        if let TypeSpec::Record(RecordSpec {
+            kind,
+            tag: Some(tag),
+            members: None,
+            ..
+        }) = &spec.ty
+            && bare
+        {
+            let scope = self.scopes.last().expect("internal declare must in scope");
+            if !scope.tagged_map.contains_key(tag) {
+                return self.declare_tag(record_ctor(*kind), Some(*tag));
+            }
+        }
+        self.spec_ty(spec)
+    }
 
+    /// The type named by the declaration specifiers alone.
+    pub fn spec_ty(&mut self, spec: &DeclSpec) -> Ty {
+        match &spec.ty {
+            TypeSpec::Void => Ty::Void,
+            TypeSpec::Int(kind) => Ty::Int(*kind),
+            TypeSpec::Record(record) => self.record_ty(record),
+            TypeSpec::Enum(spec) => self.enum_ty(spec),
+            // the parser only takes a name for a type if it is a typedef
+            // in scope
+            TypeSpec::Typedef(name) => match self.lookup(*name).map(|id| &self.resolved.objs[id]) {
+                Some(obj @ Obj { kind: ObjKind::Typedef, .. }) => obj.ty.clone(),
+                _ => unreachable!("`{name}` is not a typedef name"),
+            },
+        }
+    }
+
+    fn enum_ty(&mut self, spec: &EnumSpec) -> Ty {
+        let Some(enumerators) = &spec.enumerators else {
+            let tag = spec.tag.expect("an enum without a body has a tag");
+            return match self.lookup_tag(tag) {
+                Some(ty) => self.check_tag_kind(ty, tag, "enum", spec.span),
+                // GNU C: a forward reference to an enum
+                None => self.declare_tag(Ty::Enum, Some(tag)),
+            };
+        };
+        let ty = match spec.tag {
+            Some(tag) => match self.scopes.last().expect("internal declare must in scope").tagged_map.get(&tag) {
+                Some(ty) => {
+                    let ty = self.check_tag_kind(ty.clone(), tag, "enum", spec.span);
+                    if !matches!(&ty, Ty::Enum(r) if !self.defined_enums.contains(&r.id)) {
+                        self.errors.push(
+                            Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of `{ty}`"))
+                                .with_primary(spec.span, ""),
+                        );
+                        return ty;
+                    }
+                    ty
+                }
+                None => self.declare_tag(Ty::Enum, Some(tag)),
+            },
+            None => self.declare_tag(Ty::Enum, None),
+        };
+        if let Ty::Enum(r) = &ty {
+            self.defined_enums.insert(r.id);
+        }
+        // each enumerator without a value is one more than the previous
+        let mut next = 0i64;
+        for enumerator in enumerators {
+            if let Some(expr) = &enumerator.value {
+                self.resolve_expr(expr);
+                match consteval::eval_int(&self.resolved, expr) {
+                    Ok(value) => next = value,
+                    Err(e) => self.errors.push(
+                        Diagnostic::error(
+                            ErrorCode::NotConstant,
+                            format!("value of enumerator `{}` is not an integer constant", enumerator.name),
+                        )
+                        .with_primary(e.span, e.reason),
+                    ),
                 }
             }
-            StmtKind::Null => (),
+            self.declare_enum_const(enumerator, next);
+            next = next.wrapping_add(1);
         }
+        ty
     }
 
+    fn declare_enum_const(&mut self, enumerator: &Enumerator, value: i64) -> ObjId {
+        let scope = self.scopes.last().expect("internal declare must in scope");
+        if scope.ord_map.contains_key(&enumerator.name) {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of `{}`", enumerator.name))
+                    .with_primary(enumerator.span, ""),
+            );
+        }
+        let id = self.new_obj(enumerator.name, ObjKind::EnumConst(value), Ty::INT, Linkage::None);
+        let scope = self.scopes.last_mut().expect("internal declare must in scope");
+        scope.ord_map.insert(enumerator.name, id);
+        id
+    }
+
+    fn record_ty(&mut self, record: &RecordSpec) -> Ty {
+        let Some(members) = &record.members else {
+            let tag = record.tag.expect("a record without a body has a tag");
+            return match self.lookup_tag(tag) {
+                Some(ty) => self.check_tag_kind(ty, tag, record_keyword(record.kind), record.span),
+                // first mention of the tag: an incomplete type to be
+                // completed later
+                None => self.declare_tag(record_ctor(record.kind), Some(tag)),
+            };
+        };
+        let ty = match record.tag {
+            Some(tag) => match self.scopes.last().expect("internal declare must in scope").tagged_map.get(&tag) {
+                Some(ty) => {
+                    let ty = self.check_tag_kind(ty.clone(), tag, record_keyword(record.kind), record.span);
+                    if self.resolved.tcx.is_complete(&ty) {
+                        self.errors.push(
+                            Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of `{ty}`"))
+                                .with_primary(record.span, ""),
+                        );
+                        return ty;
+                    }
+                    ty
+                }
+                None => self.declare_tag(record_ctor(record.kind), Some(tag)),
+            },
+            None => self.declare_tag(record_ctor(record.kind), None),
+        };
+        let layout = self.record_layout(record.kind, members);
+        if let Ty::Struct(r) | Ty::Union(r) = &ty {
+            self.resolved.tcx.records[r.id] = layout;
+        }
+        ty
+    }
+
+    /// Lays out the members in declaration order (C11 6.7.2.1): each at
+    /// the next offset suiting its alignment for a struct, all at offset 0
+    /// for a union; the size is rounded up to the largest alignment.
+    fn record_layout(&mut self, kind: RecordKind, decls: &[MemberDecl]) -> RecordLayout {
+        let mut layout = RecordLayout {
+            align: 1,
+            is_complete: true,
+            ..RecordLayout::default()
+        };
+        for decl in decls {
+            let base = self.spec_ty(&decl.spec);
+            let mut fields = vec![];
+            if decl.declarators.is_empty() {
+                // an anonymous struct or union lends its members to this one
+                if let TypeSpec::Record(RecordSpec { tag: None, .. }) = &decl.spec.ty {
+                    fields.push((None, base.clone()));
+                }
+            }
+            for declarator in &decl.declarators {
+                let name = declarator.function_name().expect("declarator without a name");
+                let ty = self.declarator_ty(declarator, base.clone());
+                // a flexible array member, as in `int data[]`, takes no space
+                let sized = match &ty {
+                    Ty::Array(elem, None) => elem,
+                    ty => ty,
+                };
+                if !self.resolved.tcx.is_complete(sized) || matches!(ty, Ty::Func(_)) {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::IncompleteType, format!("field `{name}` has incomplete type `{ty}`"))
+                            .with_primary(declarator.span, ""),
+                    );
+                    continue;
+                }
+                fields.push((Some(name), ty));
+            }
+            for (name, ty) in fields {
+                let tcx = &self.resolved.tcx;
+                let (size, align) = (tcx.size_of(&ty), tcx.align_of(&ty));
+                let offset = match kind {
+                    RecordKind::Struct => layout.size.next_multiple_of(align),
+                    RecordKind::Union => 0,
+                };
+                match name {
+                    Some(name) => layout.members.push(Member { name, ty, offset }),
+                    None => {
+                        let (Ty::Struct(r) | Ty::Union(r)) = ty else { unreachable!() };
+                        let inner = tcx.records[r.id].members.iter().map(|m| Member {
+                            offset: offset + m.offset,
+                            ..m.clone()
+                        });
+                        layout.members.extend(inner.collect::<Vec<_>>());
+                    }
+                }
+                layout.size = layout.size.max(offset + size);
+                layout.align = layout.align.max(align);
+            }
+        }
+        layout.size = layout.size.next_multiple_of(layout.align);
+        layout
+    }
+
+    /// Reports a tag used with a different keyword than it was declared
+    /// with, such as `union s` after `struct s { ... }`.
+    fn check_tag_kind(&mut self, ty: Ty, tag: Symbol, keyword: &str, span: Span) -> Ty {
+        if ty.tag_keyword() != Some(keyword) {
+            self.errors.push(
+                Diagnostic::error(
+                    ErrorCode::TagMismatch,
+                    format!("`{tag}` was declared as `{ty}`, not as {} {keyword}", if keyword == "enum" { "an" } else { "a" }),
+                )
+                .with_primary(span, ""),
+            );
+        }
+        ty
+    }
+
+    /// Creates a new tagged type, incomplete if a struct or union, and
+    /// binds `tag` to it in the current scope.
+    fn declare_tag(&mut self, ctor: fn(TagRef) -> Ty, tag: Option<Symbol>) -> Ty {
+        let records = &mut self.resolved.tcx.records;
+        let r = TagRef { id: records.len(), tag };
+        let ty = ctor(r);
+        records.push(RecordLayout {
+            align: 1,
+            is_complete: matches!(ty, Ty::Enum(_)),
+            ..RecordLayout::default()
+        });
+        if let Some(tag) = tag {
+            let scope = self.scopes.last_mut().expect("internal declare must in scope");
+            scope.tagged_map.insert(tag, ty.clone());
+        }
+        ty
+    }
+
+    pub fn lookup_tag(&self, tag: Symbol) -> Option<Ty> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.tagged_map.get(&tag).cloned())
+    }
+
+    /// The type a declarator gives to its name, where `base` is the type
+    /// from the declaration specifiers.
+    pub fn declarator_ty(&mut self, decl: &Declarator, base: Ty) -> Ty {
+        let mut ty = base;
+        let mut ptr = decl.ptr.as_deref();
+        while let Some(p) = ptr {
+            ty = Ty::ptr_to(ty);
+            ptr = p.inner.as_deref();
+        }
+        // the suffix written last binds closest to the base type
+        let mut direct = &decl.direct;
+        loop {
+            match direct {
+                DirectDeclarator::Ident(_) | DirectDeclarator::Abstract => return ty,
+                DirectDeclarator::Paren(inner) => return self.declarator_ty(inner, ty),
+                DirectDeclarator::Func { inner, params, variadic, prototyped } => {
+                    // array and function parameters are adjusted to
+                    // pointers (C11 6.7.6.3p7-8)
+                    let params = params
+                        .iter()
+                        .map(|param| {
+                            let base = self.spec_ty(&param.spec);
+                            match &param.declarator {
+                                Some(d) => self.declarator_ty(d, base).decay(),
+                                None => base.decay(),
+                            }
+                        })
+                        .collect();
+                    ty = Ty::Func(Box::new(FnTy {
+                        ret: ty,
+                        params,
+                        variadic: *variadic,
+                        prototyped: *prototyped,
+                    }));
+                    direct = inner;
+                }
+                DirectDeclarator::Array { inner, size } => {
+                    let len = size.as_deref().map(|size| self.array_len(size));
+                    ty = Ty::Array(Box::new(ty), len);
+                    direct = inner;
+                }
+            }
+        }
+    }
+
+    fn array_len(&mut self, size: &Expr) -> usize {
+        self.resolve_expr(size);
+        match consteval::eval_int(&self.resolved, size) {
+            Ok(len) if len >= 0 => len as usize,
+            Ok(_) => {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::InvalidArraySize, "array size is negative")
+                        .with_primary(size.span, ""),
+                );
+                0
+            }
+            Err(e) => {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::InvalidArraySize, "array size is not an integer constant")
+                        .with_primary(e.span, e.reason)
+                        .with_note("variable length arrays are not supported"),
+                );
+                0
+            }
+        }
+    }
+
+    /// Binds every name used in `expr` to the object it refers to.
     pub fn resolve_expr(&mut self, expr: &Expr) {
         match &expr.kind {
-            ExprKind::Assign(a, b) => {
+            ExprKind::Assign(a, b)
+            | ExprKind::AssignOp(_, a, b)
+            | ExprKind::Binary(_, a, b)
+            | ExprKind::VaCopy(a, b) => {
                 self.resolve_expr(a.as_ref());
                 self.resolve_expr(b.as_ref());
             }
-            ExprKind::Binary(_, a, b) => {
-                self.resolve_expr(a.as_ref());
-                self.resolve_expr(b.as_ref());
+            ExprKind::Cond(cond, then, els) => {
+                self.resolve_expr(cond.as_ref());
+                self.resolve_expr(then.as_ref());
+                self.resolve_expr(els.as_ref());
             }
-            ExprKind::FnCall(sym, params) => {
-                let id = expr.id;
-                let obj = self
-                    .lookup(sym)
-                    .expect("identfier must be declared before use");
-                self.resolved.expr_resolutions.insert(id, obj);
-                for expr in params {
-                    self.resolve_expr(expr);
+            ExprKind::FnCall(callee, args) => {
+                // only a call may name a function before its declaration
+                match &callee.kind {
+                    ExprKind::Var(sym) if self.lookup(*sym).is_none() => {
+                        let obj = self.declare_called_fn(*sym, callee.span);
+                        self.resolved.expr_resolutions.insert(callee.id, obj);
+                    }
+                    _ => self.resolve_expr(callee),
+                }
+                for arg in args {
+                    self.resolve_expr(arg);
                 }
             }
-            ExprKind::Unary(_, expr) => {
+            ExprKind::Unary(_, expr)
+            | ExprKind::Member(expr, _)
+            | ExprKind::SizeofExpr(expr)
+            | ExprKind::VaEnd(expr) => {
                 self.resolve_expr(expr.as_ref());
             }
+            ExprKind::VaStart(ap, last) => {
+                self.resolve_expr(ap);
+                self.resolve_expr(last);
+                let fn_ty = self.operating_fn.as_ref().map(|func| &self.resolved.objs[func.fn_id].ty);
+                if !matches!(fn_ty, Some(Ty::Func(func)) if func.variadic) {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::MisplacedVaStart, "`va_start` used in function with fixed arguments")
+                            .with_primary(expr.span, "")
+                            .with_note("only a function whose parameter list ends in `...` has variable arguments"),
+                    );
+                } else if let Some(func) = &self.operating_fn
+                    && !(matches!(last.kind, ExprKind::Var(_))
+                        && self.resolved.expr_resolutions.get(&last.id) == func.params.last())
+                {
+                    // the arguments are found from the last named one
+                    // (C11 7.16.1.4p4)
+                    let param = func
+                        .params
+                        .last()
+                        .map(|&id| self.resolved.objs[id].name)
+                        .filter(|name| !name.as_str().is_empty());
+                    let mut diag = Diagnostic::error(
+                        ErrorCode::MisplacedVaStart,
+                        "second argument to `va_start` is not the last named parameter",
+                    )
+                    .with_primary(last.span, "");
+                    if let Some(param) = param {
+                        diag = diag.with_help(format!("pass `{param}` instead"));
+                    }
+                    self.errors.push(diag);
+                }
+            }
+            ExprKind::VaArg(ap, name) => {
+                self.resolve_expr(ap);
+                self.resolve_type_name(name);
+            }
+            ExprKind::Cast(name, expr) => {
+                self.resolve_type_name(name);
+                self.resolve_expr(expr);
+            }
+            ExprKind::SizeofType(name) | ExprKind::Alignof(name) => self.resolve_type_name(name),
             ExprKind::Var(sym) => {
                 let id = expr.id;
-                let obj = self
-                    .lookup(sym)
-                    .expect("identfier must be declared before use");
-                self.resolved.expr_resolutions.insert(id, obj);
+                match self.lookup(*sym) {
+                    Some(obj) if let ObjKind::Typedef = self.resolved.objs[obj].kind => {
+                        self.errors.push(
+                            Diagnostic::error(
+                                ErrorCode::TypeNameInExpr,
+                                format!("unexpected type name `{sym}`: expected expression"),
+                            )
+                            .with_primary(expr.span, ""),
+                        );
+                    }
+                    Some(obj) => {
+                        self.resolved.expr_resolutions.insert(id, obj);
+                    }
+                    None => {
+                        self.errors.push(
+                            Diagnostic::error(
+                                ErrorCode::UndeclaredIdentifier,
+                                format!("use of undeclared identifier `{sym}`"),
+                            )
+                            .with_primary(expr.span, "not found in this scope")
+                            .with_help(format!("declare it first, e.g. `int {sym};`")),
+                        );
+                    }
+                }
             }
             ExprKind::Literal(_) | ExprKind::Error => (),
         }
     }
 
-    pub fn declare_local(&mut self, name: &Symbol) -> ObjId {
-        let obj = Obj {
-            id: self.obj_cnt,
-            name: name.clone(),
-            kind: ObjKind::Local,
-        };
+    /// Declares the undeclared function a call names: one defined further
+    /// down, which gives it its type, or else an implicit `int f()`.
+    fn declare_called_fn(&mut self, sym: Symbol, span: Span) -> ObjId {
+        if self.later_fns.contains(&sym) {
+            if let Some(&id) = self.linked.get(&sym) {
+                return id;
+            }
+            let id = self.declare_fn(sym, Ty::INT, false, span);
+            self.hoisted_fns.insert(id);
+            return id;
+        }
+        self.errors.push(
+            Diagnostic::warning(ErrorCode::ImplicitFnDecl, format!("implicit declaration of function `{sym}`"))
+                .with_primary(span, "")
+                .with_note("the function is assumed to be defined in another translation unit"),
+        );
+        // an undeclared function is taken to be `int f()`, declared in the
+        // innermost block
+        let ty = Ty::Func(Box::new(FnTy {
+            ret: Ty::INT,
+            params: vec![],
+            variadic: false,
+            prototyped: false,
+        }));
+        let id = self.declare_fn(sym, ty, false, span);
+        let scope = self.scopes.last_mut().expect("internal declare must in scope");
+        scope.ord_map.insert(sym, id);
+        id
+    }
+
+    /// Records the type a type name stands for; a struct, union or enum
+    /// body in it declares its tag in the current scope.
+    fn resolve_type_name(&mut self, name: &TypeName) {
+        let base = self.spec_ty(&name.spec);
+        let ty = self.declarator_ty(&name.declarator, base);
+        self.resolved.type_names.insert(name.declarator.id, ty);
+    }
+
+    pub fn declare_local(&mut self, name: Symbol, ty: Ty, span: Span) -> ObjId {
+        self.check_unique(name, span);
+        let id = self.new_obj(name, ObjKind::Local, ty, Linkage::None);
         let scope = self
             .scopes
             .last_mut()
             .expect("internal declare must in scope");
-        scope.ord_map.insert(obj.name.clone(), obj.id);
-        let id = obj.id;
-        self.resolved.objs.push(obj);
-        self.obj_cnt += 1;
+        scope.ord_map.insert(name, id);
         self.operating_fn.as_mut().unwrap().locals.push(id);
         id
     }
 
-    pub fn declare_param(&mut self, name: &Symbol) -> ObjId {
-        let obj = Obj {
-            id: self.obj_cnt,
-            name: name.clone(),
-            kind: ObjKind::Param,
-        };
+    pub fn declare_param(&mut self, name: Symbol, ty: Ty, span: Span) -> ObjId {
+        if !name.as_str().is_empty() {
+            self.check_unique(name, span);
+        }
+        let id = self.new_obj(name, ObjKind::Param, ty, Linkage::None);
         let scope = self
             .scopes
             .last_mut()
             .expect("internal declare must in scope");
-        scope.ord_map.insert(obj.name.clone(), obj.id);
-        let id = obj.id;
-        self.resolved.objs.push(obj);
-        self.obj_cnt += 1;
+        scope.ord_map.insert(name, id);
         self.operating_fn.as_mut().unwrap().params.push(id);
         id
     }
 
-    pub fn declare_fn(&mut self, name: &Symbol) -> ObjId {
-        let obj = Obj {
-            id: self.obj_cnt,
-            name: name.clone(),
-            kind: ObjKind::Func,
+    /// Reports a second declaration of an object without linkage in the
+    /// same block.
+    fn check_unique(&mut self, name: Symbol, span: Span) {
+        let scope = self.scopes.last().expect("internal declare must in scope");
+        if scope.ord_map.contains_key(&name) {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of `{name}`")).with_primary(span, ""),
+            );
+        }
+    }
+
+    /// Binds a typedef name to the type it stands for in the current scope.
+    pub fn declare_typedef(&mut self, name: Symbol, ty: Ty) -> ObjId {
+        let id = self.new_obj(name, ObjKind::Typedef, ty, Linkage::None);
+        let scope = self
+            .scopes
+            .last_mut()
+            .expect("internal declare must in scope");
+        scope.ord_map.insert(name, id);
+        id
+    }
+
+    /// Declares a variable with linkage: one at file scope, or an `extern`
+    /// one in a block, which the caller makes visible in the block only.
+    /// Declaring it again refers to the same object and must agree on its
+    /// type; at most one of the declarations may have an initializer, and
+    /// only a declaration that is not `extern` or has one defines the
+    /// object.
+    fn declare_global(&mut self, decl: &VarDecl, name: Symbol, mut ty: Ty, spec: &DeclSpec) -> ObjId {
+        let init = decl.init.as_ref().and_then(|init| {
+            self.resolve_init(init);
+            self.static_init(&mut ty, init)
+        });
+        let is_extern = spec.storage == Some(StorageClass::Extern);
+        if !is_extern {
+            self.check_complete(name, &ty, decl.declarator.span);
+        }
+        let id = match self.prior_decl(name) {
+            Some(id) if let ObjKind::Global = self.resolved.objs[id].kind => {
+                // `static` on any declaration makes the linkage internal;
+                // a later `extern` keeps it
+                if spec.storage == Some(StorageClass::Static) {
+                    self.resolved.objs[id].linkage = Linkage::Internal;
+                }
+                self.merge_ty(id, ty.clone(), decl.declarator.span);
+                id
+            }
+            Some(id) => {
+                self.report_kind_clash(name, decl.declarator.span);
+                return id;
+            }
+            None => {
+                let linkage = match spec.storage {
+                    Some(StorageClass::Static) => Linkage::Internal,
+                    _ => Linkage::External,
+                };
+                let id = self.new_obj(name, ObjKind::Global, ty.clone(), linkage);
+                self.linked.insert(name, id);
+                id
+            }
         };
-        self.scopes[0].ord_map.insert(obj.name.clone(), obj.id);
-        let id = obj.id;
-        self.resolved.objs.push(obj);
+        if self.scopes.len() == 1 {
+            self.scopes[0].ord_map.insert(name, id);
+        }
+        if !is_extern || init.is_some() {
+            self.define_static(id, ty, init, spec, decl.declarator.span);
+        }
+        id
+    }
+
+    /// Declares a block-scope `static` variable: an object of its own with
+    /// static storage and no linkage.
+    fn declare_static_local(&mut self, decl: &VarDecl, name: Symbol, mut ty: Ty, spec: &DeclSpec) -> ObjId {
+        let init = decl.init.as_ref().and_then(|init| {
+            self.resolve_init(init);
+            self.static_init(&mut ty, init)
+        });
+        self.check_complete(name, &ty, decl.declarator.span);
+        self.check_unique(name, decl.declarator.span);
+        let id = self.new_obj(name, ObjKind::Global, ty.clone(), Linkage::None);
+        let scope = self.scopes.last_mut().expect("internal declare must in scope");
+        scope.ord_map.insert(name, id);
+        self.define_static(id, ty, init, spec, decl.declarator.span);
+        id
+    }
+
+    /// Records a definition of the static object `id`, merging it with an
+    /// earlier tentative definition.
+    fn define_static(&mut self, id: ObjId, ty: Ty, init: Option<GlobalInit>, spec: &DeclSpec, span: Span) {
+        let name = self.resolved.objs[id].name;
+        match self.resolved.globals.iter_mut().find(|g| g.id == id) {
+            Some(global) if global.init.is_some() && init.is_some() => self.errors.push(
+                Diagnostic::error(ErrorCode::Redefinition, format!("redefinition of `{name}`")).with_primary(span, ""),
+            ),
+            Some(global) => {
+                if init.is_some() {
+                    global.init = init;
+                }
+            }
+            None => {
+                // `const` applies to the object itself unless the
+                // declarator made it a pointer
+                let mut base = &ty;
+                while let Ty::Array(elem, _) = base {
+                    base = elem;
+                }
+                let read_only = spec.quals.is_const && !base.is_ptr();
+                self.resolved.globals.push(Global {
+                    id,
+                    init,
+                    read_only,
+                    is_thread_local: spec.is_thread_local,
+                });
+            }
+        }
+    }
+
+    /// Reports an object defined with an incomplete type. Only a tentative
+    /// definition at file scope may leave an array's length for a later
+    /// declaration.
+    fn check_complete(&mut self, name: Symbol, ty: &Ty, span: Span) {
+        let sized = match ty {
+            Ty::Array(elem, None) if self.scopes.len() == 1 => elem,
+            ty => ty,
+        };
+        if let Ty::Array(_, None) = sized {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::InvalidArraySize, format!("array size missing in `{name}`"))
+                    .with_primary(span, ""),
+            );
+        } else if !self.resolved.tcx.is_complete(sized) {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::IncompleteType, format!("variable `{name}` has incomplete type `{ty}`"))
+                    .with_primary(span, ""),
+            );
+        }
+    }
+
+    /// Evaluates the initializer of an object with static storage of type
+    /// `ty`, completing an array of unknown size from it.
+    fn static_init(&mut self, ty: &mut Ty, init: &Initializer) -> Option<GlobalInit> {
+        let fields = self.layout_init(ty, init);
+        if let Initializer::Expr(expr) = init {
+            return self.global_init(ty, expr);
+        }
+        let mut values: Vec<(usize, Ty, GlobalInit)> = vec![];
+        let mut complete = true;
+        for (expr, offset, mut ty) in fields {
+            let Some(value) = self.global_init(&mut ty, expr) else {
+                complete = false;
+                continue;
+            };
+            // a later initializer of the same bytes replaces the earlier
+            let tcx = &self.resolved.tcx;
+            let end = offset + tcx.size_of(&ty);
+            values.retain(|(other, other_ty, _)| other + tcx.size_of(other_ty) <= offset || end <= *other);
+            values.push((offset, ty, value));
+        }
+        values.sort_by_key(|(offset, ..)| *offset);
+        complete.then_some(GlobalInit::List(values))
+    }
+
+    /// Evaluates one initializing expression for an element of type `ty`,
+    /// completing an array of unknown size from a string literal.
+    fn global_init(&mut self, ty: &mut Ty, init: &Expr) -> Option<GlobalInit> {
+        if let Some(units) = str_array_init(&self.resolved.tcx, ty, init) {
+            return Some(GlobalInit::Str(units));
+        }
+        match consteval::eval(&self.resolved, init) {
+            Ok(ConstValue::Int(value)) => Some(GlobalInit::Int(value)),
+            // an address fits only a pointer or an integer of the same width
+            Ok(ConstValue::Addr(addr)) if ty.is_ptr() || (ty.is_integer() && self.resolved.tcx.size_of(ty) == 8) => {
+                Some(GlobalInit::Addr(addr))
+            }
+            Ok(ConstValue::Addr(_)) => {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::NotConstant, "initializer element is not computable at load time")
+                        .with_primary(init.span, format!("an address does not fit in `{ty}`")),
+                );
+                None
+            }
+            Err(e) => {
+                self.errors.push(
+                    Diagnostic::error(ErrorCode::NotConstant, "initializer element is not a compile-time constant")
+                        .with_primary(e.span, e.reason),
+                );
+                None
+            }
+        }
+    }
+
+    /// Declares a function, at file scope or in a block, where the caller
+    /// makes it visible. Declaring it again reuses the object and must
+    /// agree on its type. A function is `static` if any of its
+    /// declarations is.
+    pub fn declare_fn(&mut self, name: Symbol, ty: Ty, is_static: bool, span: Span) -> ObjId {
+        let linkage = if is_static { Linkage::Internal } else { Linkage::External };
+        let id = match self.prior_decl(name) {
+            Some(id) if let ObjKind::Func = self.resolved.objs[id].kind => {
+                if self.hoisted_fns.remove(&id) {
+                    self.resolved.objs[id].ty = ty;
+                } else {
+                    self.merge_ty(id, ty, span);
+                }
+                if is_static {
+                    self.resolved.objs[id].linkage = linkage;
+                }
+                id
+            }
+            Some(id) => {
+                self.report_kind_clash(name, span);
+                return id;
+            }
+            None => {
+                let id = self.new_obj(name, ObjKind::Func, ty, linkage);
+                self.linked.insert(name, id);
+                id
+            }
+        };
+        if self.scopes.len() == 1 {
+            self.scopes[0].ord_map.insert(name, id);
+        }
+        id
+    }
+
+    /// The object an earlier declaration of `name` with linkage declared,
+    /// or at file scope whatever else the name stands for there.
+    fn prior_decl(&self, name: Symbol) -> Option<ObjId> {
+        match self.linked.get(&name) {
+            Some(&id) => Some(id),
+            None if self.scopes.len() == 1 => self.scopes[0].ord_map.get(&name).copied(),
+            None => None,
+        }
+    }
+
+    /// Gives the object `id` the composite of its type and `ty`, the type
+    /// of another declaration of it.
+    fn merge_ty(&mut self, id: ObjId, ty: Ty, span: Span) {
+        let obj = &mut self.resolved.objs[id];
+        if obj.ty.is_compatible(&ty) {
+            obj.ty = std::mem::replace(&mut obj.ty, Ty::Void).composite(ty);
+        } else {
+            let (name, prev) = (obj.name, obj.ty.clone());
+            self.errors.push(
+                Diagnostic::error(ErrorCode::Redefinition, format!("conflicting types for `{name}`"))
+                    .with_primary(span, format!("declared here as `{ty}`"))
+                    .with_note(format!("previously declared as `{prev}`")),
+            );
+        }
+    }
+
+    fn report_kind_clash(&mut self, name: Symbol, span: Span) {
+        self.errors.push(
+            Diagnostic::error(
+                ErrorCode::Redefinition,
+                format!("redefinition of `{name}` as a different kind of symbol"),
+            )
+            .with_primary(span, ""),
+        );
+    }
+
+    fn new_obj(&mut self, name: Symbol, kind: ObjKind, ty: Ty, linkage: Linkage) -> ObjId {
+        let id = self.obj_cnt;
+        self.resolved.objs.push(Obj {
+            id,
+            name,
+            kind,
+            ty,
+            linkage,
+        });
         self.obj_cnt += 1;
         id
     }
 
-    pub fn lookup(&self, id: &Symbol) -> Option<ObjId> {
+    pub fn lookup(&self, id: Symbol) -> Option<ObjId> {
         self.scopes
             .iter()
             .rev()
-            .find_map(|scope| scope.ord_map.get(id).copied())
+            .find_map(|scope| scope.ord_map.get(&id).copied())
+    }
+}
+
+/// The code units a char array of type `ty` holds when initialized from
+/// the string literal `init`, truncated or zero-padded to its length. An
+/// array of unknown size takes the length of the string.
+pub fn str_array_init(tcx: &TyCtx, ty: &mut Ty, init: &Expr) -> Option<Vec<u32>> {
+    let (
+        Ty::Array(elem, len),
+        ExprKind::Literal(Lit {
+            kind: LitKind::Str(_),
+            symbol,
+            ..
+        }),
+    ) = (ty, &init.kind)
+    else {
+        return None;
+    };
+    if !elem.is_integer() {
+        return None;
+    }
+    let (_, units) = unescape_str(symbol.as_str());
+    let mut units = stored_units(&units, tcx.size_of(elem));
+    let len = len.get_or_insert(units.len() + 1);
+    units.resize(*len, 0);
+    Some(units)
+}
+
+fn record_ctor(kind: RecordKind) -> fn(TagRef) -> Ty {
+    match kind {
+        RecordKind::Struct => Ty::Struct,
+        RecordKind::Union => Ty::Union,
+    }
+}
+
+fn record_keyword(kind: RecordKind) -> &'static str {
+    match kind {
+        RecordKind::Struct => "struct",
+        RecordKind::Union => "union",
     }
 }
@@ -1,4 +1,5 @@
 pub mod source_map;
+pub mod symbol;
 
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub struct Span {
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::path::PathBuf;
 
 pub enum FileName {
@@ -5,6 +6,15 @@ pub enum FileName {
     Cli,
 }
 
+impl fmt::Display for FileName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileName::Real(path) => write!(f, "{}", path.display()),
+            FileName::Cli => f.write_str("<cli>"),
+        }
+    }
+}
+
 pub struct SourceFile {
     pub name: FileName,
     pub src: String,
@@ -29,7 +39,7 @@ impl SourceFile {
     }
 
     pub fn lookup_line(&self, pos: usize) -> Option<usize> {
-        self.lines.partition_point(|x| x < &pos).checked_sub(1)
+        self.lines.partition_point(|x| x <= &pos).checked_sub(1)
     }
 
     pub fn lookup_line_column(&self, pos: usize) -> (usize, usize) {
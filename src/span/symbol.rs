@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::tokenizer::KEYWORDS;
+
+/// An interned string. Comparing and hashing a `Symbol` is an integer
+/// operation; `as_str` gives the text back.
+///
+/// Symbols belong to the interner of the thread that created them. The
+/// keywords are interned first, in `KEYWORDS` order, so a symbol's index
+/// tells whether it is a keyword (see `KeywordKind::from_symbol`).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn intern(text: &str) -> Symbol {
+        INTERNER.with(|interner| interner.borrow_mut().intern(text))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        INTERNER.with(|interner| interner.borrow().strings[self.0 as usize])
+    }
+
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+struct Interner {
+    names: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+}
+
+impl Interner {
+    fn prefill(init: impl Iterator<Item = &'static str>) -> Interner {
+        let mut interner = Interner {
+            names: HashMap::new(),
+            strings: vec![],
+        };
+        for text in init {
+            interner.insert(text);
+        }
+        interner
+    }
+
+    fn intern(&mut self, text: &str) -> Symbol {
+        if let Some(&sym) = self.names.get(text) {
+            return sym;
+        }
+        // strings live as long as the compiler process, so leaking them is
+        // cheaper than tracking an arena
+        self.insert(Box::leak(text.into()))
+    }
+
+    fn insert(&mut self, text: &'static str) -> Symbol {
+        let sym = Symbol(self.strings.len() as u32);
+        self.strings.push(text);
+        self.names.insert(text, sym);
+        sym
+    }
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::prefill(KEYWORDS.iter().map(|(text, _)| *text)));
+}
@@ -1,32 +1,113 @@
-﻿use core::panic;
+﻿use std::fmt;
 
+use crate::error_handler::{Diagnostic, ErrorCode};
 use crate::span::*;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LitKind {
-    Integer,
-    Str,
+    Integer(IntForm),
+    Char(Encoding),
+    Str(Encoding),
 }
 
-pub type Symbol = String;
+/// How an integer constant was written. Together with its value this
+/// decides the constant's type, see `IntForm::ty`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntForm {
+    pub decimal: bool,
+    pub unsigned: bool,
+    /// 0 without a long suffix, 1 for `l`, 2 for `ll`
+    pub longs: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntTy {
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+}
 
-#[derive(Debug, Clone, PartialEq)]
+/// The encoding prefix of a character constant or string literal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Plain,
+    /// `u8`
+    Utf8,
+    /// `L`
+    Wide,
+    /// `u`
+    Utf16,
+    /// `U`
+    Utf32,
+}
+
+pub use crate::span::symbol::Symbol;
+
+/// A literal token. `symbol` is the source text; for string literals it
+/// includes prefix and quotes, with adjacent literals joined by a space (use
+/// `unescape_str` to decode it).
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Lit {
     pub kind: LitKind,
-    pub symbol: String,
+    pub symbol: Symbol,
+    /// Value of an integer or character constant as two's complement bits;
+    /// always 0 for string literals.
+    pub value: u64,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// Every keyword of C11 (6.4.1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KeywordKind {
-    Return,
-    If,
+    Auto,
+    Break,
+    Case,
+    Char,
+    Const,
+    Continue,
+    Default,
+    Do,
+    Double,
     Else,
-    While,
+    Enum,
+    Extern,
+    Float,
     For,
+    Goto,
+    If,
+    Inline,
     Int,
+    Long,
+    Register,
+    Restrict,
+    Return,
+    Short,
+    Signed,
+    Sizeof,
+    Static,
+    Struct,
+    Switch,
+    Typedef,
+    Union,
+    Unsigned,
+    Void,
+    Volatile,
+    While,
+    Alignas,
+    Alignof,
+    Atomic,
+    Bool,
+    Complex,
+    Generic,
+    Imaginary,
+    Noreturn,
+    StaticAssert,
+    ThreadLocal,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TokenKind {
     Keyword(KeywordKind),
     Literal(Lit),
@@ -35,10 +116,13 @@ pub enum TokenKind {
     Sub,
     Mul,
     Div,
+    Mod,
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
     Eq,
     EqEq,
     Ne,
@@ -49,25 +133,176 @@ pub enum TokenKind {
     Semi,
     Comma,
     And,
-    Reserved(String),
+    Or,
+    Caret,
+    Tilde,
+    Bang,
+    AndAnd,
+    OrOr,
+    Shl,
+    Shr,
+    Question,
+    Colon,
+    Inc,
+    Dec,
+    Arrow,
+    Dot,
+    Ellipsis,
+    AddEq,
+    SubEq,
+    MulEq,
+    DivEq,
+    ModEq,
+    AndEq,
+    OrEq,
+    CaretEq,
+    ShlEq,
+    ShrEq,
+    Reserved(Symbol),
+    /// A byte sequence the lexer could not make sense of; a diagnostic has
+    /// already been recorded for it.
+    Error,
     Eof,
 }
 
+/// Every punctuator, longest first so that a linear scan finds the
+/// longest match (`<<=` before `<<` before `<`).
+static PUNCTUATORS: &[(&str, TokenKind)] = &[
+    ("...", TokenKind::Ellipsis),
+    ("<<=", TokenKind::ShlEq),
+    (">>=", TokenKind::ShrEq),
+    ("==", TokenKind::EqEq),
+    ("!=", TokenKind::Ne),
+    ("<=", TokenKind::Le),
+    (">=", TokenKind::Ge),
+    ("&&", TokenKind::AndAnd),
+    ("||", TokenKind::OrOr),
+    ("<<", TokenKind::Shl),
+    (">>", TokenKind::Shr),
+    ("++", TokenKind::Inc),
+    ("--", TokenKind::Dec),
+    ("->", TokenKind::Arrow),
+    ("+=", TokenKind::AddEq),
+    ("-=", TokenKind::SubEq),
+    ("*=", TokenKind::MulEq),
+    ("/=", TokenKind::DivEq),
+    ("%=", TokenKind::ModEq),
+    ("&=", TokenKind::AndEq),
+    ("|=", TokenKind::OrEq),
+    ("^=", TokenKind::CaretEq),
+    ("+", TokenKind::Add),
+    ("-", TokenKind::Sub),
+    ("*", TokenKind::Mul),
+    ("/", TokenKind::Div),
+    ("%", TokenKind::Mod),
+    ("=", TokenKind::Eq),
+    ("<", TokenKind::Lt),
+    (">", TokenKind::Gt),
+    ("!", TokenKind::Bang),
+    ("~", TokenKind::Tilde),
+    ("&", TokenKind::And),
+    ("|", TokenKind::Or),
+    ("^", TokenKind::Caret),
+    ("?", TokenKind::Question),
+    (":", TokenKind::Colon),
+    ("(", TokenKind::LParen),
+    (")", TokenKind::RParen),
+    ("{", TokenKind::LBrace),
+    ("}", TokenKind::RBrace),
+    ("[", TokenKind::LBracket),
+    ("]", TokenKind::RBracket),
+    (";", TokenKind::Semi),
+    (",", TokenKind::Comma),
+    (".", TokenKind::Dot),
+];
+
+impl TokenKind {
+    pub fn punct_str(&self) -> Option<&'static str> {
+        PUNCTUATORS
+            .iter()
+            .find(|(_, kind)| kind == self)
+            .map(|(text, _)| *text)
+    }
+}
+
+/// The keyword table. The interner pre-interns these in this order, so the
+/// position of a keyword here is also its `Symbol` index.
+pub(crate) static KEYWORDS: &[(&str, KeywordKind)] = &[
+    ("auto", KeywordKind::Auto),
+    ("break", KeywordKind::Break),
+    ("case", KeywordKind::Case),
+    ("char", KeywordKind::Char),
+    ("const", KeywordKind::Const),
+    ("continue", KeywordKind::Continue),
+    ("default", KeywordKind::Default),
+    ("do", KeywordKind::Do),
+    ("double", KeywordKind::Double),
+    ("else", KeywordKind::Else),
+    ("enum", KeywordKind::Enum),
+    ("extern", KeywordKind::Extern),
+    ("float", KeywordKind::Float),
+    ("for", KeywordKind::For),
+    ("goto", KeywordKind::Goto),
+    ("if", KeywordKind::If),
+    ("inline", KeywordKind::Inline),
+    ("int", KeywordKind::Int),
+    ("long", KeywordKind::Long),
+    ("register", KeywordKind::Register),
+    ("restrict", KeywordKind::Restrict),
+    ("return", KeywordKind::Return),
+    ("short", KeywordKind::Short),
+    ("signed", KeywordKind::Signed),
+    ("sizeof", KeywordKind::Sizeof),
+    ("static", KeywordKind::Static),
+    ("struct", KeywordKind::Struct),
+    ("switch", KeywordKind::Switch),
+    ("typedef", KeywordKind::Typedef),
+    ("union", KeywordKind::Union),
+    ("unsigned", KeywordKind::Unsigned),
+    ("void", KeywordKind::Void),
+    ("volatile", KeywordKind::Volatile),
+    ("while", KeywordKind::While),
+    ("_Alignas", KeywordKind::Alignas),
+    ("_Alignof", KeywordKind::Alignof),
+    ("_Atomic", KeywordKind::Atomic),
+    ("_Bool", KeywordKind::Bool),
+    ("_Complex", KeywordKind::Complex),
+    ("_Generic", KeywordKind::Generic),
+    ("_Imaginary", KeywordKind::Imaginary),
+    ("_Noreturn", KeywordKind::Noreturn),
+    ("_Static_assert", KeywordKind::StaticAssert),
+    ("_Thread_local", KeywordKind::ThreadLocal),
+];
+
 impl KeywordKind {
-    pub fn lex_keyword(token: &str) -> Option<KeywordKind> {
-        match token {
-            "return" => Some(KeywordKind::Return),
-            "if" => Some(KeywordKind::If),
-            "else" => Some(KeywordKind::Else),
-            "for" => Some(KeywordKind::For),
-            "while" => Some(KeywordKind::While),
-            "int" => Some(KeywordKind::Int),
-            _ => None,
+    pub fn from_symbol(sym: Symbol) -> Option<KeywordKind> {
+        KEYWORDS.get(sym.as_u32() as usize).map(|(_, kw)| *kw)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        KEYWORDS
+            .iter()
+            .find(|(_, kw)| kw == self)
+            .map(|(text, _)| *text)
+            .unwrap()
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Keyword(kw) => write!(f, "keyword `{}`", kw.as_str()),
+            TokenKind::Literal(lit) => write!(f, "literal `{}`", lit.symbol),
+            TokenKind::Ident(sym) => write!(f, "identifier `{sym}`"),
+            TokenKind::Reserved(s) => write!(f, "`{s}`"),
+            TokenKind::Error => f.write_str("invalid token"),
+            TokenKind::Eof => f.write_str("end of file"),
+            punct => write!(f, "`{}`", punct.punct_str().unwrap_or("?")),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
@@ -75,263 +310,895 @@ pub struct Token {
 
 pub type TokenContainer = Vec<Token>;
 
-pub fn parse_next_number(s: &[u8], cursor: &mut usize) -> String {
-    let start = *cursor;
-    while let Some(c) = s.get(*cursor) {
-        if !c.is_ascii_digit() {
-            break;
+impl IntForm {
+    /// The type of an integer constant (C11 6.4.4.1): the first of the
+    /// candidate types that can represent `value`, where `long` is
+    /// `long_bits` wide. `None` if not even `unsigned long long` can.
+    pub fn ty(&self, value: u64, long_bits: u32) -> Option<IntTy> {
+        use IntTy::*;
+        let candidates: &[IntTy] = match (self.unsigned, self.longs, self.decimal) {
+            (false, 0, true) => &[Int, Long, LongLong],
+            (false, 0, false) => &[Int, UInt, Long, ULong, LongLong, ULongLong],
+            (true, 0, _) => &[UInt, ULong, ULongLong],
+            (false, 1, true) => &[Long, LongLong],
+            (false, 1, false) => &[Long, ULong, LongLong, ULongLong],
+            (true, 1, _) => &[ULong, ULongLong],
+            (false, _, true) => &[LongLong],
+            (false, _, false) => &[LongLong, ULongLong],
+            (true, _, _) => &[ULongLong],
+        };
+        candidates.iter().copied().find(|ty| {
+            let max = match ty {
+                Int => i32::MAX as u64,
+                UInt => u32::MAX as u64,
+                Long if long_bits == 32 => i32::MAX as u64,
+                ULong if long_bits == 32 => u32::MAX as u64,
+                Long | LongLong => i64::MAX as u64,
+                ULong | ULongLong => u64::MAX,
+            };
+            value <= max
+        })
+    }
+}
+
+impl IntTy {
+    pub fn is_unsigned(&self) -> bool {
+        matches!(self, IntTy::UInt | IntTy::ULong | IntTy::ULongLong)
+    }
+}
+
+fn parse_int_suffix(suffix: &[u8]) -> Option<(bool, u8)> {
+    let lower = suffix.to_ascii_lowercase();
+    // `lL` and `Ll` are not valid spellings of `ll`
+    if suffix.windows(2).any(|w| w == b"lL" || w == b"Ll") {
+        return None;
+    }
+    match lower.as_slice() {
+        b"" => Some((false, 0)),
+        b"u" => Some((true, 0)),
+        b"l" => Some((false, 1)),
+        b"ul" | b"lu" => Some((true, 1)),
+        b"ll" => Some((false, 2)),
+        b"ull" | b"llu" => Some((true, 2)),
+        _ => None,
+    }
+}
+
+/// Lexes an integer constant: decimal, `0x` hex, `0b` binary or octal with
+/// a leading zero, `'` digit separators and `u`/`l`/`ll` suffixes. A
+/// floating constant is reported as unsupported.
+fn lex_number(s: &[u8], cursor: &mut usize, errors: &mut Vec<Diagnostic>) -> Token {
+    let pos = *cursor;
+    let (radix, mut i) = match s[pos..] {
+        [b'0', b'x' | b'X', ..] => (16, pos + 2),
+        [b'0', b'b' | b'B', ..] => (2, pos + 2),
+        [b'0', ..] => (8, pos + 1),
+        _ => (10, pos),
+    };
+    let digits_start = i;
+    let mut value: Option<u64> = Some(0);
+    let mut bad_digit = None;
+    loop {
+        match s.get(i) {
+            Some(c) if c.is_ascii_hexdigit() && (radix == 16 || c.is_ascii_digit()) => {
+                let d = (*c as char).to_digit(16).unwrap();
+                if d >= radix {
+                    bad_digit.get_or_insert(i);
+                }
+                value = value
+                    .and_then(|v| v.checked_mul(u64::from(radix)))
+                    .and_then(|v| v.checked_add(u64::from(d)));
+                i += 1;
+            }
+            // a digit separator must sit between two digits
+            Some(b'\'') if i > digits_start && s.get(i + 1).is_some_and(|c| c.is_ascii_hexdigit() && (radix == 16 || c.is_ascii_digit())) => {
+                i += 1;
+            }
+            _ => break,
+        }
+    }
+    let digits_end = i;
+
+    // a fraction or exponent after decimal digits, or after a leading
+    // zero as in `0.5`, makes a floating constant
+    if radix != 16 && radix != 2 && matches!(s.get(i), Some(b'.' | b'e' | b'E')) {
+        while let Some(&c) = s.get(i) {
+            let exponent_sign = matches!(c, b'+' | b'-') && matches!(s[i - 1], b'e' | b'E');
+            if !(c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || exponent_sign) {
+                break;
+            }
+            i += 1;
+        }
+        *cursor = i;
+        let span = Span { pos, len: i - pos };
+        errors.push(
+            Diagnostic::error(ErrorCode::UnsupportedFloat, "floating-point constants are not supported")
+                .with_primary(span, ""),
+        );
+        return Token {
+            kind: TokenKind::Error,
+            span,
+        };
+    }
+
+    while s.get(i).is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_') {
+        i += 1;
+    }
+    *cursor = i;
+    let span = Span { pos, len: i - pos };
+    let symbol = Symbol::intern(&String::from_utf8_lossy(&s[pos..i]));
+    let suffix = &s[digits_end..i];
+
+    let (unsigned, longs) = match parse_int_suffix(suffix) {
+        Some(parsed) => parsed,
+        None => {
+            let suffix_span = Span {
+                pos: digits_end,
+                len: suffix.len(),
+            };
+            errors.push(
+                Diagnostic::error(
+                    ErrorCode::InvalidIntLiteral,
+                    format!("invalid suffix `{}` on integer constant", String::from_utf8_lossy(suffix)),
+                )
+                .with_primary(suffix_span, "")
+                .with_help("valid suffixes are combinations of `u` with `l` or `ll`"),
+            );
+            (false, 0)
         }
-        *cursor += 1;
+    };
+    if radix != 8 && digits_end == digits_start {
+        errors.push(
+            Diagnostic::error(
+                ErrorCode::InvalidIntLiteral,
+                format!("no digits in {} constant", if radix == 16 { "hexadecimal" } else { "binary" }),
+            )
+            .with_primary(span, ""),
+        );
+    } else if let Some(at) = bad_digit {
+        errors.push(
+            Diagnostic::error(
+                ErrorCode::InvalidIntLiteral,
+                format!(
+                    "invalid digit `{}` in {} constant",
+                    s[at] as char,
+                    if radix == 8 { "octal" } else { "binary" }
+                ),
+            )
+            .with_primary(Span { pos: at, len: 1 }, ""),
+        );
+    }
+
+    let form = IntForm {
+        decimal: radix == 10,
+        unsigned,
+        longs,
+    };
+    let value = match value {
+        Some(v) if form.ty(v, 64).is_some() => v,
+        _ => {
+            errors.push(
+                Diagnostic::error(ErrorCode::IntLiteralOverflow, "integer constant is too large for its type")
+                    .with_primary(span, "")
+                    .with_note(if form.decimal && !form.unsigned {
+                        "the largest signed decimal constant is 9223372036854775807; add a `u` suffix for larger values"
+                    } else {
+                        "the largest integer constant is 18446744073709551615"
+                    }),
+            );
+            0
+        }
+    };
+    Token {
+        kind: TokenKind::Literal(Lit {
+            kind: LitKind::Integer(form),
+            symbol,
+            value,
+        }),
+        span,
     }
-    String::from_utf8(s[start..*cursor].to_vec()).unwrap()
 }
 
-pub fn parse_next_ident(s: &[u8], cursor: &mut usize) -> String {
-    let start = *cursor;
-    while let Some(c) = s.get(*cursor) {
-        if !(c.is_ascii_digit() | c.is_ascii_alphabetic()) {
-            break;
+impl Encoding {
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Encoding::Plain => "",
+            Encoding::Utf8 => "u8",
+            Encoding::Wide => "L",
+            Encoding::Utf16 => "u",
+            Encoding::Utf32 => "U",
+        }
+    }
+
+    /// Size in bytes of one code unit; `wchar_size` comes from the target ABI.
+    pub fn unit_size(&self, wchar_size: usize) -> usize {
+        match self {
+            Encoding::Plain | Encoding::Utf8 => 1,
+            Encoding::Utf16 => 2,
+            Encoding::Utf32 => 4,
+            Encoding::Wide => wchar_size,
+        }
+    }
+
+    fn max_unit(&self) -> u32 {
+        match self {
+            Encoding::Plain | Encoding::Utf8 => 0xFF,
+            Encoding::Utf16 => 0xFFFF,
+            Encoding::Wide | Encoding::Utf32 => u32::MAX,
+        }
+    }
+
+    fn encode(&self, c: char, out: &mut Vec<u32>) {
+        match self {
+            Encoding::Plain | Encoding::Utf8 => {
+                out.extend(c.encode_utf8(&mut [0; 4]).bytes().map(u32::from));
+            }
+            Encoding::Utf16 => {
+                out.extend(c.encode_utf16(&mut [0; 2]).iter().map(|u| u32::from(*u)));
+            }
+            Encoding::Wide | Encoding::Utf32 => out.push(c as u32),
         }
-        *cursor += 1;
     }
-    String::from_utf8(s[start..*cursor].to_vec()).unwrap()
 }
 
-fn look_ahead_is(s: &[u8], cursor: usize, expected: u8) -> bool {
-    s.get(cursor).copied() == Some(expected)
+/// Recognizes an encoding prefix directly followed by a quote. `u8` only
+/// prefixes string literals (C11 6.4.5), so `u8'a'` is not a literal.
+fn lex_encoding_prefix(s: &[u8], cursor: usize) -> Option<(Encoding, usize)> {
+    let rest = &s[cursor..];
+    let (enc, len) = match rest {
+        [b'u', b'8', b'"', ..] => return Some((Encoding::Utf8, 2)),
+        [b'u', ..] => (Encoding::Utf16, 1),
+        [b'U', ..] => (Encoding::Utf32, 1),
+        [b'L', ..] => (Encoding::Wide, 1),
+        _ => (Encoding::Plain, 0),
+    };
+    matches!(rest.get(len), Some(b'"' | b'\'')).then_some((enc, len))
 }
 
-pub fn tokenize(s: &[u8]) -> TokenContainer {
-    let mut vec = Vec::new();
-    let mut cursor = 0;
-    while cursor < s.len() {
-        match s[cursor] {
-            c if c.is_ascii_whitespace() => {
-                cursor += 1;
-            }
-            b'!' => {
-                if look_ahead_is(s, cursor + 1, b'=') {
-                    vec.push(Token {
-                        kind: TokenKind::Ne,
-                        span: Span {
-                            pos: cursor,
-                            len: 2,
-                        },
-                    });
-                    cursor += 2;
-                } else {
-                    panic!("Not Supported Operator!")
+pub struct EscapeError {
+    /// Byte range of the offending escape, relative to the literal body.
+    pub offset: usize,
+    pub len: usize,
+    pub message: String,
+    pub is_warning: bool,
+}
+
+/// Decodes the body of a quoted literal (the bytes between the quotes) into
+/// code units of `enc`.
+fn unescape_body(body: &[u8], enc: Encoding, out: &mut Vec<u32>, errors: &mut Vec<EscapeError>) {
+    let mut i = 0;
+    while i < body.len() {
+        if body[i] != b'\\' {
+            let (c, len) = next_char(body, i);
+            enc.encode(c, out);
+            i += len;
+            continue;
+        }
+        let start = i;
+        i += 1;
+        let Some(&c) = body.get(i) else {
+            break;
+        };
+        i += 1;
+        let simple = match c {
+            b'\'' | b'"' | b'?' | b'\\' => Some(u32::from(c)),
+            b'a' => Some(7),
+            b'b' => Some(8),
+            b'f' => Some(12),
+            b'n' => Some(10),
+            b'r' => Some(13),
+            b't' => Some(9),
+            b'v' => Some(11),
+            _ => None,
+        };
+        if let Some(v) = simple {
+            out.push(v);
+            continue;
+        }
+        match c {
+            b'0'..=b'7' => {
+                let mut v = u32::from(c - b'0');
+                while i < body.len() && i - start < 4 && (b'0'..=b'7').contains(&body[i]) {
+                    v = v * 8 + u32::from(body[i] - b'0');
+                    i += 1;
                 }
-            }
-            b'=' => {
-                if look_ahead_is(s, cursor + 1, b'=') {
-                    vec.push(Token {
-                        kind: TokenKind::EqEq,
-                        span: Span {
-                            pos: cursor,
-                            len: 2,
-                        },
-                    });
-                    cursor += 2;
-                } else {
-                    vec.push(Token {
-                        kind: TokenKind::Eq,
-                        span: Span {
-                            pos: cursor,
-                            len: 1,
-                        },
+                if v > enc.max_unit() {
+                    errors.push(EscapeError {
+                        offset: start,
+                        len: i - start,
+                        message: "octal escape sequence out of range".to_string(),
+                        is_warning: false,
                     });
-                    cursor += 1;
                 }
+                out.push(v & enc.max_unit());
             }
-            b'>' => {
-                if look_ahead_is(s, cursor + 1, b'=') {
-                    vec.push(Token {
-                        kind: TokenKind::Ge,
-                        span: Span {
-                            pos: cursor,
-                            len: 2,
-                        },
+            b'x' => {
+                let digits = body[i..].iter().take_while(|b| b.is_ascii_hexdigit()).count();
+                let v = body[i..i + digits].iter().fold(0u64, |v, b| {
+                    (v << 4 | u64::from((*b as char).to_digit(16).unwrap())).min(u64::from(u32::MAX) + 1)
+                });
+                i += digits;
+                if digits == 0 {
+                    errors.push(EscapeError {
+                        offset: start,
+                        len: 2,
+                        message: "`\\x` used with no following hex digits".to_string(),
+                        is_warning: false,
                     });
-                    cursor += 2;
-                } else {
-                    vec.push(Token {
-                        kind: TokenKind::Gt,
-                        span: Span {
-                            pos: cursor,
-                            len: 1,
-                        },
+                } else if v > u64::from(enc.max_unit()) {
+                    errors.push(EscapeError {
+                        offset: start,
+                        len: i - start,
+                        message: "hex escape sequence out of range".to_string(),
+                        is_warning: false,
                     });
-                    cursor += 1;
                 }
+                out.push(v.min(u64::from(enc.max_unit())) as u32);
             }
-            b'<' => {
-                if look_ahead_is(s, cursor + 1, b'=') {
-                    vec.push(Token {
-                        kind: TokenKind::Le,
-                        span: Span {
-                            pos: cursor,
-                            len: 2,
+            b'u' | b'U' => {
+                let want = if c == b'u' { 4 } else { 8 };
+                let digits = body[i..].iter().take(want).take_while(|b| b.is_ascii_hexdigit()).count();
+                let text = std::str::from_utf8(&body[i..i + digits]).unwrap();
+                i += digits;
+                let ch = u32::from_str_radix(text, 16).ok().and_then(char::from_u32);
+                match ch {
+                    Some(ch) if digits == want => enc.encode(ch, out),
+                    _ => errors.push(EscapeError {
+                        offset: start,
+                        len: i - start,
+                        message: if digits == want {
+                            format!("`\\{}{text}` is not a valid universal character", c as char)
+                        } else {
+                            format!("incomplete universal character name, expected {want} hex digits")
                         },
-                    });
-                    cursor += 2;
-                } else {
-                    vec.push(Token {
-                        kind: TokenKind::Lt,
-                        span: Span {
-                            pos: cursor,
-                            len: 1,
-                        },
-                    });
-                    cursor += 1;
+                        is_warning: false,
+                    }),
                 }
             }
-            b'+' => {
-                vec.push(Token {
-                    kind: TokenKind::Add,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
-            }
-            b'-' => {
-                vec.push(Token {
-                    kind: TokenKind::Sub,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
+            _ => {
+                let (ch, len) = next_char(body, i - 1);
+                i += len - 1;
+                errors.push(EscapeError {
+                    offset: start,
+                    len: i - start,
+                    message: format!("unknown escape sequence `\\{ch}`"),
+                    is_warning: true,
                 });
-                cursor += 1;
+                enc.encode(ch, out);
             }
-            b'*' => {
-                vec.push(Token {
-                    kind: TokenKind::Mul,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
+        }
+    }
+}
+
+/// Splits the text of a (possibly concatenated) quoted literal into its
+/// encoding and the bodies of each quoted piece.
+fn quoted_pieces(raw: &str) -> (Encoding, Vec<&[u8]>) {
+    let s = raw.as_bytes();
+    let mut enc = Encoding::Plain;
+    let mut pieces = vec![];
+    let mut i = 0;
+    while i < s.len() {
+        if s[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let (piece_enc, prefix_len) = lex_encoding_prefix(s, i).unwrap_or((Encoding::Plain, 0));
+        if piece_enc != Encoding::Plain {
+            enc = piece_enc;
+        }
+        i += prefix_len;
+        let quote = s[i];
+        let start = i + 1;
+        i = start;
+        while i < s.len() && s[i] != quote {
+            i += if s[i] == b'\\' { 2 } else { 1 };
+        }
+        pieces.push(&s[start..i.min(s.len())]);
+        i += 1;
+    }
+    (enc, pieces)
+}
+
+/// Decodes a string literal into code units, without the terminating NUL.
+pub fn unescape_str(raw: &str) -> (Encoding, Vec<u32>) {
+    let (enc, pieces) = quoted_pieces(raw);
+    let mut units = vec![];
+    for body in pieces {
+        unescape_body(body, enc, &mut units, &mut vec![]);
+    }
+    (enc, units)
+}
+
+/// The code units of a decoded string as stored in memory, `unit_size`
+/// bytes each. A 16-bit unit holds UTF-16, so characters outside the BMP
+/// become surrogate pairs.
+pub fn stored_units(units: &[u32], unit_size: usize) -> Vec<u32> {
+    if unit_size != 2 {
+        return units.to_vec();
+    }
+    units
+        .iter()
+        .flat_map(|u| match char::from_u32(*u) {
+            Some(c) => c.encode_utf16(&mut [0; 2]).iter().map(|u| u32::from(*u)).collect(),
+            None => vec![*u & 0xFFFF],
+        })
+        .collect()
+}
+
+/// The value of a character constant, following GCC for multi-character
+/// constants.
+pub fn char_value(raw: &str) -> i64 {
+    let (enc, pieces) = quoted_pieces(raw);
+    let mut units = vec![];
+    unescape_body(pieces.first().copied().unwrap_or_default(), enc, &mut units, &mut vec![]);
+    match enc {
+        Encoding::Plain if units.len() == 1 => i64::from(units[0] as u8 as i8),
+        Encoding::Plain => i64::from(units.iter().fold(0u32, |v, u| v << 8 | (u & 0xFF)) as i32),
+        Encoding::Utf8 => units.last().map_or(0, |u| i64::from(*u as u8)),
+        Encoding::Utf16 => units.last().map_or(0, |u| i64::from(*u as u16)),
+        Encoding::Utf32 => units.last().map_or(0, |u| i64::from(*u)),
+        Encoding::Wide => units.last().map_or(0, |u| i64::from(*u as i32)),
+    }
+}
+
+/// Lexes a character constant or string literal whose prefix (if any) starts
+/// at `cursor` and is `prefix_len` bytes long.
+fn lex_quoted(
+    s: &[u8],
+    cursor: &mut usize,
+    enc: Encoding,
+    prefix_len: usize,
+    errors: &mut Vec<Diagnostic>,
+) -> Token {
+    let pos = *cursor;
+    let quote = s[pos + prefix_len];
+    let body_start = pos + prefix_len + 1;
+    let mut i = body_start;
+    while i < s.len() && s[i] != quote && s[i] != b'\n' {
+        i += if s[i] == b'\\' && s.get(i + 1) != Some(&b'\n') { 2 } else { 1 };
+    }
+    let body_end = i.min(s.len());
+    let what = if quote == b'"' { "string literal" } else { "character constant" };
+    if s.get(body_end) == Some(&quote) {
+        *cursor = body_end + 1;
+    } else {
+        *cursor = body_end;
+        errors.push(
+            Diagnostic::error(ErrorCode::UnterminatedLiteral, format!("unterminated {what}"))
+                .with_primary(
+                    Span {
+                        pos,
+                        len: body_end - pos,
                     },
-                });
-                cursor += 1;
+                    "",
+                )
+                .with_help(format!("add a closing `{}` before the end of the line", quote as char)),
+        );
+    }
+    let span = Span {
+        pos,
+        len: *cursor - pos,
+    };
+
+    let mut units = vec![];
+    let mut escape_errors = vec![];
+    unescape_body(&s[body_start..body_end], enc, &mut units, &mut escape_errors);
+    for e in escape_errors {
+        let code = if e.is_warning { ErrorCode::UnknownEscape } else { ErrorCode::InvalidEscape };
+        let diag = if e.is_warning {
+            Diagnostic::warning(code, e.message)
+        } else {
+            Diagnostic::error(code, e.message)
+        };
+        errors.push(diag.with_primary(
+            Span {
+                pos: body_start + e.offset,
+                len: e.len,
+            },
+            "",
+        ));
+    }
+
+    let kind = if quote == b'"' {
+        LitKind::Str(enc)
+    } else {
+        if units.is_empty() && s.get(body_end) == Some(&quote) {
+            errors.push(
+                Diagnostic::error(ErrorCode::EmptyCharLiteral, "empty character constant")
+                    .with_primary(span, ""),
+            );
+        } else if units.len() > 1 {
+            errors.push(
+                Diagnostic::warning(ErrorCode::MultiCharLiteral, "multi-character character constant")
+                    .with_primary(span, "")
+                    .with_note("its value is implementation-defined"),
+            );
+        }
+        LitKind::Char(enc)
+    };
+    let text = String::from_utf8_lossy(&s[pos..*cursor]);
+    let value = match kind {
+        LitKind::Char(_) => char_value(&text) as u64,
+        _ => 0,
+    };
+    Token {
+        kind: TokenKind::Literal(Lit {
+            kind,
+            symbol: Symbol::intern(&text),
+            value,
+        }),
+        span,
+    }
+}
+
+/// Translation phase 6: concatenates adjacent string literal tokens.
+fn concat_strings(tokens: TokenContainer, errors: &mut Vec<Diagnostic>) -> TokenContainer {
+    let mut result: TokenContainer = Vec::with_capacity(tokens.len());
+    // source text of the run of literals ending at `result.last()`, interned
+    // once the run is over
+    let mut joined: Option<String> = None;
+    for tk in tokens {
+        if let (
+            Some(Token {
+                kind: TokenKind::Literal(prev),
+                span: prev_span,
+            }),
+            TokenKind::Literal(Lit {
+                kind: LitKind::Str(enc),
+                symbol,
+                ..
+            }),
+        ) = (result.last_mut(), tk.kind)
+            && let LitKind::Str(prev_enc) = prev.kind
+        {
+            let merged = match (prev_enc, enc) {
+                (a, b) if a == b => a,
+                (Encoding::Plain, b) => b,
+                (a, Encoding::Plain) => a,
+                (a, b) => {
+                    errors.push(
+                        Diagnostic::error(
+                            ErrorCode::MixedStringPrefix,
+                            "unsupported concatenation of string literals with different prefixes",
+                        )
+                        .with_primary(tk.span, format!("this literal has prefix `{}`", b.prefix()))
+                        .with_secondary(*prev_span, format!("previous literal has prefix `{}`", a.prefix())),
+                    );
+                    a
+                }
+            };
+            prev.kind = LitKind::Str(merged);
+            let text = joined.get_or_insert_with(|| prev.symbol.as_str().to_owned());
+            text.push(' ');
+            text.push_str(symbol.as_str());
+            prev_span.len = tk.span.end() - prev_span.pos;
+            continue;
+        }
+        finish_joined(&mut result, &mut joined);
+        result.push(tk);
+    }
+    finish_joined(&mut result, &mut joined);
+    result
+}
+
+fn finish_joined(result: &mut TokenContainer, joined: &mut Option<String>) {
+    if let Some(text) = joined.take()
+        && let Some(Token {
+            kind: TokenKind::Literal(lit),
+            ..
+        }) = result.last_mut()
+    {
+        lit.symbol = Symbol::intern(&text);
+    }
+}
+
+/// Characters allowed in an identifier (C11 Annex D.1), besides the basic
+/// `[A-Za-z0-9_]`.
+static IDENT_RANGES: &[(u32, u32)] = &[
+    (0x00A8, 0x00A8),
+    (0x00AA, 0x00AA),
+    (0x00AD, 0x00AD),
+    (0x00AF, 0x00AF),
+    (0x00B2, 0x00B5),
+    (0x00B7, 0x00BA),
+    (0x00BC, 0x00BE),
+    (0x00C0, 0x00D6),
+    (0x00D8, 0x00F6),
+    (0x00F8, 0x00FF),
+    (0x0100, 0x167F),
+    (0x1681, 0x180D),
+    (0x180F, 0x1FFF),
+    (0x200B, 0x200D),
+    (0x202A, 0x202E),
+    (0x203F, 0x2040),
+    (0x2054, 0x2054),
+    (0x2060, 0x206F),
+    (0x2070, 0x218F),
+    (0x2460, 0x24FF),
+    (0x2776, 0x2793),
+    (0x2C00, 0x2DFF),
+    (0x2E80, 0x2FFF),
+    (0x3004, 0x3007),
+    (0x3021, 0x302F),
+    (0x3031, 0x303F),
+    (0x3040, 0xD7FF),
+    (0xF900, 0xFD3D),
+    (0xFD40, 0xFDCF),
+    (0xFDF0, 0xFE44),
+    (0xFE47, 0xFFFD),
+    (0x10000, 0x1FFFD),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+    (0x40000, 0x4FFFD),
+    (0x50000, 0x5FFFD),
+    (0x60000, 0x6FFFD),
+    (0x70000, 0x7FFFD),
+    (0x80000, 0x8FFFD),
+    (0x90000, 0x9FFFD),
+    (0xA0000, 0xAFFFD),
+    (0xB0000, 0xBFFFD),
+    (0xC0000, 0xCFFFD),
+    (0xD0000, 0xDFFFD),
+    (0xE0000, 0xEFFFD),
+];
+
+/// Combining characters that may not begin an identifier (C11 Annex D.2).
+static IDENT_NOT_INITIAL: &[(u32, u32)] = &[(0x0300, 0x036F), (0x1DC0, 0x1DFF), (0x20D0, 0x20FF), (0xFE20, 0xFE2F)];
+
+fn in_ranges(ranges: &[(u32, u32)], c: char) -> bool {
+    let c = c as u32;
+    ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c))
+}
+
+fn is_ident_char(c: char, first: bool) -> bool {
+    if c.is_ascii() {
+        return c == '_' || c.is_ascii_alphabetic() || (!first && c.is_ascii_digit());
+    }
+    in_ranges(IDENT_RANGES, c) && !(first && in_ranges(IDENT_NOT_INITIAL, c))
+}
+
+fn starts_ucn(s: &[u8], cursor: usize) -> bool {
+    s[cursor] == b'\\' && matches!(s.get(cursor + 1), Some(b'u' | b'U'))
+}
+
+fn starts_ident(s: &[u8], cursor: usize) -> bool {
+    starts_ucn(s, cursor) || is_ident_char(next_char(s, cursor).0, true)
+}
+
+/// Decodes the universal character name `\uXXXX` or `\UXXXXXXXX` at `cursor`.
+/// On failure returns the message to report; either way returns the number
+/// of bytes consumed.
+fn lex_ucn(s: &[u8], cursor: usize) -> (Result<char, String>, usize) {
+    let digits = if s[cursor + 1] == b'u' { 4 } else { 8 };
+    let found = s[cursor + 2..].iter().take(digits).take_while(|c| c.is_ascii_hexdigit()).count();
+    let len = 2 + found;
+    let text = String::from_utf8_lossy(&s[cursor..cursor + len]);
+    if found < digits {
+        return (Err(format!("incomplete universal character name `{text}`")), len);
+    }
+    let value = u32::from_str_radix(&text[2..], 16).unwrap();
+    match char::from_u32(value) {
+        // C11 6.4.3p2: the basic character set must be written directly
+        Some(c) if value >= 0xA0 || matches!(c, '$' | '@' | '`') => (Ok(c), len),
+        _ => (Err(format!("`{text}` is not a valid universal character")), len),
+    }
+}
+
+/// Lexes an identifier or keyword. Universal character names are replaced
+/// by the character they designate, so `\u00e9` and `é` name the same thing.
+fn lex_ident(s: &[u8], cursor: &mut usize, errors: &mut Vec<Diagnostic>) -> Token {
+    let pos = *cursor;
+    let mut name = String::new();
+    let mut valid = true;
+    while *cursor < s.len() {
+        let first = name.is_empty();
+        if starts_ucn(s, *cursor) {
+            let (c, len) = lex_ucn(s, *cursor);
+            let span = Span { pos: *cursor, len };
+            match c {
+                Ok(c) if is_ident_char(c, first) => name.push(c),
+                Ok(c) => {
+                    let place = if is_ident_char(c, false) { "at the start of" } else { "in" };
+                    errors.push(
+                        Diagnostic::error(
+                            ErrorCode::InvalidUcn,
+                            format!("character `{}` is not allowed {place} an identifier", c.escape_unicode()),
+                        )
+                        .with_primary(span, ""),
+                    );
+                    valid = false;
+                }
+                Err(message) => {
+                    errors.push(Diagnostic::error(ErrorCode::InvalidUcn, message).with_primary(span, ""));
+                    valid = false;
+                }
             }
-            b'/' => {
-                vec.push(Token {
-                    kind: TokenKind::Div,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            *cursor += len;
+            continue;
+        }
+        let (c, len) = next_char(s, *cursor);
+        if !is_ident_char(c, first) {
+            break;
+        }
+        name.push(c);
+        *cursor += len;
+    }
+    let kind = if !valid {
+        TokenKind::Error
+    } else {
+        let sym = Symbol::intern(&name);
+        KeywordKind::from_symbol(sym).map_or(TokenKind::Ident(sym), TokenKind::Keyword)
+    };
+    Token {
+        kind,
+        span: Span {
+            pos,
+            len: *cursor - pos,
+        },
+    }
+}
+
+/// Decodes the (possibly multi-byte) character at `cursor`; invalid UTF-8
+/// is reported as U+FFFD covering a single byte.
+fn next_char(s: &[u8], cursor: usize) -> (char, usize) {
+    let width = match s[cursor] {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    };
+    s.get(cursor..cursor + width)
+        .and_then(|bytes| std::str::from_utf8(bytes).ok())
+        .and_then(|text| text.chars().next())
+        .map(|c| (c, width))
+        .unwrap_or((char::REPLACEMENT_CHARACTER, 1))
+}
+
+fn look_ahead_is(s: &[u8], cursor: usize, expected: u8) -> bool {
+    s.get(cursor).copied() == Some(expected)
+}
+
+fn lex_punct(s: &[u8], cursor: usize) -> Option<(TokenKind, usize)> {
+    PUNCTUATORS
+        .iter()
+        .find(|(text, _)| s[cursor..].starts_with(text.as_bytes()))
+        .map(|(text, kind)| (*kind, text.len()))
+}
+
+/// Translation phase 2: deletes every backslash-newline pair.
+///
+/// Returns the spliced text together with, for each of its bytes (plus one
+/// past the end), the offset of that byte in the original source.
+fn splice_lines(s: &[u8]) -> (Vec<u8>, Vec<usize>) {
+    let mut text = Vec::with_capacity(s.len());
+    let mut origin = Vec::with_capacity(s.len() + 1);
+    let mut cursor = 0;
+    while cursor < s.len() {
+        if s[cursor] == b'\\' {
+            match (s.get(cursor + 1), s.get(cursor + 2)) {
+                (Some(b'\n'), _) => {
+                    cursor += 2;
+                    continue;
+                }
+                (Some(b'\r'), Some(b'\n')) => {
+                    cursor += 3;
+                    continue;
+                }
+                _ => (),
             }
-            b'(' => {
-                vec.push(Token {
-                    kind: TokenKind::LParen,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
+        }
+        text.push(s[cursor]);
+        origin.push(cursor);
+        cursor += 1;
+    }
+    origin.push(s.len());
+    (text, origin)
+}
+
+/// Maps a span over spliced text back onto the original source.
+fn unsplice(span: Span, origin: &[usize]) -> Span {
+    let pos = origin[span.pos];
+    let end = if span.len == 0 {
+        pos
+    } else {
+        origin[span.end() - 1] + 1
+    };
+    Span {
+        pos,
+        len: end - pos,
+    }
+}
+
+/// Splits `s` into tokens. Lexing never stops early: anything unrecognized
+/// becomes a `TokenKind::Error` token and a diagnostic in the returned list.
+///
+/// Line splices and comments are handled here; every returned span points
+/// into the original `s`.
+pub fn tokenize(s: &[u8]) -> (TokenContainer, Vec<Diagnostic>) {
+    let (spliced, origin) = splice_lines(s);
+    let (mut tokens, mut errors) = tokenize_spliced(&spliced);
+    for tk in tokens.iter_mut() {
+        tk.span = unsplice(tk.span, &origin);
+    }
+    for e in errors.iter_mut() {
+        for label in e.labels.iter_mut() {
+            label.span = unsplice(label.span, &origin);
+        }
+    }
+    let tokens = concat_strings(tokens, &mut errors);
+    (tokens, errors)
+}
+
+fn tokenize_spliced(s: &[u8]) -> (TokenContainer, Vec<Diagnostic>) {
+    let mut vec = Vec::new();
+    let mut errors = Vec::new();
+    let mut cursor = 0;
+    while cursor < s.len() {
+        match s[cursor] {
+            c if c.is_ascii_whitespace() => {
                 cursor += 1;
             }
-            b')' => {
-                vec.push(Token {
-                    kind: TokenKind::RParen,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            b'/' if look_ahead_is(s, cursor + 1, b'/') => {
+                while cursor < s.len() && s[cursor] != b'\n' {
+                    cursor += 1;
+                }
             }
-            b'{' => {
-                vec.push(Token {
-                    kind: TokenKind::LBrace,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            b'/' if look_ahead_is(s, cursor + 1, b'*') => {
+                match s[cursor + 2..].windows(2).position(|w| w == b"*/") {
+                    Some(offset) => cursor += offset + 4,
+                    None => {
+                        errors.push(
+                            Diagnostic::error(ErrorCode::UnterminatedComment, "unterminated block comment")
+                                .with_primary(Span { pos: cursor, len: 2 }, "comment starts here")
+                                .with_help("close it with `*/`"),
+                        );
+                        cursor = s.len();
+                    }
+                }
             }
-            b'}' => {
-                vec.push(Token {
-                    kind: TokenKind::RBrace,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            b'"' | b'\'' => {
+                vec.push(lex_quoted(s, &mut cursor, Encoding::Plain, 0, &mut errors));
             }
-            b';' => {
-                vec.push(Token {
-                    kind: TokenKind::Semi,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            _ if let Some((enc, prefix_len)) = lex_encoding_prefix(s, cursor) => {
+                vec.push(lex_quoted(s, &mut cursor, enc, prefix_len, &mut errors));
             }
-            b',' => {
-                vec.push(Token {
-                    kind: TokenKind::Comma,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            _ if starts_ident(s, cursor) => {
+                vec.push(lex_ident(s, &mut cursor, &mut errors));
             }
-            b'&' => {
-                vec.push(Token {
-                    kind: TokenKind::And,
-                    span: Span {
-                        pos: cursor,
-                        len: 1,
-                    },
-                });
-                cursor += 1;
+            c if c.is_ascii_digit() || (c == b'.' && s.get(cursor + 1).is_some_and(u8::is_ascii_digit)) => {
+                vec.push(lex_number(s, &mut cursor, &mut errors));
             }
-            ident if ident.is_ascii_alphabetic() => {
-                let pos = cursor;
-                let data = parse_next_ident(s, &mut cursor);
-                let kind = if let Some(kw) = KeywordKind::lex_keyword(data.as_str()) {
-                    TokenKind::Keyword(kw)
-                } else {
-                    TokenKind::Ident(data)
-                };
+            _ if let Some((kind, len)) = lex_punct(s, cursor) => {
                 vec.push(Token {
                     kind,
-                    span: Span {
-                        pos,
-                        len: cursor - pos,
-                    },
+                    span: Span { pos: cursor, len },
                 });
+                cursor += len;
             }
-            c if c.is_ascii_digit() => {
-                let pos = cursor;
+            _ => {
+                let (shown, len) = next_char(s, cursor);
+                let span = Span { pos: cursor, len };
+                errors.push(
+                    Diagnostic::error(
+                        ErrorCode::UnknownCharacter,
+                        format!("unknown start of token: `{}`", shown.escape_default()),
+                    )
+                    .with_primary(span, ""),
+                );
                 vec.push(Token {
-                    kind: TokenKind::Literal(Lit {
-                        kind: LitKind::Integer,
-                        symbol: parse_next_number(s, &mut cursor),
-                    }),
-                    span: Span {
-                        pos,
-                        len: cursor - pos,
-                    },
+                    kind: TokenKind::Error,
+                    span,
                 });
-            }
-            b'\n' => {
-                cursor += 1;
-            }
-            _ => {
-                panic!("unexpected token");
+                cursor += span.len;
             }
         }
     }
-    vec
+    vec.push(Token {
+        kind: TokenKind::Eof,
+        span: Span {
+            pos: s.len(),
+            len: 0,
+        },
+    });
+    (vec, errors)
 }
@@ -0,0 +1,481 @@
+use std::fmt;
+
+use crate::codegen::abi::TargetAbi;
+use crate::tokenizer::{Encoding, IntTy, Symbol};
+
+/// The integer types of C11 6.2.5, `_Bool` included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntKind {
+    Bool,
+    /// Plain `char`; signed on every target we support.
+    Char,
+    SChar,
+    UChar,
+    Short,
+    UShort,
+    Int,
+    UInt,
+    Long,
+    ULong,
+    LongLong,
+    ULongLong,
+}
+
+impl IntKind {
+    pub fn is_unsigned(self) -> bool {
+        use IntKind::*;
+        matches!(self, Bool | UChar | UShort | UInt | ULong | ULongLong)
+    }
+
+    /// Integer conversion rank (C11 6.3.1.1p1).
+    pub fn rank(self) -> u8 {
+        use IntKind::*;
+        match self {
+            Bool => 0,
+            Char | SChar | UChar => 1,
+            Short | UShort => 2,
+            Int | UInt => 3,
+            Long | ULong => 4,
+            LongLong | ULongLong => 5,
+        }
+    }
+
+    pub fn to_unsigned(self) -> IntKind {
+        use IntKind::*;
+        match self {
+            Char | SChar => UChar,
+            Short => UShort,
+            Int => UInt,
+            Long => ULong,
+            LongLong => ULongLong,
+            unsigned => unsigned,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        use IntKind::*;
+        match self {
+            Bool => "_Bool",
+            Char => "char",
+            SChar => "signed char",
+            UChar => "unsigned char",
+            Short => "short",
+            UShort => "unsigned short",
+            Int => "int",
+            UInt => "unsigned int",
+            Long => "long",
+            ULong => "unsigned long",
+            LongLong => "long long",
+            ULongLong => "unsigned long long",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FnTy {
+    pub ret: Ty,
+    pub params: Vec<Ty>,
+    pub variadic: bool,
+    /// The type lists its parameters: `int f(void)` does, while `int f()`
+    /// leaves them unspecified.
+    pub prototyped: bool,
+}
+
+/// Names a struct, union or enum declared in the program; `id` indexes
+/// `TyCtx::records`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TagRef {
+    pub id: usize,
+    pub tag: Option<Symbol>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Ty {
+    Void,
+    Int(IntKind),
+    Ptr(Box<Ty>),
+    /// An array of known length, or of unknown length as in `int a[]`.
+    Array(Box<Ty>, Option<usize>),
+    Func(Box<FnTy>),
+    Struct(TagRef),
+    Union(TagRef),
+    Enum(TagRef),
+}
+
+impl Ty {
+    pub const INT: Ty = Ty::Int(IntKind::Int);
+
+    /// The keyword introducing a tagged type: `struct`, `union` or `enum`.
+    pub fn tag_keyword(&self) -> Option<&'static str> {
+        match self {
+            Ty::Struct(_) => Some("struct"),
+            Ty::Union(_) => Some("union"),
+            Ty::Enum(_) => Some("enum"),
+            _ => None,
+        }
+    }
+
+    pub fn ptr_to(ty: Ty) -> Ty {
+        Ty::Ptr(Box::new(ty))
+    }
+
+    /// The pointed-to type, treating arrays as pointers to their first
+    /// element.
+    pub fn pointee(&self) -> Option<&Ty> {
+        match self {
+            Ty::Ptr(base) | Ty::Array(base, _) => Some(base),
+            _ => None,
+        }
+    }
+
+    /// The type of the function a call through this type invokes: a
+    /// function itself or a pointer to one.
+    pub fn as_fn(&self) -> Option<&FnTy> {
+        match self {
+            Ty::Func(func) => Some(func),
+            Ty::Ptr(base) => match &**base {
+                Ty::Func(func) => Some(func),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether two declarations of one object or function may have these
+    /// types (C11 6.2.7): they agree except that an array may lack its
+    /// length and a function its prototype.
+    pub fn is_compatible(&self, other: &Ty) -> bool {
+        match (self, other) {
+            (Ty::Ptr(a), Ty::Ptr(b)) => a.is_compatible(b),
+            (Ty::Array(a, n), Ty::Array(b, m)) => a.is_compatible(b) && (n == m || n.is_none() || m.is_none()),
+            (Ty::Func(a), Ty::Func(b)) => {
+                a.ret.is_compatible(&b.ret)
+                    && (!a.prototyped
+                        || !b.prototyped
+                        || (a.variadic == b.variadic
+                            && a.params.len() == b.params.len()
+                            && a.params.iter().zip(&b.params).all(|(a, b)| a.is_compatible(b))))
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// The type of an object or function declared with the compatible
+    /// types `self` and `other`, taking an array length or a prototype
+    /// from whichever has it.
+    pub fn composite(self, other: Ty) -> Ty {
+        match (self, other) {
+            (Ty::Ptr(a), Ty::Ptr(b)) => Ty::ptr_to(a.composite(*b)),
+            (Ty::Array(a, n), Ty::Array(b, m)) => Ty::Array(Box::new(a.composite(*b)), n.or(m)),
+            (Ty::Func(a), Ty::Func(b)) if !a.prototyped => Ty::Func(b),
+            (a, _) => a,
+        }
+    }
+
+    /// Array-to-pointer and function-to-pointer conversion (C11 6.3.2.1).
+    pub fn decay(self) -> Ty {
+        match self {
+            Ty::Array(elem, _) => Ty::Ptr(elem),
+            func @ Ty::Func(_) => Ty::ptr_to(func),
+            ty => ty,
+        }
+    }
+
+    /// Whether this is a struct or union type.
+    pub fn is_record(&self) -> bool {
+        matches!(self, Ty::Struct(_) | Ty::Union(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, Ty::Array(..))
+    }
+
+    pub fn is_ptr(&self) -> bool {
+        matches!(self, Ty::Ptr(_))
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Ty::Int(_) | Ty::Enum(_))
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        self.is_integer() || self.is_ptr()
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        match self {
+            Ty::Int(kind) => kind.is_unsigned(),
+            Ty::Ptr(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Writes the type the way a declaration would, with `inner` standing
+    /// for the declared name: `int (*inner)[3]`.
+    fn fmt_around(&self, inner: String, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Ptr(base) => match **base {
+                Ty::Array(..) | Ty::Func(_) => base.fmt_around(format!("(*{inner})"), f),
+                _ => base.fmt_around(format!("*{inner}"), f),
+            },
+            Ty::Array(elem, len) => {
+                let len = len.map_or(String::new(), |len| len.to_string());
+                elem.fmt_around(format!("{inner}[{len}]"), f)
+            }
+            Ty::Func(func) => {
+                let mut params = func.params.iter().map(Ty::to_string).collect::<Vec<_>>();
+                if func.variadic {
+                    params.push("...".to_string());
+                } else if params.is_empty() && func.prototyped {
+                    params.push("void".to_string());
+                }
+                func.ret.fmt_around(format!("{inner}({})", params.join(", ")), f)
+            }
+            base => {
+                match base {
+                    Ty::Void => f.write_str("void")?,
+                    Ty::Int(kind) => f.write_str(kind.as_str())?,
+                    Ty::Struct(r) | Ty::Union(r) | Ty::Enum(r) => {
+                        let keyword = base.tag_keyword().expect("tagged type");
+                        match r.tag {
+                            Some(tag) => write!(f, "{keyword} {tag}")?,
+                            None => write!(f, "{keyword} <anonymous>")?,
+                        }
+                    }
+                    _ => unreachable!("derived types handled above"),
+                }
+                if inner.starts_with('*') || inner.starts_with("(*") {
+                    f.write_str(" ")?;
+                }
+                f.write_str(&inner)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_around(String::new(), f)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub name: Symbol,
+    pub ty: Ty,
+    /// Byte offset from the start of the record.
+    pub offset: usize,
+}
+
+/// Members, size and alignment of a struct or union, known once its body
+/// is seen; until then the record is incomplete.
+#[derive(Clone, Debug, Default)]
+pub struct RecordLayout {
+    pub members: Vec<Member>,
+    pub size: usize,
+    pub align: usize,
+    pub is_complete: bool,
+}
+
+/// Target facts and aggregate layouts needed to answer size queries.
+pub struct TyCtx {
+    /// 8 on LP64 (SysV), 4 on LLP64 (Win64).
+    pub long_size: usize,
+    /// The type `wchar_t` is defined as.
+    pub wchar: IntKind,
+    /// The type `va_list` is defined as.
+    pub va_list: Ty,
+    /// Bytes a variadic function reserves in its frame for the argument
+    /// registers that `va_arg` reads.
+    pub va_save_area: usize,
+    pub records: Vec<RecordLayout>,
+}
+
+impl Default for TyCtx {
+    fn default() -> Self {
+        TyCtx::new(TargetAbi::host())
+    }
+}
+
+impl TyCtx {
+    pub fn new(abi: TargetAbi) -> TyCtx {
+        let (long_size, wchar) = match abi {
+            TargetAbi::SysV => (8, IntKind::Int),
+            TargetAbi::Win64 => (4, IntKind::UShort),
+        };
+        let mut tcx = TyCtx {
+            long_size,
+            wchar,
+            va_list: Ty::ptr_to(Ty::Int(IntKind::Char)),
+            va_save_area: 0,
+            records: vec![],
+        };
+        if abi == TargetAbi::SysV {
+            tcx.define_va_list_tag();
+        }
+        tcx
+    }
+
+    /// On SysV `va_list` is `struct __va_list_tag[1]`: the offsets of the
+    /// next general-purpose and vector register in the save area, where
+    /// the stack arguments continue, and the save area itself, which holds
+    /// the 6 general-purpose and 8 vector argument registers.
+    fn define_va_list_tag(&mut self) {
+        let member = |name, ty, offset| Member {
+            name: Symbol::intern(name),
+            ty,
+            offset,
+        };
+        let void_ptr = Ty::ptr_to(Ty::Void);
+        let tag = TagRef {
+            id: self.records.len(),
+            tag: Some(Symbol::intern("__va_list_tag")),
+        };
+        self.records.push(RecordLayout {
+            members: vec![
+                member("gp_offset", Ty::Int(IntKind::UInt), 0),
+                member("fp_offset", Ty::Int(IntKind::UInt), 4),
+                member("overflow_arg_area", void_ptr.clone(), 8),
+                member("reg_save_area", void_ptr, 16),
+            ],
+            size: 24,
+            align: 8,
+            is_complete: true,
+        });
+        self.va_list = Ty::Array(Box::new(Ty::Struct(tag)), Some(1));
+        self.va_save_area = 6 * 8 + 8 * 16;
+    }
+
+    pub fn int_size(&self, kind: IntKind) -> usize {
+        use IntKind::*;
+        match kind {
+            Bool | Char | SChar | UChar => 1,
+            Short | UShort => 2,
+            Int | UInt => 4,
+            Long | ULong => self.long_size,
+            LongLong | ULongLong => 8,
+        }
+    }
+
+    pub fn size_of(&self, ty: &Ty) -> usize {
+        match ty {
+            // GNU C: arithmetic on `void *` and function pointers steps bytes
+            Ty::Void | Ty::Func(_) => 1,
+            Ty::Int(kind) => self.int_size(*kind),
+            Ty::Ptr(_) => 8,
+            Ty::Array(elem, len) => self.size_of(elem) * len.unwrap_or(0),
+            Ty::Struct(r) | Ty::Union(r) => self.records[r.id].size,
+            Ty::Enum(_) => 4,
+        }
+    }
+
+    pub fn align_of(&self, ty: &Ty) -> usize {
+        match ty {
+            Ty::Array(elem, _) => self.align_of(elem),
+            Ty::Struct(r) | Ty::Union(r) => self.records[r.id].align,
+            ty => self.size_of(ty),
+        }
+    }
+
+    /// Whether objects of the type can be created, i.e. its size is known.
+    pub fn is_complete(&self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Void => false,
+            Ty::Array(elem, len) => len.is_some() && self.is_complete(elem),
+            Ty::Struct(r) | Ty::Union(r) => self.records[r.id].is_complete,
+            _ => true,
+        }
+    }
+
+    /// Looks up `name` among the members of a struct or union type.
+    pub fn member(&self, ty: &Ty, name: Symbol) -> Option<&Member> {
+        match ty {
+            Ty::Struct(r) | Ty::Union(r) => self.records[r.id].members.iter().find(|m| m.name == name),
+            _ => None,
+        }
+    }
+
+    /// The type of an integer constant, as chosen by `IntForm::ty`.
+    pub fn int_lit_ty(&self, ty: IntTy) -> Ty {
+        Ty::Int(match ty {
+            IntTy::Int => IntKind::Int,
+            IntTy::UInt => IntKind::UInt,
+            IntTy::Long => IntKind::Long,
+            IntTy::ULong => IntKind::ULong,
+            IntTy::LongLong => IntKind::LongLong,
+            IntTy::ULongLong => IntKind::ULongLong,
+        })
+    }
+
+    /// The type of a character constant with the given prefix.
+    pub fn char_lit_ty(&self, enc: Encoding) -> Ty {
+        match enc {
+            Encoding::Plain => Ty::INT,
+            Encoding::Utf8 => Ty::Int(IntKind::UChar),
+            Encoding::Wide => Ty::Int(self.wchar),
+            Encoding::Utf16 => Ty::Int(IntKind::UShort),
+            Encoding::Utf32 => Ty::Int(IntKind::UInt),
+        }
+    }
+
+    /// The element type of a string literal with the given prefix.
+    pub fn str_elem_ty(&self, enc: Encoding) -> Ty {
+        match enc {
+            Encoding::Plain | Encoding::Utf8 => Ty::Int(IntKind::Char),
+            enc => self.char_lit_ty(enc),
+        }
+    }
+
+    /// Integer promotion (C11 6.3.1.1p2): everything ranked below `int`
+    /// becomes `int`, which can represent all of its values.
+    pub fn promote(&self, ty: &Ty) -> Ty {
+        match ty {
+            Ty::Int(kind) if kind.rank() < IntKind::Int.rank() => Ty::INT,
+            Ty::Enum(_) => Ty::INT,
+            ty => ty.clone(),
+        }
+    }
+
+    /// The common type of the usual arithmetic conversions (C11 6.3.1.8).
+    pub fn usual_arith(&self, lhs: &Ty, rhs: &Ty) -> Ty {
+        let (Ty::Int(a), Ty::Int(b)) = (self.promote(lhs), self.promote(rhs)) else {
+            return Ty::INT;
+        };
+        if a == b {
+            return Ty::Int(a);
+        }
+        if a.is_unsigned() == b.is_unsigned() {
+            return Ty::Int(if a.rank() >= b.rank() { a } else { b });
+        }
+        let (unsigned, signed) = if a.is_unsigned() { (a, b) } else { (b, a) };
+        if unsigned.rank() >= signed.rank() {
+            Ty::Int(unsigned)
+        } else if self.int_size(signed) > self.int_size(unsigned) {
+            Ty::Int(signed)
+        } else {
+            Ty::Int(signed.to_unsigned())
+        }
+    }
+
+    /// Converts an integer constant to the integer type `ty`, wrapping it
+    /// to the type's width the way a value held in a register is kept.
+    pub fn wrap_int(&self, value: i64, ty: &Ty) -> i64 {
+        let bits = self.size_of(ty) as u32 * 8;
+        match ty {
+            Ty::Int(IntKind::Bool) => i64::from(value != 0),
+            _ if bits >= 64 => value,
+            ty if ty.is_unsigned() => value & ((1 << bits) - 1),
+            _ => value << (64 - bits) >> (64 - bits),
+        }
+    }
+
+    /// `ptrdiff_t`, the type of a pointer difference.
+    pub fn ptrdiff_ty(&self) -> Ty {
+        Ty::Int(if self.long_size == 8 { IntKind::Long } else { IntKind::LongLong })
+    }
+
+    /// `size_t`, the type of `sizeof` and `_Alignof`.
+    pub fn size_ty(&self) -> Ty {
+        Ty::Int(if self.long_size == 8 { IntKind::ULong } else { IntKind::ULongLong })
+    }
+}
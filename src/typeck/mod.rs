@@ -0,0 +1,466 @@
+use crate::ast::*;
+use crate::consteval;
+use crate::error_handler::{Diagnostic, ErrorCode};
+use crate::resolver::ResolvedCrate;
+use crate::tokenizer::{Lit, LitKind, stored_units, unescape_str};
+use crate::ty::{IntKind, Ty};
+use std::collections::HashMap;
+
+/// Where a value converts to the type of what receives it.
+#[derive(Clone, Copy)]
+enum Conversion {
+    Assign,
+    Init,
+    Arg,
+    Return,
+}
+
+impl Conversion {
+    /// Says what converts a `value_ty` value to `ty`, as in "assigning to
+    /// `int` from type `char *`".
+    fn describe(self, ty: &Ty, value_ty: &Ty, incompatible: bool) -> String {
+        let adj = if incompatible { "incompatible " } else { "" };
+        match self {
+            Conversion::Assign => format!("assigning to `{ty}` from {adj}type `{value_ty}`"),
+            Conversion::Init => format!("initializing `{ty}` with an expression of {adj}type `{value_ty}`"),
+            Conversion::Arg => format!("passing `{value_ty}` to parameter of {adj}type `{ty}`"),
+            Conversion::Return => format!("returning `{value_ty}` from a function with {adj}result type `{ty}`"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TypeckResults {
+    pub expr_tys: HashMap<NodeId, Ty>,
+}
+
+/// Gives every expression its C type and reports operands whose types do
+/// not fit their operator. Runs after name resolution, so every `Var`
+/// already points at its object.
+pub struct TypeChecker<'a> {
+    resolved: &'a ResolvedCrate,
+    pub results: TypeckResults,
+    pub errors: Vec<Diagnostic>,
+    /// Return type of the function being checked.
+    ret: Ty,
+}
+
+impl<'a> TypeChecker<'a> {
+    pub fn new(resolved: &'a ResolvedCrate) -> TypeChecker<'a> {
+        TypeChecker {
+            resolved,
+            results: TypeckResults::default(),
+            errors: vec![],
+            ret: Ty::INT,
+        }
+    }
+
+    pub fn check(&mut self, source: &Crate) {
+        for item in &source.items {
+            match item {
+                Item::Fn(func) => {
+                    let name = func.declarator.function_name().expect("invalid fn def");
+                    let fn_ty = self.resolved.fn_info.get(&name).map(|info| &self.resolved.objs[info.fn_id].ty);
+                    self.ret = match fn_ty {
+                        Some(Ty::Func(func)) => func.ret.clone(),
+                        _ => Ty::INT,
+                    };
+                    self.check_stmt(&func.body);
+                }
+                Item::Decl(_, var_decls) => self.check_inits(var_decls),
+            }
+        }
+    }
+
+    fn check_inits(&mut self, var_decls: &[VarDecl]) {
+        for var in var_decls {
+            let Some(init) = &var.init else { continue };
+            for expr in init.exprs() {
+                let init_ty = self.check_expr(expr);
+                let Some((_, ty)) = self.resolved.init_targets.get(&expr.id) else { continue };
+                if let Ty::Array(elem, _) = ty {
+                    // only a string literal of the element's width
+                    // initializes an array
+                    let tcx = &self.resolved.tcx;
+                    let is_str = matches!(expr.kind, ExprKind::Literal(Lit { kind: LitKind::Str(_), .. }));
+                    if !is_str || !elem.is_integer() || tcx.size_of(elem) != init_ty.pointee().map_or(0, |e| tcx.size_of(e)) {
+                        self.errors.push(
+                            Diagnostic::error(
+                                ErrorCode::InvalidOperands,
+                                format!("array type `{ty}` cannot be initialized from `{init_ty}`"),
+                            )
+                            .with_primary(expr.span, ""),
+                        );
+                    }
+                    continue;
+                }
+                self.check_compatible(Conversion::Init, ty, &init_ty.decay(), expr);
+            }
+        }
+    }
+
+    pub fn check_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Block(stmts) => {
+                for stmt in stmts {
+                    self.check_stmt(stmt);
+                }
+            }
+            StmtKind::ExprStmt(expr) => {
+                self.check_expr(expr);
+            }
+            StmtKind::Return(expr) => {
+                if let Some(expr) = expr {
+                    let ret = self.ret.clone();
+                    let ty = self.check_expr(expr).decay();
+                    if ret == Ty::Void && ty != Ty::Void {
+                        self.errors.push(
+                            Diagnostic::error(ErrorCode::InvalidReturn, "void function should not return a value")
+                                .with_primary(expr.span, ""),
+                        );
+                    } else {
+                        self.check_compatible(Conversion::Return, &ret, &ty, expr);
+                    }
+                }
+            }
+            StmtKind::For(init, cond, incr, stmt) => {
+                init.as_ref().inspect(|init| self.check_stmt(init));
+                for expr in [cond, incr].into_iter().flatten() {
+                    self.check_expr(expr);
+                }
+                self.check_stmt(stmt);
+            }
+            StmtKind::While(cond, stmt) => {
+                self.check_expr(cond);
+                self.check_stmt(stmt);
+            }
+            StmtKind::DoWhile(stmt, cond) => {
+                self.check_stmt(stmt);
+                self.check_expr(cond);
+            }
+            StmtKind::Switch(value, stmt) => {
+                let ty = self.check_expr(value).decay();
+                if !ty.is_integer() {
+                    self.errors.push(
+                        Diagnostic::error(
+                            ErrorCode::InvalidOperands,
+                            format!("switch quantity is not an integer (have `{ty}`)"),
+                        )
+                        .with_primary(value.span, ""),
+                    );
+                }
+                self.check_stmt(stmt);
+            }
+            StmtKind::Case(value, stmt) => {
+                self.check_expr(value);
+                self.check_stmt(stmt);
+            }
+            StmtKind::Default(_, stmt) | StmtKind::Label(_, _, stmt) => self.check_stmt(stmt),
+            StmtKind::If(cond, if_stmt, else_stmt) => {
+                self.check_expr(cond);
+                self.check_stmt(if_stmt);
+                else_stmt.as_ref().inspect(|stmt| self.check_stmt(stmt));
+            }
+            StmtKind::Decl(_, var_decls) => self.check_inits(var_decls),
+            StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Goto(..) | StmtKind::Null | StmtKind::Error => (),
+        }
+    }
+
+    /// Types `expr` and its operands, recording each in `expr_tys`. The
+    /// type is the one before array-to-pointer decay; users decay it.
+    pub fn check_expr(&mut self, expr: &Expr) -> Ty {
+        let ty = self.expr_ty(expr);
+        self.results.expr_tys.insert(expr.id, ty.clone());
+        ty
+    }
+
+    fn expr_ty(&mut self, expr: &Expr) -> Ty {
+        let resolved = self.resolved;
+        let tcx = &resolved.tcx;
+        match &expr.kind {
+            ExprKind::Assign(lhs, rhs) | ExprKind::AssignOp(_, lhs, rhs) => {
+                let ty = self.check_expr(lhs);
+                let rhs_ty = self.check_expr(rhs).decay();
+                match &expr.kind {
+                    ExprKind::AssignOp(op, ..) => {
+                        self.binary_ty(*op, &ty.clone().decay(), &rhs_ty, expr);
+                    }
+                    _ => self.check_compatible(Conversion::Assign, &ty, &rhs_ty, rhs),
+                }
+                self.check_assignable(&ty, lhs);
+                ty
+            }
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs = self.check_expr(lhs).decay();
+                let rhs = self.check_expr(rhs).decay();
+                self.binary_ty(*op, &lhs, &rhs, expr)
+            }
+            ExprKind::Cond(cond, then, els) => {
+                self.check_expr(cond);
+                let then = self.check_expr(then).decay();
+                let els = self.check_expr(els).decay();
+                if then.is_integer() && els.is_integer() {
+                    tcx.usual_arith(&then, &els)
+                } else if els.is_ptr() && !then.is_ptr() {
+                    // `c ? 0 : p` is a pointer like `c ? p : 0`
+                    els
+                } else {
+                    then
+                }
+            }
+            ExprKind::FnCall(callee, args) => {
+                let callee_ty = self.check_expr(callee);
+                let arg_tys = args.iter().map(|arg| self.check_expr(arg).decay()).collect::<Vec<_>>();
+                let Some(func) = callee_ty.as_fn() else {
+                    self.errors.push(
+                        Diagnostic::error(
+                            ErrorCode::NotCallable,
+                            format!("called object type `{callee_ty}` is not a function or function pointer"),
+                        )
+                        .with_primary(expr.span, ""),
+                    );
+                    return Ty::INT;
+                };
+                // without a prototype the arguments are taken as they are
+                if func.prototyped {
+                    let (expected, have) = (func.params.len(), args.len());
+                    if have < expected || (have > expected && !func.variadic) {
+                        let few = if have < expected { "few" } else { "many" };
+                        let at_least = if func.variadic { "at least " } else { "" };
+                        self.errors.push(
+                            Diagnostic::error(
+                                ErrorCode::ArgCountMismatch,
+                                format!("too {few} arguments to function call, expected {at_least}{expected}, have {have}"),
+                            )
+                            .with_primary(expr.span, ""),
+                        );
+                    }
+                    for ((param, arg), arg_ty) in func.params.iter().zip(args).zip(&arg_tys) {
+                        self.check_compatible(Conversion::Arg, param, arg_ty, arg);
+                    }
+                }
+                func.ret.clone()
+            }
+            ExprKind::Unary(op, operand) => {
+                let ty = self.check_expr(operand);
+                match op {
+                    UnaryOpKind::AddrOf => Ty::ptr_to(ty),
+                    UnaryOpKind::Deref => match ty.decay() {
+                        Ty::Ptr(base) => *base,
+                        ty => {
+                            self.errors.push(
+                                Diagnostic::error(
+                                    ErrorCode::InvalidDeref,
+                                    format!("type `{ty}` cannot be dereferenced"),
+                                )
+                                .with_primary(expr.span, ""),
+                            );
+                            Ty::INT
+                        }
+                    },
+                    UnaryOpKind::PreInc | UnaryOpKind::PreDec | UnaryOpKind::PostInc | UnaryOpKind::PostDec => {
+                        self.check_assignable(&ty, operand);
+                        ty
+                    }
+                    UnaryOpKind::Not => {
+                        let ty = ty.decay();
+                        if !ty.is_scalar() {
+                            self.errors.push(
+                                Diagnostic::error(
+                                    ErrorCode::InvalidOperands,
+                                    format!("invalid operand to unary `!` (have `{ty}`)"),
+                                )
+                                .with_primary(expr.span, ""),
+                            );
+                        }
+                        Ty::INT
+                    }
+                    UnaryOpKind::Pos | UnaryOpKind::Neg | UnaryOpKind::BitNot => {
+                        let ty = ty.decay();
+                        if !ty.is_integer() {
+                            let op = match op {
+                                UnaryOpKind::Pos => "+",
+                                UnaryOpKind::Neg => "-",
+                                _ => "~",
+                            };
+                            self.errors.push(
+                                Diagnostic::error(
+                                    ErrorCode::InvalidOperands,
+                                    format!("invalid operand to unary `{op}` (have `{ty}`)"),
+                                )
+                                .with_primary(expr.span, ""),
+                            );
+                            return Ty::INT;
+                        }
+                        tcx.promote(&ty)
+                    }
+                }
+            }
+            ExprKind::Var(_) => match resolved.expr_resolutions.get(&expr.id) {
+                Some(id) => resolved.objs[*id].ty.clone(),
+                None => Ty::INT,
+            },
+            ExprKind::Literal(lit) => match lit.kind {
+                LitKind::Integer(form) => {
+                    let ty = form.ty(lit.value, tcx.long_size as u32 * 8);
+                    ty.map_or(Ty::Int(IntKind::ULongLong), |ty| tcx.int_lit_ty(ty))
+                }
+                LitKind::Char(enc) => tcx.char_lit_ty(enc),
+                LitKind::Str(enc) => {
+                    let elem = tcx.str_elem_ty(enc);
+                    let (_, units) = unescape_str(lit.symbol.as_str());
+                    let len = stored_units(&units, tcx.size_of(&elem)).len();
+                    Ty::Array(Box::new(elem), Some(len + 1))
+                }
+            },
+            ExprKind::Member(base, name) => {
+                let ty = self.check_expr(base);
+                if !matches!(ty, Ty::Struct(_) | Ty::Union(_)) {
+                    self.errors.push(
+                        Diagnostic::error(
+                            ErrorCode::NotARecord,
+                            format!("member reference base type `{ty}` is not a structure or union"),
+                        )
+                        .with_primary(expr.span, ""),
+                    );
+                    return Ty::INT;
+                }
+                match tcx.member(&ty, *name) {
+                    Some(member) => member.ty.clone(),
+                    None => {
+                        self.errors.push(
+                            Diagnostic::error(ErrorCode::NoSuchMember, format!("no member named `{name}` in `{ty}`"))
+                                .with_primary(expr.span, ""),
+                        );
+                        Ty::INT
+                    }
+                }
+            }
+            ExprKind::Cast(name, operand) => {
+                let ty = resolved.type_names[&name.declarator.id].clone();
+                let from = self.check_expr(operand).decay();
+                // anything converts to `void`; otherwise only scalars convert
+                if ty != Ty::Void && !(ty.is_scalar() && from.is_scalar()) {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::InvalidCast, format!("invalid cast from `{from}` to `{ty}`"))
+                            .with_primary(expr.span, ""),
+                    );
+                }
+                ty
+            }
+            ExprKind::SizeofExpr(operand) => {
+                let ty = self.check_expr(operand);
+                self.check_sizeof("sizeof", &ty, expr);
+                tcx.size_ty()
+            }
+            ExprKind::SizeofType(name) | ExprKind::Alignof(name) => {
+                let op = if let ExprKind::Alignof(_) = expr.kind { "_Alignof" } else { "sizeof" };
+                self.check_sizeof(op, &resolved.type_names[&name.declarator.id], expr);
+                tcx.size_ty()
+            }
+            ExprKind::VaStart(ap, last) => {
+                self.check_va_list("va_start", ap);
+                self.check_expr(last);
+                Ty::Void
+            }
+            ExprKind::VaArg(ap, name) => {
+                self.check_va_list("va_arg", ap);
+                let ty = resolved.type_names[&name.declarator.id].clone();
+                if !ty.is_scalar() {
+                    self.errors.push(
+                        Diagnostic::error(ErrorCode::InvalidVaArg, format!("`va_arg` of type `{ty}` is not supported"))
+                            .with_primary(expr.span, "")
+                            .with_note("only scalar arguments can be read"),
+                    );
+                }
+                ty
+            }
+            ExprKind::VaEnd(ap) => {
+                self.check_va_list("va_end", ap);
+                Ty::Void
+            }
+            ExprKind::VaCopy(dest, src) => {
+                self.check_va_list("va_copy", dest);
+                self.check_va_list("va_copy", src);
+                Ty::Void
+            }
+            ExprKind::Error => Ty::INT,
+        }
+    }
+
+    /// Reports an argument of `op` that is not a `va_list`.
+    fn check_va_list(&mut self, op: &str, ap: &Expr) {
+        let ty = self.check_expr(ap).decay();
+        if ty != self.resolved.tcx.va_list.clone().decay() {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::InvalidVaArg, format!("argument to `{op}` is not a `va_list` (have `{ty}`)"))
+                    .with_primary(ap.span, ""),
+            );
+        }
+    }
+
+    /// Reports `sizeof` or `_Alignof` of a struct, union or array whose
+    /// size is not known. `void` and functions have size 1, as in GNU C.
+    fn check_sizeof(&mut self, op: &str, ty: &Ty, expr: &Expr) {
+        if !self.resolved.tcx.is_complete(ty) && *ty != Ty::Void {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::InvalidSizeof, format!("invalid application of `{op}` to incomplete type `{ty}`"))
+                    .with_primary(expr.span, ""),
+            );
+        }
+    }
+
+    /// The type of `lhs <op> rhs` with both operands already decayed.
+    fn binary_ty(&mut self, op: BinaryOpKind, lhs: &Ty, rhs: &Ty, expr: &Expr) -> Ty {
+        let resolved = self.resolved;
+        let tcx = &resolved.tcx;
+        use BinaryOpKind::*;
+        match op {
+            Add if lhs.is_ptr() && rhs.is_integer() => lhs.clone(),
+            Add if lhs.is_integer() && rhs.is_ptr() => rhs.clone(),
+            Sub if lhs.is_ptr() && rhs.is_integer() => lhs.clone(),
+            Sub if lhs.is_ptr() && rhs.is_ptr() => tcx.ptrdiff_ty(),
+            EqEq | Ne | Le | Lt | Ge | Gt | LogAnd | LogOr if lhs.is_scalar() && rhs.is_scalar() => Ty::INT,
+            Comma => rhs.clone(),
+            Shl | Shr if lhs.is_integer() && rhs.is_integer() => tcx.promote(lhs),
+            _ if lhs.is_integer() && rhs.is_integer() => tcx.usual_arith(lhs, rhs),
+            _ => {
+                self.errors.push(
+                    Diagnostic::error(
+                        ErrorCode::InvalidOperands,
+                        format!("invalid operands to binary `{}` (have `{lhs}` and `{rhs}`)", op.as_str()),
+                    )
+                    .with_primary(expr.span, ""),
+                );
+                Ty::INT
+            }
+        }
+    }
+
+    /// Reports a value that does not convert to `ty` as if by assignment
+    /// (C11 6.5.16.1): structs and unions only come from a value of the
+    /// same type, a `void` value converts to nothing, and pointers and
+    /// integers only mix through a cast, except for a null pointer
+    /// constant or a pointer tested by a `_Bool`.
+    fn check_compatible(&mut self, conv: Conversion, ty: &Ty, value_ty: &Ty, value: &Expr) {
+        let msg = if (ty.is_record() || value_ty.is_record() || *value_ty == Ty::Void) && ty != value_ty {
+            conv.describe(ty, value_ty, true)
+        } else if ty.is_ptr() && value_ty.is_integer() && !matches!(consteval::eval_int(self.resolved, value), Ok(0)) {
+            format!("incompatible integer to pointer conversion {}", conv.describe(ty, value_ty, false))
+        } else if ty.is_integer() && *ty != Ty::Int(IntKind::Bool) && value_ty.is_ptr() {
+            format!("incompatible pointer to integer conversion {}", conv.describe(ty, value_ty, false))
+        } else {
+            return;
+        };
+        self.errors.push(Diagnostic::error(ErrorCode::InvalidOperands, msg).with_primary(value.span, ""));
+    }
+
+    fn check_assignable(&mut self, ty: &Ty, lhs: &Expr) {
+        if ty.is_array() {
+            self.errors.push(
+                Diagnostic::error(ErrorCode::NotAssignable, format!("array type `{ty}` is not assignable"))
+                    .with_primary(lhs.span, ""),
+            );
+        }
+    }
+}
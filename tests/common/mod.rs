@@ -0,0 +1,146 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static SEQ: AtomicU64 = AtomicU64::new(0);
+
+fn unique_base() -> String {
+    let pid = std::process::id();
+    let tid = format!("{:?}", std::thread::current().id());
+    let n = SEQ.fetch_add(1, Ordering::Relaxed);
+    format!("chibicc_it_{pid}_{tid}_{n}")
+}
+
+/// Runs the compiler on `source` with the extra command-line `args`,
+/// leaving the assembly on stdout and the diagnostics on stderr.
+pub fn compile(source: &str, args: &[&str]) -> Output {
+    let src_path = std::env::temp_dir().join(format!("{}.c", unique_base()));
+    fs::write(&src_path, source).expect("write src failed");
+
+    let cc = env!("CARGO_BIN_EXE_chibicc-for-rust");
+    let out = Command::new(cc)
+        .arg(&src_path)
+        .args(args)
+        .output()
+        .expect("invoke compiler failed");
+    let _ = fs::remove_file(&src_path);
+    out
+}
+
+/// The diagnostics of a `compile` run, with Unix line endings.
+pub fn stderr_of(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).replace("\r\n", "\n")
+}
+
+pub fn compile_and_run(source: &str) -> Result<i32, String> {
+    compile_and_capture(source).map(|(code, _)| code)
+}
+
+/// Like `compile_and_run`, but also returns what the program wrote to stdout.
+pub fn compile_and_capture(source: &str) -> Result<(i32, String), String> {
+    compile_and_link(source, None)
+}
+
+/// Compiles `source` and links it with `other`, a second translation unit
+/// built by the system C compiler, then runs the result.
+pub fn compile_and_run_linked(source: &str, other: &str) -> Result<i32, String> {
+    compile_and_link(source, Some(other)).map(|(code, _)| code)
+}
+
+fn compile_and_link(source: &str, other: Option<&str>) -> Result<(i32, String), String> {
+    let dir = std::env::temp_dir();
+    let base = unique_base();
+    let asm_path = dir.join(format!("{base}.s"));
+    let exe_path = dir.join(format!("{base}.exe"));
+    let other_path = dir.join(format!("{base}_other.c"));
+
+    let out = compile(source, &[]);
+    if !out.status.success() {
+        let stdout = String::from_utf8_lossy(&out.stdout).replace("\r\n", "\n");
+        let stderr = stderr_of(&out);
+        return Err(format!(
+            "compiler failed.\nstdout:\n{stdout}\nstderr:\n{stderr}"
+        ));
+    }
+
+    fs::write(&asm_path, &out.stdout).map_err(|e| format!("write asm failed: {e}"))?;
+
+    let mut clang = Command::new("clang");
+    clang.args(["-o", exe_path.to_str().unwrap(), asm_path.to_str().unwrap()]);
+    if let Some(other) = other {
+        fs::write(&other_path, other).map_err(|e| format!("write src failed: {e}"))?;
+        clang.arg(&other_path);
+    }
+    let clang = clang.output().map_err(|e| format!("invoke clang failed: {e}"))?;
+    let _ = fs::remove_file(&other_path);
+
+    if !clang.status.success() {
+        let stderr = String::from_utf8_lossy(&clang.stderr).replace("\r\n", "\n");
+        let _ = fs::remove_file(&asm_path);
+        return Err(format!("clang failed:\n{stderr}"));
+    }
+
+    let run = Command::new(&exe_path)
+        .output()
+        .map_err(|e| format!("run exe failed: {e}"))?;
+
+    let code = run
+        .status
+        .code()
+        .ok_or_else(|| "process terminated by signal".to_string())?;
+
+    let _ = fs::remove_file(&asm_path);
+    let _ = fs::remove_file(&exe_path);
+
+    let stdout = String::from_utf8_lossy(&run.stdout).replace("\r\n", "\n");
+    Ok((code, stdout))
+}
+
+/// Builds `source` with the system C compiler instead and runs it, so a
+/// test can check that both compilers agree.
+pub fn compile_and_run_with_cc(source: &str) -> Result<i32, String> {
+    let dir = std::env::temp_dir();
+    let base = unique_base();
+    let src_path = dir.join(format!("{base}.c"));
+    let exe_path = dir.join(format!("{base}.exe"));
+
+    fs::write(&src_path, source).map_err(|e| format!("write src failed: {e}"))?;
+
+    let clang = Command::new("clang")
+        .args(["-w", "-o", exe_path.to_str().unwrap(), src_path.to_str().unwrap()])
+        .output()
+        .map_err(|e| format!("invoke clang failed: {e}"))?;
+    let _ = fs::remove_file(&src_path);
+
+    if !clang.status.success() {
+        let stderr = String::from_utf8_lossy(&clang.stderr).replace("\r\n", "\n");
+        return Err(format!("clang failed:\n{stderr}"));
+    }
+
+    let run = Command::new(&exe_path)
+        .output()
+        .map_err(|e| format!("run exe failed: {e}"))?;
+    let _ = fs::remove_file(&exe_path);
+
+    run.status
+        .code()
+        .ok_or_else(|| "process terminated by signal".to_string())
+}
+
+pub fn run(source: &str) -> i32 {
+    compile_and_run(source).unwrap_or_else(|err| panic!("{err}"))
+}
+
+pub fn run_stdout(source: &str) -> (i32, String) {
+    compile_and_capture(source).unwrap_or_else(|err| panic!("{err}"))
+}
+
+pub fn run_linked(source: &str, other: &str) -> i32 {
+    compile_and_run_linked(source, other).unwrap_or_else(|err| panic!("{err}"))
+}
+
+pub fn run_with_cc(source: &str) -> i32 {
+    compile_and_run_with_cc(source).unwrap_or_else(|err| panic!("{err}"))
+}
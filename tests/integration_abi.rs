@@ -0,0 +1,58 @@
+mod common;
+
+use common::{compile, run, stderr_of};
+
+/// Compiles `source` and returns the emitted instructions, one per entry.
+fn asm_of(source: &str, args: &[&str]) -> Vec<String> {
+    let out = compile(source, args);
+    assert!(out.status.success(), "{}", stderr_of(&out));
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(not(windows))]
+#[test]
+fn default_abi_follows_the_host() {
+    assert_eq!(run("main() { return abs(-5); }"), 5);
+}
+
+#[test]
+fn abi_is_chosen_on_the_command_line() {
+    let src = "main() { return abs(-5); }";
+    assert!(asm_of(src, &["--abi=sysv"]).contains(&"pop rdi".to_string()));
+    assert!(asm_of(src, &["--abi=win64"]).contains(&"pop rcx".to_string()));
+}
+
+#[test]
+fn unknown_abi_is_rejected() {
+    let out = compile("main() { return 0; }", &["--abi=x86"]);
+    assert_eq!(out.status.code(), Some(2));
+    let err = stderr_of(&out);
+    assert!(err.contains("unknown abi `x86`"), "{err}");
+}
+
+#[test]
+fn frame_size_is_a_multiple_of_16() {
+    let asm = asm_of("main() { int a; int b; int c; a = 1; return a; }", &[]);
+    let prologue = asm
+        .iter()
+        .position(|line| line == "mov rbp, rsp")
+        .expect("no prologue");
+    let size = asm[prologue + 1]
+        .strip_prefix("sub rsp, ")
+        .and_then(|n| n.parse::<usize>().ok())
+        .expect("no frame allocation");
+    assert_eq!(size % 16, 0, "frame of {size} bytes");
+}
+
+#[test]
+fn calls_at_an_odd_stack_depth_are_padded() {
+    // whichever operand is evaluated second sees the first one on the stack
+    let src = "main() { return abs(-2) + abs(-3); }";
+    assert!(asm_of(src, &[]).contains(&"sub rsp, 8".to_string()));
+    assert_eq!(run(src), 5);
+}
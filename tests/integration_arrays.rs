@@ -0,0 +1,66 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn array_subscripts() {
+    assert_eq!(run("int main() { int a[3]; a[0]=1; a[1]=2; a[2]=3; return a[0]+a[1]+a[2]; }"), 6);
+    assert_eq!(run("int main() { int a[3]; *a=4; *(a+1)=5; return a[0]*a[1]; }"), 20);
+    assert_eq!(run("int main() { int a[3]; 2[a]=7; return a[2]; }"), 7);
+    assert_eq!(run("int main() { int a[4]; int i; for (i=0; i<4; i++) a[i]=i*i; return a[3]+a[2]; }"), 13);
+}
+
+#[test]
+fn arrays_do_not_overlap_neighbours() {
+    assert_eq!(run("int main() { int x=1; int a[3]; int y=2; a[0]=9; a[1]=9; a[2]=9; return x+y; }"), 3);
+}
+
+#[test]
+fn arrays_decay_to_pointers() {
+    assert_eq!(run("int main() { int a[3]; int *p=a; p[1]=8; return a[1]; }"), 8);
+    assert_eq!(run("int main() { int a[5]; return &a[4] - a; }"), 4);
+    assert_eq!(run("int main() { int a[2]; a[0]=3; a[1]=4; int *p=a+1; return *p - *(p-1); }"), 1);
+}
+
+#[test]
+fn multidimensional_arrays() {
+    let code = r#"
+        int main() {
+            int m[2][3];
+            int i;
+            int j;
+            for (i = 0; i < 2; i++)
+                for (j = 0; j < 3; j++)
+                    m[i][j] = i * 3 + j;
+            return m[0][1] + m[1][2] * 10 + (m[1] - m[0]) * 20;
+        }
+    "#;
+    assert_eq!(run(code), 111);
+}
+
+#[test]
+fn arrays_passed_to_functions() {
+    let code = r#"
+        int sum(int *a, int n) {
+            int s = 0;
+            int i;
+            for (i = 0; i < n; i++) s += a[i];
+            return s;
+        }
+        int last(int a[], int n) {
+            return a[n - 1];
+        }
+        int main() {
+            int a[4];
+            int i;
+            for (i = 0; i < 4; i++) a[i] = i + 1;
+            return sum(a, 4) * 10 + last(a, 4);
+        }
+    "#;
+    assert_eq!(run(code), 104);
+}
+
+#[test]
+fn arrays_of_pointers() {
+    assert_eq!(run("int main() { int x=1; int y=2; int *a[2]; a[0]=&x; a[1]=&y; *a[1]=5; return *a[0]+y; }"), 6);
+}
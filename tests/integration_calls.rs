@@ -1,80 +1,14 @@
-use std::fs;
-use std::process::Command;
-use std::sync::atomic::{AtomicU64, Ordering};
+mod common;
 
-static SEQ: AtomicU64 = AtomicU64::new(0);
-
-fn unique_base() -> String {
-    let pid = std::process::id();
-    let tid = format!("{:?}", std::thread::current().id());
-    let n = SEQ.fetch_add(1, Ordering::Relaxed);
-    format!("chibicc_it_{pid}_{tid}_{n}")
-}
-
-fn compile_and_run(source: &str) -> Result<i32, String> {
-    let dir = std::env::temp_dir();
-    let base = unique_base();
-    let src_path = dir.join(format!("{base}.c"));
-    let asm_path = dir.join(format!("{base}.s"));
-    let exe_path = dir.join(format!("{base}.exe"));
-
-    fs::write(&src_path, source).map_err(|e| format!("write src failed: {e}"))?;
-
-    let cc = env!("CARGO_BIN_EXE_chibicc-for-rust");
-    let out = Command::new(cc)
-        .arg(&src_path)
-        .output()
-        .map_err(|e| format!("invoke compiler failed: {e}"))?;
-
-    if !out.status.success() {
-        let stdout = String::from_utf8_lossy(&out.stdout).replace("\r\n", "\n");
-        let stderr = String::from_utf8_lossy(&out.stderr).replace("\r\n", "\n");
-        let _ = fs::remove_file(&src_path);
-        return Err(format!(
-            "compiler failed.\nstdout:\n{stdout}\nstderr:\n{stderr}"
-        ));
-    }
-
-    fs::write(&asm_path, &out.stdout).map_err(|e| format!("write asm failed: {e}"))?;
-
-    let clang = Command::new("clang")
-        .args(["-o", exe_path.to_str().unwrap(), asm_path.to_str().unwrap()])
-        .output()
-        .map_err(|e| format!("invoke clang failed: {e}"))?;
-
-    if !clang.status.success() {
-        let stderr = String::from_utf8_lossy(&clang.stderr).replace("\r\n", "\n");
-        let _ = fs::remove_file(&src_path);
-        let _ = fs::remove_file(&asm_path);
-        return Err(format!("clang failed:\n{stderr}"));
-    }
-
-    let run = Command::new(&exe_path)
-        .status()
-        .map_err(|e| format!("run exe failed: {e}"))?;
-
-    let code = run
-        .code()
-        .ok_or_else(|| "process terminated by signal".to_string())?;
-
-    let _ = fs::remove_file(&src_path);
-    let _ = fs::remove_file(&asm_path);
-    let _ = fs::remove_file(&exe_path);
-
-    Ok(code)
-}
-
-fn run(source: &str) -> i32 {
-    compile_and_run(source).unwrap_or_else(|err| panic!("{err}"))
-}
+use common::run;
 
 #[test]
 fn call_three_params_regression() {
     let code = r#"
-        fma(a, b, c){
+        int fma(int a, int b, int c){
             return a*b+c;
         }
-        main(){
+        int main(){
             return fma(5, 6, 2);
         }
     "#;
@@ -85,10 +19,10 @@ fn call_three_params_regression() {
 #[test]
 fn forward_function_call_should_work() {
     let code = r#"
-        main(){
+        int main(){
             return foo(3);
         }
-        foo(a){
+        int foo(int a){
             return a + 1;
         }
     "#;
@@ -99,10 +33,10 @@ fn forward_function_call_should_work() {
 #[test]
 fn fifth_argument_should_be_read_correctly() {
     let code = r#"
-        foo(a, b, c, d, e){
+        int foo(int a, int b, int c, int d, int e){
             return e;
         }
-        main(){
+        int main(){
             return foo(1, 2, 3, 4, 5);
         }
     "#;
@@ -112,78 +46,78 @@ fn fifth_argument_should_be_read_correctly() {
 
 #[test]
 fn test_literals() {
-    assert_eq!(run("main(){return 0;}"), 0);
-    assert_eq!(run("main(){return 42;}"), 42);
-    assert_eq!(run("main(){return 255;}"), 255);
+    assert_eq!(run("int main(){return 0;}"), 0);
+    assert_eq!(run("int main(){return 42;}"), 42);
+    assert_eq!(run("int main(){return 255;}"), 255);
 }
 
 #[test]
 fn test_addition() {
-    assert_eq!(run("main(){return 5+3;}"), 8);
-    assert_eq!(run("main(){return 1+2+3;}"), 6);
+    assert_eq!(run("int main(){return 5+3;}"), 8);
+    assert_eq!(run("int main(){return 1+2+3;}"), 6);
 }
 
 #[test]
 fn test_subtraction() {
-    assert_eq!(run("main(){return 10-3;}"), 7);
-    assert_eq!(run("main(){return 5-2-1;}"), 2);
+    assert_eq!(run("int main(){return 10-3;}"), 7);
+    assert_eq!(run("int main(){return 5-2-1;}"), 2);
 }
 
 #[test]
 fn test_multiplication() {
-    assert_eq!(run("main(){return 2*3;}"), 6);
-    assert_eq!(run("main(){return 5+6*7;}"), 47); // Your example!
+    assert_eq!(run("int main(){return 2*3;}"), 6);
+    assert_eq!(run("int main(){return 5+6*7;}"), 47); // Your example!
 }
 
 #[test]
 fn test_division() {
-    assert_eq!(run("main(){return 8/2;}"), 4);
-    assert_eq!(run("main(){return 9/2;}"), 4); // Integer division
+    assert_eq!(run("int main(){return 8/2;}"), 4);
+    assert_eq!(run("int main(){return 9/2;}"), 4); // Integer division
 }
 
 #[test]
 fn test_complex_expressions() {
-    assert_eq!(run("main(){return 5*(9-6);}"), 15);
-    assert_eq!(run("main(){return (3+5)/2;}"), 4);
+    assert_eq!(run("int main(){return 5*(9-6);}"), 15);
+    assert_eq!(run("int main(){return (3+5)/2;}"), 4);
 }
 
 #[test]
 fn test_unary_plus() {
-    assert_eq!(run("main(){return +5;}"), 5);
-    assert_eq!(run("main(){return +42;}"), 42);
-    assert_eq!(run("main(){return 0+ +10;}"), 10); // 0 + (+10)
+    assert_eq!(run("int main(){return +5;}"), 5);
+    assert_eq!(run("int main(){return +42;}"), 42);
+    assert_eq!(run("int main(){return 0+ +10;}"), 10); // 0 + (+10)
 }
 
 #[test]
 fn test_unary_minus() {
-    assert_eq!(run("main(){return 10-5;}"), 5);
-    assert_eq!(run("main(){return 5+ -3;}"), 2); // 5 + (-3)
+    assert_eq!(run("int main(){return 10-5;}"), 5);
+    assert_eq!(run("int main(){return 5+ -3;}"), 2); // 5 + (-3)
 }
 
 #[test]
 fn test_unary_in_expressions() {
-    assert_eq!(run("main(){return -5+10;}"), 5); // (-5) + 10
-    assert_eq!(run("main(){return 10+ -5;}"), 5); // 10 + (-5)
+    assert_eq!(run("int main(){return -5+10;}"), 5); // (-5) + 10
+    assert_eq!(run("int main(){return 10+ -5;}"), 5); // 10 + (-5)
 }
 
 #[test]
 fn test_precedence_with_unary() {
-    assert_eq!(run("main(){return -5*2+10;}"), 0); // ((-5)*2) + 10 = -10 + 10 = 0
+    assert_eq!(run("int main(){return -5*2+10;}"), 0); // ((-5)*2) + 10 = -10 + 10 = 0
 }
 
 #[test]
 fn test_equality_precedence_with_unary() {
-    assert_eq!(run("main(){return -5*2+10 != 0;}"), 0); // ((-5)*2) + 10 = -10 + 10 = 0
+    assert_eq!(run("int main(){return -5*2+10 != 0;}"), 0); // ((-5)*2) + 10 = -10 + 10 = 0
 }
 
 #[test]
-fn test_fn_call_without_param() {
+fn test_fn_call_with_params() {
     assert_eq!(
         run(r#"
-        fma(a, b, c){
+        int fma(int a, int b, int c){
             return a*b+c;
         }
-        main(){
+        int main(){
             return fma(5, 6, 2);
         }
         "#),
@@ -193,35 +127,129 @@ fn test_fn_call_without_param() {
 
 #[test]
 fn test_addr_deref_roundtrip() {
-    assert_eq!(run("main() { int x=3; return *&x; }"), 3);
+    assert_eq!(run("int main() { int x=3; return *&x; }"), 3);
 }
 
 #[test]
 fn test_multi_level_deref() {
-    assert_eq!(run("main() { int x=3; int y=&x; int z=&y; return **z; }"), 3);
+    assert_eq!(run("int main() { int x=3; int *y=&x; int **z=&y; return **z; }"), 3);
 }
 
 #[test]
 fn test_deref_with_addrof_plus_offset() {
-    assert_eq!(run("main() { int x=3; int y=5; return *(&x-8); }"), 5);
+    assert_eq!(run("int main() { int x=3; int y=5; return *(&x-1); }"), 5);
 }
 
 #[test]
 fn test_deref_with_addrof_minus_offset() {
-    assert_eq!(run("main() { int x=3; int y=5; return *(&y+8); }"), 3);
+    assert_eq!(run("int main() { int x=3; int y=5; return *(&y+1); }"), 3);
 }
 
 #[test]
 fn test_store_through_pointer() {
-    assert_eq!(run("main() { int x=3; int y=&x; *y=5; return x; }"), 5);
+    assert_eq!(run("int main() { int x=3; int *y=&x; *y=5; return x; }"), 5);
 }
 
 #[test]
 fn test_store_with_addrof_plus_offset() {
-    assert_eq!(run("main() { int x=3; int y=5; *(&x-8)=7; return y; }"), 7);
+    assert_eq!(run("int main() { int x=3; int y=5; *(&x-1)=7; return y; }"), 7);
 }
 
 #[test]
 fn test_store_with_addrof_minus_offset() {
-    assert_eq!(run("main() { int x=3; int y=5; *(&y+8)=7; return x; }"), 7);
+    assert_eq!(run("int main() { int x=3; int y=5; *(&y+1)=7; return x; }"), 7);
+}
+
+#[test]
+fn void_parameter_list_takes_no_arguments() {
+    assert_eq!(
+        run(r#"
+        int seven(void){
+            return 7;
+        }
+        int main(void){
+            return seven();
+        }
+        "#),
+        7
+    );
+}
+
+#[test]
+fn arguments_beyond_the_registers_are_read_from_the_stack() {
+    let code = r#"
+        int weigh(int a, int b, int c, int d, int e, int f, int g, int h){
+            return a + 2*b + 3*c + 4*d + 5*e + 6*f + 7*g + 8*h;
+        }
+        int main(){
+            return weigh(1, 1, 1, 1, 1, 1, 1, 2);
+        }
+    "#;
+    assert_eq!(run(code), 44);
+}
+
+#[test]
+fn unnamed_parameter_keeps_its_position() {
+    let code = r#"
+        int second(int, int b){
+            return b;
+        }
+        int main(){
+            return second(100, 3);
+        }
+    "#;
+    assert_eq!(run(code), 3);
+}
+
+#[test]
+fn parameters_are_assignable_locals() {
+    let code = r#"
+        int sum_to(int n){
+            int acc = 0;
+            while (n > 0) { acc = acc + n; n = n - 1; }
+            return acc;
+        }
+        int main(){
+            return sum_to(10);
+        }
+    "#;
+    assert_eq!(run(code), 55);
+}
+
+#[test]
+fn functions_are_values() {
+    assert_eq!(run("int f(){return 7;} int main(){ int (*p)() = f; return p(); }"), 7);
+    assert_eq!(run("int f(){return 7;} int main(){ return &f != 0; }"), 1);
+}
+
+#[test]
+fn calls_through_function_pointers_pass_their_arguments() {
+    let code = r#"
+        static int sub(int a, int b) { return a - b; }
+        int add(int a, int b) { return a + b; }
+        int (*op)(int, int) = sub;
+        int apply(int (*f)(int, int), int a, int b) { return f(a, b); }
+        int main() {
+            int (*ops[2])(int, int) = {add, &sub};
+            return apply(add, 30, 12) + op(10, 4) + ops[0](1, 2) + apply(op, 9, 1);
+        }
+    "#;
+    assert_eq!(run(code), 42 + 6 + 3 + 8);
+}
+
+#[test]
+fn any_expression_of_function_type_can_be_called() {
+    let code = r#"
+        struct S { int (*cb)(void); int n; };
+        int seven(void) { return 7; }
+        int twice(int x) { return 2 * x; }
+        int (*pick(int i))(int) { return i ? twice : 0; }
+        int main() {
+            int (*fp)(int) = twice;
+            struct S s = {seven, 1};
+            struct S *p = &s;
+            return (*fp)(10) + (**fp)(1) + p->cb() + s.cb() + pick(1)(50) + (&seven)();
+        }
+    "#;
+    assert_eq!(run(code), 20 + 2 + 7 + 7 + 100 + 7);
 }
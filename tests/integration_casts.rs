@@ -0,0 +1,102 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn sizeof_types_and_expressions() {
+    let code = r#"
+        struct s { char c; long l; };
+        int main() {
+            int a[3][5];
+            char *p;
+            return sizeof(int) + sizeof(char) + sizeof(struct s) + sizeof a + sizeof a[0] + sizeof p
+                + sizeof(int *[4]) + sizeof(int (*)[4]) + sizeof(long long) + sizeof "abc";
+        }
+    "#;
+    assert_eq!(run(code), 4 + 1 + 16 + 60 + 20 + 8 + 32 + 8 + 8 + 4);
+}
+
+#[test]
+fn sizeof_does_not_evaluate_its_operand() {
+    assert_eq!(run("int main() { int x = 1; int n = sizeof(x = 5) + sizeof x++; return x * 10 + n; }"), 18);
+}
+
+#[test]
+fn sizeof_is_an_unsigned_long_constant() {
+    let code = r#"
+        int g[sizeof(long) * 2];
+        int main() {
+            char buf[sizeof g / sizeof g[0]];
+            return sizeof buf + (sizeof(int) - 5 > 0) + sizeof(sizeof(char));
+        }
+    "#;
+    assert_eq!(run(code), 16 + 1 + 8);
+}
+
+#[test]
+fn alignof_types() {
+    let code = r#"
+        struct s { char c; int i; };
+        int main() {
+            return _Alignof(char) + _Alignof(short) * 10 + _Alignof(struct s) * 20 + _Alignof(long[3]);
+        }
+    "#;
+    assert_eq!(run(code), 1 + 20 + 80 + 8);
+}
+
+#[test]
+fn casts_truncate_and_extend() {
+    let code = r#"
+        int main() {
+            long big = 0x1234567890;
+            int n = -1;
+            return ((char)300 == 44) + ((unsigned char)-1 == 255) + ((short)big == 0x7890)
+                + ((unsigned)n == 4294967295L) + ((long)n == -1) + ((_Bool)256) + ((int)big == 0x34567890);
+        }
+    "#;
+    assert_eq!(run(code), 7);
+}
+
+#[test]
+fn casts_between_pointers_and_integers() {
+    let code = r#"
+        int main() {
+            int x = 0x01020304;
+            char *p = (char *)&x;
+            long addr = (long)&x;
+            int *q = (int *)addr;
+            (void)p;
+            return p[0] + p[3] * 10 + (*q == x) * 100;
+        }
+    "#;
+    assert_eq!(run(code), 4 + 10 + 100);
+}
+
+#[test]
+fn casts_bind_tighter_than_binary_operators() {
+    let code = r#"
+        typedef unsigned char byte_t;
+        int main() {
+            int n = 511;
+            return (byte_t)n + 1 - -(char)n;
+        }
+    "#;
+    assert_eq!(run(code), 255 + 1 - 1);
+}
+
+#[test]
+fn abstract_declarators_in_prototypes() {
+    let code = r#"
+        int first(int *, int[]);
+        long apply(int (*)[2], long);
+        int main() {
+            int a[2];
+            int (*pa)[2] = &a;
+            a[0] = 3;
+            return first(a, a) + apply(pa, 4) + sizeof pa;
+        }
+        int first(int *p, int q[]) { return *p + q[0]; }
+        long apply(int (*row)[2], long n) { return (*row)[0] * n; }
+    "#;
+    assert_eq!(run(code), 6 + 12 + 8);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn globals_take_address_constants() {
+    let code = r#"
+        int arr[4] = {1, 2, 3, 4};
+        int *third = &arr[2];
+        int *last = arr + 3;
+        int *first = &arr[3] - 3;
+        char *tail = "hello" + 3;
+        struct pair { int a, b; } p = {5, 6};
+        int *pb = &p.b;
+        long addr = (long)&arr[1];
+        int **pp = &third;
+        int *ptrs[] = {arr, &p.a, 0};
+        int main() {
+            return *third + *last + *first + *tail + *pb + *(int *)addr + **pp + *ptrs[1] + (ptrs[2] == 0);
+        }
+    "#;
+    assert_eq!(run(code), 3 + 4 + 1 + 'l' as i32 + 6 + 2 + 3 + 5 + 1);
+}
+
+#[test]
+fn static_locals_take_address_constants() {
+    let code = r#"
+        int counter;
+        int *next() { static int *p = &counter; return p; }
+        int main() {
+            static int slots[3];
+            static int *end = slots + 3;
+            *next() = 7;
+            return counter + (end - slots);
+        }
+    "#;
+    assert_eq!(run(code), 10);
+}
+
+#[test]
+fn integer_constants_wrap_to_their_type() {
+    let code = r#"
+        int overflow = 4294967295u + 1 == 0;
+        int narrowed = (char)300;
+        int shifted = -16 >> 2;
+        int divided = -7 / 2;
+        long unsigned_div = -8 / 2u;
+        int unsigned_cmp = -1 > 0u;
+        int promoted = (unsigned char)255 + 1 == 256;
+        int main() {
+            return overflow + narrowed + (shifted == -4) + (divided == -3) + (unsigned_div == 2147483644)
+                + unsigned_cmp + promoted;
+        }
+    "#;
+    assert_eq!(run(code), 1 + 44 + 1 + 1 + 1 + 1 + 1);
+}
+
+#[test]
+fn array_sizes_and_case_labels_are_constant_expressions() {
+    let code = r#"
+        struct s { char c; int i; long l; };
+        char pad[(long)&((struct s *)0)->l];
+        int grid[sizeof(int) > 2 ? 3 : 1][(unsigned)-1 > 0 ? 2 : 1];
+        int classify(long n) {
+            switch (n) {
+            case sizeof(long) * 2: return 1;
+            case (char)257: return 2;
+            case -1u >> 30: return 3;
+            }
+            return 0;
+        }
+        int main() {
+            return sizeof pad * 10 + sizeof grid / sizeof(int) + classify(16) + classify(1) + classify(3);
+        }
+    "#;
+    assert_eq!(run(code), 80 + 6 + 1 + 2 + 3);
+}
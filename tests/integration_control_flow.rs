@@ -0,0 +1,144 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn do_while_runs_the_body_first() {
+    assert_eq!(run("int main() { int i = 10; do i = i + 1; while (i < 5); return i; }"), 11);
+    assert_eq!(run("int main() { int i = 0, n = 0; do { n += i; i++; } while (i < 5); return n; }"), 10);
+}
+
+#[test]
+fn break_and_continue_bind_to_the_innermost_loop() {
+    let code = r#"
+        int main() {
+            int n = 0;
+            for (;;) {
+                n++;
+                if (n == 3) continue;
+                if (n > 6) break;
+            }
+            int i = 0, m = 0;
+            while (1) {
+                i++;
+                if (i % 2) continue;
+                for (;;) break;
+                if (i >= 10) break;
+                m += i;
+            }
+            do { m++; if (m > 0) break; m = 100; } while (1);
+            return n * 10 + m;
+        }
+    "#;
+    assert_eq!(run(code), 70 + 21);
+}
+
+#[test]
+fn switch_jumps_to_the_matching_case() {
+    let code = r#"
+        int classify(int x) {
+            switch (x) {
+            case 0: return 10;
+            case 1:
+            case 2: return 20;
+            case 3 + 4: return 70;
+            default: return -1;
+            }
+        }
+        int main() { return classify(0) + classify(2) + classify(7) + classify(9); }
+    "#;
+    assert_eq!(run(code), 99);
+}
+
+#[test]
+fn switch_cases_fall_through() {
+    let code = r#"
+        int main() {
+            int n = 0;
+            switch (2) {
+            case 1: n += 1;
+            case 2: n += 2;
+            case 3: n += 3; break;
+            case 4: n += 4;
+            }
+            switch (5) { case 1: n = 100; }
+            switch (9) { default: n += 10; case 1: n += 20; }
+            return n;
+        }
+    "#;
+    assert_eq!(run(code), 35);
+}
+
+#[test]
+fn continue_inside_switch_continues_the_loop() {
+    let code = r#"
+        int main() {
+            int n = 0, i;
+            for (i = 0; i < 5; i++) {
+                switch (i) {
+                case 1: continue;
+                case 3: break;
+                default: n += 10;
+                }
+                n++;
+            }
+            return n;
+        }
+    "#;
+    assert_eq!(run(code), 34);
+}
+
+#[test]
+fn switch_compares_in_the_promoted_type() {
+    let code = r#"
+        int main() {
+            unsigned char c = 255;
+            long big = 1L << 40;
+            int n = 0;
+            switch (c) { case -1: n = 1; break; case 255: n = 2; break; }
+            switch (big) { case 0: break; case 1L << 40: n += 10; }
+            return n;
+        }
+    "#;
+    assert_eq!(run(code), 12);
+}
+
+#[test]
+fn goto_jumps_within_the_function() {
+    let code = r#"
+        int main() {
+            int i = 0;
+        again:
+            i++;
+            if (i < 5) goto again;
+            goto done;
+            i = 100;
+        done:
+            return i;
+        }
+        int other() { goto done; done: return 0; }
+    "#;
+    assert_eq!(run(code), 5);
+}
+
+#[test]
+fn for_declares_its_variables_for_the_loop() {
+    let code = r#"
+        typedef int T;
+        int main() {
+            int i = 100, n = 0;
+            for (int i = 0, j = 10; i < j; i++, j--)
+                n += i;
+            for (T T = 0; T < 3; T++) {
+                int i = T;
+                n += i;
+            }
+            for (long k = 0; k < 2; k++)
+                for (int k = 5; k > 3; k--)
+                    n++;
+            T t = 1;
+            return i + n + t;
+        }
+    "#;
+    assert_eq!(run(code), 100 + 10 + 3 + 4 + 1);
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{run, run_with_cc};
+
+/// Runs every `main` body through both compilers and reports the ones whose
+/// exit codes differ.
+fn assert_same_as_cc(bodies: &[&str]) {
+    let mismatches = bodies
+        .iter()
+        .filter_map(|body| {
+            let source = format!("int main() {{ {body} }}");
+            let (ours, theirs) = (run(&source), run_with_cc(&source));
+            (ours != theirs).then(|| format!("`{body}`: got {ours}, cc gives {theirs}"))
+        })
+        .collect::<Vec<_>>();
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn signed_and_unsigned_comparisons() {
+    assert_same_as_cc(&[
+        "int a = -1; unsigned b = 1; return a < b;",
+        "int a = -1; unsigned long b = 1; return a < b;",
+        "int a = -1; long b = 1; return a < b;",
+        "unsigned a = 1; long b = -1; return a > b;",
+        "unsigned a = 10; int b = -20; return a + b > 0;",
+        "long a = 10; unsigned b = 20; return a - b < 0;",
+        "unsigned char c = 200; char d = 100; return c + d > 255;",
+        "unsigned short s = 1; short t = -1; return s > t;",
+        "int a = -1; unsigned b = 0; return (1 ? a : b) > 0;",
+    ]);
+}
+
+#[test]
+fn unsigned_division_and_shifts() {
+    assert_same_as_cc(&[
+        "unsigned a = 7; int b = -2; return a / b;",
+        "int a = -7; int b = 2; return a / b + 10;",
+        "unsigned a = -7; return a % 10;",
+        "int a = -7; return a % 3 + 5;",
+        "int a = -16; return a >> 2;",
+        "unsigned a = -16; return a >> 28;",
+        "unsigned a = 3; return a << 31 >> 31;",
+        "int a = -3; unsigned b = 2; return (a * b) >> 28;",
+        "long long x = -5; unsigned long long y = 3; return x / y;",
+    ]);
+}
+
+#[test]
+fn arithmetic_wraps_at_the_operand_width() {
+    assert_same_as_cc(&[
+        "unsigned a = 4294967295; a = a + 1; return a == 0;",
+        "unsigned a = 4294967295; return a + 1 == 0;",
+        "unsigned a = 3000000000; unsigned b = 3000000000; return (a + b) / 1000000000;",
+        "unsigned a = 0; return -a > 0;",
+        "unsigned a = 1; return -a >> 31;",
+        "unsigned char a = 255; return ~a < 0;",
+        "unsigned short s = 65535; s += 1; return s;",
+        "char c = 127; c += 1; return c < 0;",
+        "unsigned a = 5; a -= 10; return a > 100;",
+    ]);
+}
+
+#[test]
+fn conversions_on_assignment() {
+    assert_same_as_cc(&[
+        "short s = -1; unsigned u = s; return u == 4294967295;",
+        "unsigned u = 4294967295; long l = u; return l > 0;",
+        "int i = -1; unsigned long ul = i; return ul + 1 == 0;",
+        "char c = -1; unsigned char d = c; return d;",
+        "int i = 1000; char c = i; return c;",
+        "_Bool b = 256; return b;",
+        "_Bool b = 1; b = 5 - 5; return b;",
+        "long l = 4294967296; int i = l; return i == 0;",
+    ]);
+}
+
+#[test]
+fn conversions_at_calls_and_returns() {
+    let code = r#"
+        int widen(char c) { return c; }
+        unsigned char narrow() { return 511; }
+        _Bool truth(long x) { return x; }
+        long sub(unsigned a, unsigned b) { return a - b; }
+        int main() {
+            return widen(300) + narrow() + truth(4294967296) + (sub(1, 2) > 0);
+        }
+    "#;
+    assert_eq!(run(code), run_with_cc(code));
+}
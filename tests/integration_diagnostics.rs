@@ -0,0 +1,707 @@
+mod common;
+
+use common::{compile, stderr_of};
+
+#[test]
+fn successful_compile_exits_zero() {
+    let out = compile("main(){ return 0; }", &[]);
+    assert!(out.status.success());
+    assert!(out.stderr.is_empty());
+}
+
+#[test]
+fn unknown_character_is_reported_with_exit_code() {
+    let out = compile("main() {\n  return 1 @ 2;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(out.stdout.is_empty());
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0001]: unknown start of token: `@`"), "{err}");
+    assert!(err.contains(":2:12"), "{err}");
+    assert!(err.contains("2 |   return 1 @ 2;\n  |            ^"), "{err}");
+}
+
+#[test]
+fn caret_uses_column_not_byte_offset() {
+    let out = compile("main() {\n  int x = 1;\n  return y;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0200]: use of undeclared identifier `y`"), "{err}");
+    assert!(err.contains("3 |   return y;\n  |          ^ not found in this scope"), "{err}");
+}
+
+#[test]
+fn globals_are_not_visible_before_their_declaration() {
+    let out = compile("int main() {\n  return x;\n}\nint x = 3;\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0200]: use of undeclared identifier `x`"), "{err}");
+    assert!(err.contains("2 |   return x;\n  |          ^"), "{err}");
+}
+
+#[test]
+fn invalid_assignment_target_is_an_error() {
+    let out = compile("main() { 3 = 4; return 0; }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0300]"));
+}
+
+#[test]
+fn warnings_do_not_fail_the_build() {
+    let out = compile("main() { return foo(); } ", &[]);
+    assert!(out.status.success());
+    assert!(stderr_of(&out).contains("warning[E0201]: implicit declaration of function `foo`"));
+}
+
+#[test]
+fn json_format_emits_one_object_per_diagnostic() {
+    let out = compile("main() {\n  return y + foo();\n}\n", &["--error-format=json"]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    let lines: Vec<&str> = err.lines().collect();
+    assert_eq!(lines.len(), 2, "{err}");
+    assert!(lines.iter().all(|l| l.starts_with('{') && l.ends_with('}')));
+    assert!(lines[0].contains(r#""code":"E0200","severity":"error""#), "{err}");
+    assert!(lines[0].contains(r#""line_start":2,"line_end":2,"column_start":10,"column_end":11"#), "{err}");
+    assert!(lines[0].contains(r#""children":[{"severity":"help","message":"declare it first, e.g. `int y;`"}]"#), "{err}");
+    assert!(lines[1].contains(r#""code":"E0201","severity":"warning""#), "{err}");
+}
+
+#[test]
+fn json_format_escapes_messages() {
+    let out = compile(r"main() { return \; }", &["--error-format=json"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains(r#""message":"unknown start of token: `\\\\`""#));
+}
+
+#[test]
+fn every_lexical_error_is_reported_in_one_run() {
+    let out = compile("main() {\n  int a = 1 @ 2;\n  int b = $;\n  return a ` b;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("unknown start of token: `@`"), "{err}");
+    assert!(err.contains("unknown start of token: `$`"), "{err}");
+    assert!(err.contains("unknown start of token: ```"), "{err}");
+    assert!(err.contains("aborting due to 3 previous errors"), "{err}");
+    assert!(!err.contains("panicked"), "{err}");
+}
+
+#[test]
+fn non_ascii_characters_are_reported_once() {
+    let out = compile("main() { return 1 § 2; }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("unknown start of token: `\\u{a7}`"), "{err}");
+    assert!(err.contains("aborting due to 1 previous error"), "{err}");
+}
+
+#[test]
+fn unterminated_block_comment_points_at_its_start() {
+    let out = compile("main() {\n  return 0;\n}\n/* never closed\n\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0003]: unterminated block comment"), "{err}");
+    assert!(err.contains(":4:1"), "{err}");
+    assert!(err.contains("4 | /* never closed\n  | ^^ comment starts here"), "{err}");
+}
+
+#[test]
+fn spans_after_line_splice_point_into_original_source() {
+    let out = compile("main() {\n  int x = 1;\n  return x + \\\n   y;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("4 |    y;\n  |    ^ not found in this scope"), "{err}");
+}
+
+#[test]
+fn unterminated_string_is_reported() {
+    let out = compile("main() {\n  puts(\"oops);\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0004]: unterminated string literal"), "{err}");
+    assert!(err.contains(":2:8"), "{err}");
+}
+
+#[test]
+fn invalid_escapes_are_reported() {
+    let out = compile("main() {\n  return '\\x' + '\\777' + '';\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0005]: `\\x` used with no following hex digits"), "{err}");
+    assert!(err.contains("error[E0005]: octal escape sequence out of range"), "{err}");
+    assert!(err.contains("error[E0006]: empty character constant"), "{err}");
+}
+
+#[test]
+fn unknown_escape_is_a_warning() {
+    let out = compile("main() { return '\\q'; }", &[]);
+    assert!(out.status.success());
+    assert!(stderr_of(&out).contains("warning[E0008]: unknown escape sequence `\\q`"));
+}
+
+#[test]
+fn mixed_string_prefixes_are_rejected() {
+    let out = compile("main() { puts(u\"a\" L\"b\"); return 0; }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0009]"));
+}
+
+#[test]
+fn u8_prefix_is_only_for_strings() {
+    let out = compile("int main() {\n  return u8'a';\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("2 |   return u8'a';\n  |            ^^^ expected `;`"), "{err}");
+}
+
+#[test]
+fn malformed_integer_literals_are_reported() {
+    let out = compile("main() {\n  return 09 + 0x + 12abc + 0b2 + 1lL;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0010]: invalid digit `9` in octal constant"), "{err}");
+    assert!(err.contains("error[E0010]: no digits in hexadecimal constant"), "{err}");
+    assert!(err.contains("error[E0010]: invalid suffix `abc` on integer constant"), "{err}");
+    assert!(err.contains("error[E0010]: invalid digit `2` in binary constant"), "{err}");
+    assert!(err.contains("error[E0010]: invalid suffix `lL` on integer constant"), "{err}");
+}
+
+#[test]
+fn overflowing_integer_literals_are_reported() {
+    for lit in ["18446744073709551616", "9223372036854775808", "0x1ffffffffffffffff"] {
+        let out = compile(&format!("main() {{ return {lit}; }}"), &[]);
+        assert_eq!(out.status.code(), Some(1), "{lit}");
+        assert!(out.stdout.is_empty());
+        assert!(stderr_of(&out).contains("error[E0011]: integer constant is too large"), "{lit}");
+    }
+    let out = compile("main() { return 9223372036854775808u == 0; }", &[]);
+    assert!(out.status.success());
+}
+
+#[test]
+fn float_literals_are_rejected() {
+    let out = compile("main() { return 1.5; }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0012]"));
+    for lit in ["0.5", "1e5", ".5", "1e+5", "08.25"] {
+        let out = compile(&format!("int main() {{\n  return {lit};\n}}\n"), &[]);
+        assert_eq!(out.status.code(), Some(1));
+        let err = stderr_of(&out);
+        let carets = "^".repeat(lit.len());
+        assert!(err.contains("error[E0012]: floating-point constants are not supported"), "{err}");
+        assert!(err.contains(&format!("2 |   return {lit};\n  |          {carets}\n")), "{err}");
+        assert!(err.contains("aborting due to 1 previous error"), "{err}");
+    }
+}
+
+#[test]
+fn invalid_universal_character_names_are_reported() {
+    let out = compile("main() {\n  int \\u12 = 1;\n  int a\\u0041 = 2;\n  int \\u0301x = 3;\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0013]: incomplete universal character name `\\u12`"), "{err}");
+    assert!(err.contains("error[E0013]: `\\u0041` is not a valid universal character"), "{err}");
+    assert!(err.contains("error[E0013]: character `\\u{301}` is not allowed at the start of an identifier"), "{err}");
+    assert!(err.contains("aborting due to 3 previous errors"), "{err}");
+}
+
+#[test]
+fn keywords_are_not_identifiers() {
+    let out = compile("main() { int x = 1; return while + x; }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("found keyword `while`"));
+}
+
+#[test]
+fn parser_recovers_and_reports_every_syntax_error() {
+    let src = "main() {\n  int x = ;\n  return 1 2;\n  int = 3;\n  if (1 { return 0; }\n  return 5\n}\n";
+    let out = compile(src, &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(!err.contains("panicked"), "{err}");
+    assert!(err.contains("error[E0101]: expected expression, found `;`\n --> "), "{err}");
+    assert!(err.contains(":2:11"), "{err}");
+    assert!(err.contains("error[E0100]: expected `;`, found literal `2`"), "{err}");
+    assert!(err.contains(":3:12"), "{err}");
+    assert!(err.contains("error[E0101]: expected identifier, found `=`"), "{err}");
+    assert!(err.contains("error[E0100]: expected `)`, found `{`"), "{err}");
+    assert!(err.contains("error[E0100]: expected `;`, found `}`"), "{err}");
+    assert!(err.contains(":7:1"), "{err}");
+    assert!(err.contains("aborting due to 5 previous errors"), "{err}");
+}
+
+#[test]
+fn one_error_per_token() {
+    let out = compile("main() { return ); }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("expected expression, found `)`"), "{err}");
+    assert!(err.contains("aborting due to 1 previous error"), "{err}");
+}
+
+#[test]
+fn parser_recovers_at_the_next_function() {
+    let src = "int 3() { return 0; }\nfoo() { return (1 + ; }\nmain() { return 0 }\n";
+    let out = compile(src, &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(!err.contains("panicked"), "{err}");
+    assert!(err.contains("expected identifier, found literal `3`"), "{err}");
+    assert!(err.contains("expected expression, found `;`"), "{err}");
+    assert!(err.contains("expected `;`, found `}`"), "{err}");
+    assert!(err.contains("aborting due to 3 previous errors"), "{err}");
+}
+
+#[test]
+fn unclosed_function_body_is_reported_at_eof() {
+    let out = compile("main() {\n  return 0;\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("expected `}`, found end of file"));
+}
+
+#[test]
+fn invalid_array_sizes_are_reported() {
+    let out = compile("int main() {\n  int n = 3;\n  int a[n];\n  int b[];\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0202]: array size is not an integer constant"), "{err}");
+    assert!(err.contains("3 |   int a[n];\n  |         ^"), "{err}");
+    assert!(err.contains("error[E0202]: array size missing in `b`"), "{err}");
+    assert!(err.contains("4 |   int b[];\n  |       ^"), "{err}");
+
+
+    // a zero length is a length, unlike a missing one
+    let out = compile("int main() {\n  int z[0];\n  return sizeof z;\n}\n", &[]);
+    assert!(out.status.success(), "{}", stderr_of(&out));
+}
+
+#[test]
+fn nested_and_static_arrays_need_a_size() {
+    let out = compile("int main() {\n  int a[][3];\n  static int s[];\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0202]: array size missing in `a`"), "{err}");
+    assert!(err.contains("2 |   int a[][3];\n  |       ^"), "{err}");
+    assert!(err.contains("error[E0202]: array size missing in `s`"), "{err}");
+}
+
+#[test]
+fn locals_redeclared_in_one_scope_are_reported() {
+    let out = compile("int f(int p, int p) {\n  int a = 1;\n  int a = 2;\n  int p;\n  { int a = 3; }\n  return a;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("1 | int f(int p, int p) {\n  |                  ^"), "{err}");
+    assert!(err.contains("3 |   int a = 2;\n  |       ^"), "{err}");
+    assert!(err.contains("4 |   int p;\n  |       ^"), "{err}");
+    assert_eq!(err.matches("error[E0204]: redefinition of").count(), 3, "{err}");
+}
+
+#[test]
+fn invalid_type_specifier_combinations_are_reported() {
+    let out = compile("int main() {\n  short long x;\n  unsigned signed y;\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0102]: cannot combine `long` with the previous type specifiers"), "{err}");
+    assert!(err.contains("error[E0102]: cannot combine `signed` with the previous type specifiers"), "{err}");
+}
+
+#[test]
+fn operand_type_errors_are_reported() {
+    let out = compile(
+        "int main() {\n  int x = 1;\n  int *p = &x;\n  int a[2];\n  *x;\n  p + p;\n  -p;\n  a = p;\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0400]: type `int` cannot be dereferenced"), "{err}");
+    assert!(err.contains("5 |   *x;\n  |   ^"), "{err}");
+    assert!(err.contains("error[E0401]: invalid operands to binary `+` (have `int *` and `int *`)"), "{err}");
+    assert!(err.contains("error[E0401]: invalid operand to unary `-` (have `int *`)"), "{err}");
+    assert!(err.contains("error[E0402]: array type `int[2]` is not assignable"), "{err}");
+}
+
+#[test]
+fn struct_errors_are_reported() {
+    let out = compile(
+        "struct s { int a; };\nstruct s { int b; };\nstruct t;\nint main() {\n  struct t y;\n  union s *u;\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0204]: redefinition of `struct s`"), "{err}");
+    assert!(err.contains("error[E0205]: variable `y` has incomplete type `struct t`"), "{err}");
+    assert!(err.contains("error[E0203]: `s` was declared as `struct s`, not as a union"), "{err}");
+}
+
+#[test]
+fn member_errors_are_reported() {
+    let out = compile(
+        "struct s { int a; };\nint main() {\n  struct s x;\n  x.b = 1;\n  x.a.c = 2;\n  x = 3;\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0403]: no member named `b` in `struct s`"), "{err}");
+    assert!(err.contains("error[E0404]: member reference base type `int` is not a structure or union"), "{err}");
+    assert!(err.contains("error[E0401]: assigning to `struct s` from incompatible type `int`"), "{err}");
+}
+
+#[test]
+fn enum_errors_are_reported() {
+    let out = compile(
+        "int main() {\n  int n = 1;\n  enum e { A, B = n, A };\n  struct e *p;\n  A = 3;\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0206]: value of enumerator `B` is not an integer constant"), "{err}");
+    assert!(err.contains("3 |   enum e { A, B = n, A };\n  |                   ^"), "{err}");
+    assert!(err.contains("error[E0204]: redefinition of `A`"), "{err}");
+    assert!(err.contains("error[E0203]: `e` was declared as `enum e`, not as a struct"), "{err}");
+}
+
+#[test]
+fn enums_take_one_body() {
+    let out = compile("enum e;\nenum e { A };\nenum e { B };\nint main() {\n  return A;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0204]: redefinition of `enum e`"), "{err}");
+    assert!(err.contains("3 | enum e { B };"), "{err}");
+    assert!(err.contains("aborting due to 1 previous error"), "{err}");
+}
+
+#[test]
+fn enumerators_are_not_assignable() {
+    let out = compile("int main() {\n  enum { A };\n  A = 3;\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0300]: expression is not assignable"));
+}
+
+#[test]
+fn typedef_names_are_not_expressions() {
+    let out = compile("typedef int T;\nint main() {\n  return T;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0207]: unexpected type name `T`: expected expression"), "{err}");
+    assert!(err.contains("3 |   return T;\n  |          ^"), "{err}");
+}
+
+#[test]
+fn typedefs_take_no_initializer() {
+    let out = compile("int main() {\n  typedef int T = 1;\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0100]"));
+}
+
+#[test]
+fn global_errors_are_reported() {
+    let out = compile(
+        "int n;\nint x = n + 1;\nint y = 1;\nint y = 2;\nint a[2] = 3;\nint main() {\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0206]: initializer element is not a compile-time constant"), "{err}");
+    assert!(err.contains("2 | int x = n + 1;\n  |         ^"), "{err}");
+    assert!(err.contains("error[E0204]: redefinition of `y`"), "{err}");
+}
+
+#[test]
+fn function_redefinitions_are_reported() {
+    let out = compile("int f() { return 1; }\nint f() { return 2; }\nint main() {\n  return f();\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0204]: redefinition of `f`"), "{err}");
+    assert!(err.contains("2 | int f() { return 2; }\n  |     ^"), "{err}");
+}
+
+#[test]
+fn storage_class_errors_are_reported() {
+    let out = compile(
+        "int g;\nstatic extern int h;\nint main() {\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0102]: multiple storage classes in declaration specifiers"), "{err}");
+    assert!(err.contains("2 | static extern int h;\n  |        ^^^^^^"), "{err}");
+}
+
+#[test]
+fn block_scope_declarations_stay_in_their_block() {
+    let out = compile(
+        "int f(int a, int b);\nint main() {\n  { extern int y; int f(int); }\n  extern int z = 3;\n  return y;\n}\nint y = 4;\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0204]: conflicting types for `f`"), "{err}");
+    assert!(err.contains("3 |   { extern int y; int f(int); }\n  |                       ^ declared here as `int(int)`"), "{err}");
+    assert!(err.contains("error[E0211]: `extern` variable `z` in a block cannot be initialized"), "{err}");
+    assert!(err.contains("error[E0200]: use of undeclared identifier `y`"), "{err}");
+    assert!(err.contains("5 |   return y;\n  |          ^"), "{err}");
+}
+
+#[test]
+fn conflicting_redeclarations_are_reported() {
+    let out = compile("int x;\nchar x;\nint g;\nint g(void);\nint main() {\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0204]: conflicting types for `x`"), "{err}");
+    assert!(err.contains("= note: previously declared as `int`"), "{err}");
+    assert!(err.contains("error[E0204]: redefinition of `g` as a different kind of symbol"), "{err}");
+
+    // `(void)` is a prototype with no parameters, unlike `()`
+    let out = compile("int f(void);\nint f(int a);\nint h();\nint h(int a);\nint main() {\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0204]: conflicting types for `f`"), "{err}");
+    assert!(err.contains("= note: previously declared as `int(void)`"), "{err}");
+    assert!(!err.contains("`h`"), "{err}");
+}
+
+#[test]
+fn misplaced_storage_classes_are_reported() {
+    let out = compile(
+        "auto int g;\nint main() {\n  _Thread_local int t;\n  static int f(void);\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0208]: storage class `auto` is not allowed on file-scope variable `g`"), "{err}");
+    assert!(err.contains("error[E0208]: storage class `_Thread_local` is not allowed on block-scope variable `t`"), "{err}");
+    assert!(err.contains("error[E0208]: storage class `static` is not allowed on function `f`"), "{err}");
+}
+
+#[test]
+fn records_are_not_passed_by_value() {
+    let out = compile(
+        "struct S { int a, b; };\nint g(struct S s) {\n  return s.b;\n}\nstruct S h();\nint main() {\n  struct S s = {4, 9};\n  h();\n  return g(s);\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0301]: passing a struct or union by value is not supported"), "{err}");
+    assert!(err.contains("2 | int g(struct S s) {\n  |                ^ this has type `struct S`"), "{err}");
+    assert!(err.contains("9 |   return g(s);\n  |            ^"), "{err}");
+    assert!(err.contains("error[E0301]: returning a struct or union by value is not supported"), "{err}");
+    assert!(err.contains("8 |   h();\n  |   ^"), "{err}");
+}
+
+#[test]
+fn thread_locals_need_target_support() {
+    let out = compile("_Thread_local int t;\nint main() {\n  return t;\n}\n", &["--abi=win64"]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0301]: thread-local storage is not supported on this target"), "{err}");
+    assert!(err.contains("3 |   return t;\n  |          ^"), "{err}");
+}
+
+#[test]
+fn misplaced_jumps_are_reported() {
+    let out = compile(
+        "int main() {\n  break;\n  switch (1) {\n  case 1: continue;\n  case 1: default: default: ;\n  }\n  case 2: goto nowhere;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0209]: `break` statement not within a loop or switch"), "{err}");
+    assert!(err.contains("error[E0209]: `continue` statement not within a loop"), "{err}");
+    assert!(err.contains("error[E0210]: duplicate case value `1`"), "{err}");
+    assert!(err.contains("5 |   case 1: default: default: ;\n  |        ^"), "{err}");
+    assert!(err.contains("error[E0210]: multiple `default` labels in one switch"), "{err}");
+    assert!(err.contains("error[E0209]: `case` label not within a switch statement"), "{err}");
+    assert!(err.contains("error[E0200]: use of undeclared label `nowhere`"), "{err}");
+}
+
+#[test]
+fn duplicate_labels_are_reported() {
+    let out = compile("int main() {\nl:\n  ;\nl:\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0204]: redefinition of label `l`"));
+}
+
+#[test]
+fn invalid_casts_and_sizeof_are_reported() {
+    let out = compile(
+        "struct s { int x; } v;\nstruct t;\nint main() {\n  int n = (int)v;\n  return sizeof(struct t) + _Alignof(struct t);\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0405]: invalid cast from `struct s` to `int`"), "{err}");
+    assert!(err.contains("4 |   int n = (int)v;\n  |           ^"), "{err}");
+    assert!(err.contains("error[E0406]: invalid application of `sizeof` to incomplete type `struct t`"), "{err}");
+    assert!(err.contains("error[E0406]: invalid application of `_Alignof` to incomplete type `struct t`"), "{err}");
+
+
+    let out = compile("extern int q[];\nint main() {\n  return sizeof(int[]) + sizeof q;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("3 |   return sizeof(int[]) + sizeof q;\n  |          ^^^^^^"), "{err}");
+    assert!(err.contains("3 |   return sizeof(int[]) + sizeof q;\n  |                          ^^^^^^"), "{err}");
+    assert!(err.contains("error[E0406]: invalid application of `sizeof` to incomplete type `int[]`"), "{err}");
+    assert!(err.contains("aborting due to 2 previous errors"), "{err}");
+}
+
+#[test]
+fn storage_class_in_type_name_is_reported() {
+    let out = compile("int main() { return (static int)1; }", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr_of(&out).contains("error[E0102]: storage class specifier is not allowed in a type name"));
+}
+
+#[test]
+fn invalid_initializers_are_reported() {
+    let src = "struct s { int x; };\nint main() {\n  int a[2] = {1, 2, 3};\n  struct s v = {.y = 1};\n  int b[2] = {[2] = 1};\n  int c = {.x = 1};\n  int d[2] = {.x = 1};\n  return v.x;\n}\n";
+    let out = compile(src, &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("warning[E0212]: excess elements in array initializer"), "{err}");
+    assert!(err.contains("3 |   int a[2] = {1, 2, 3};\n  |                     ^"), "{err}");
+    assert!(err.contains("error[E0211]: no member named `y` in `struct s`"), "{err}");
+    assert!(err.contains("error[E0211]: array index `2` in initializer exceeds array bounds of `int[2]`"), "{err}");
+    assert!(err.contains("error[E0211]: designator in initializer for scalar type `int`"), "{err}");
+    assert!(err.contains("6 |   int c = {.x = 1};\n  |             ^"), "{err}");
+    assert!(err.contains("error[E0211]: member designator on non-record type `int[2]`"), "{err}");
+    assert!(err.contains("aborting due to 4 previous errors"), "{err}");
+}
+
+#[test]
+fn non_constant_subexpressions_are_pointed_at() {
+    let out = compile(
+        "int n;\nint f();\nint a = 2 * (1 + f());\nint b = 1 / (n - n);\nchar c = (char)&n;\nint d = 4 / 0;\nint e[3 > 2 ? 1 : n];\nint main() {\n  int x;\n  static int *p = &x;\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("3 | int a = 2 * (1 + f());\n  |                  ^ a function call is not allowed"), "{err}");
+    assert!(err.contains("4 | int b = 1 / (n - n);\n  |              ^ the value of a variable is not a constant"), "{err}");
+    assert!(err.contains("5 | char c = (char)&n;\n  |          ^ an address does not fit in this type"), "{err}");
+    assert!(err.contains("6 | int d = 4 / 0;\n  |           ^ division by zero"), "{err}");
+    assert!(!err.contains("7 |"), "{err}");
+    assert!(err.contains("10 |   static int *p = &x;\n   |                    ^ the address of a local variable is not a constant"), "{err}");
+    assert!(err.contains("aborting due to 5 previous errors"), "{err}");
+}
+
+#[test]
+fn signed_overflow_in_constants_is_reported() {
+    let out = compile(
+        "int a = 2147483647 + 1;\nint b = (-2147483647 - 1) / -1;\nint c = -(-2147483647 - 1);\nlong d = 9223372036854775807 * 2;\nunsigned e = 4294967295u + 1;\nint main() {\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("1 | int a = 2147483647 + 1;\n  |                    ^ signed integer overflow"), "{err}");
+    assert!(err.contains("2 | int b = (-2147483647 - 1) / -1;\n  |                           ^ signed integer overflow"), "{err}");
+    assert!(err.contains("3 | int c = -(-2147483647 - 1);\n  |         ^ signed integer overflow"), "{err}");
+    assert!(err.contains("4 | long d = 9223372036854775807 * 2;"), "{err}");
+    assert!(err.contains("aborting due to 4 previous errors"), "{err}");
+}
+
+#[test]
+fn shift_counts_in_constants_are_checked() {
+    let out = compile(
+        "enum { X = 1 << 32 };\nint a = 1 << -1;\nlong b = 1L >> 64;\nlong c = 1L << 63u >> 63;\nunsigned d = 1u >> 31;\nint main() {\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(
+        err.contains("1 | enum { X = 1 << 32 };\n  |              ^^ shift count is not less than the width of the type"),
+        "{err}"
+    );
+    assert!(err.contains("2 | int a = 1 << -1;\n  |           ^^ shift count is negative"), "{err}");
+    assert!(err.contains("3 | long b = 1L >> 64;\n  |             ^^ shift count is not less than the width of the type"), "{err}");
+    assert!(err.contains("aborting due to 3 previous errors"), "{err}");
+}
+
+#[test]
+fn invalid_variable_arguments_are_reported() {
+    let out = compile("int f(int n) {\n  va_list ap;\n  va_start(ap, n);\n  return 0;\n}\n", &[]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0213]: `va_start` used in function with fixed arguments"), "{err}");
+    assert!(err.contains("3 |   va_start(ap, n);\n  |   ^^^^^^^^"), "{err}");
+
+    let out = compile(
+        "int h(int a, int b, ...) {\n  va_list ap;\n  va_start(ap, a);\n  va_start(ap, b + 0);\n  va_start(ap, b);\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0213]: second argument to `va_start` is not the last named parameter"), "{err}");
+    assert!(err.contains("3 |   va_start(ap, a);\n  |                ^"), "{err}");
+    assert!(err.contains("= help: pass `b` instead"), "{err}");
+    assert!(err.contains("aborting due to 2 previous errors"), "{err}");
+
+    let out = compile(
+        "struct s { int x; };\nint g(int n, ...) {\n  va_list ap;\n  va_start(n, n);\n  va_arg(ap, struct s);\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0407]: argument to `va_start` is not a `va_list` (have `int`)"), "{err}");
+    assert!(err.contains("4 |   va_start(n, n);\n  |            ^"), "{err}");
+    assert!(err.contains("error[E0407]: `va_arg` of type `struct s` is not supported"), "{err}");
+}
+
+#[test]
+fn calls_are_checked_against_the_callee() {
+    let out = compile(
+        "struct s { int a; };\nint f(int a, int b);\nint g(void);\nint p(const char *fmt, ...);\nint main() {\n  int x = 5;\n  struct s v;\n  x(1);\n  f(1);\n  f(1, 2, 3);\n  g(1);\n  p();\n  f(v, 2);\n  return 0;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(err.contains("error[E0408]: called object type `int` is not a function or function pointer"), "{err}");
+    assert!(err.contains("8 |   x(1);\n  |   ^"), "{err}");
+    assert!(err.contains("error[E0409]: too few arguments to function call, expected 2, have 1"), "{err}");
+    assert!(err.contains("error[E0409]: too many arguments to function call, expected 2, have 3"), "{err}");
+    assert!(err.contains("error[E0409]: too many arguments to function call, expected 0, have 1"), "{err}");
+    assert!(err.contains("error[E0409]: too few arguments to function call, expected at least 1, have 0"), "{err}");
+    assert!(err.contains("error[E0401]: passing `struct s` to parameter of incompatible type `int`"), "{err}");
+    assert!(err.contains("13 |   f(v, 2);\n   |     ^"), "{err}");
+    assert!(err.contains("aborting due to 6 previous errors"), "{err}");
+
+    // an unprototyped declaration takes any arguments
+    let out = compile("int h();\nint main() {\n  return h(1, 2) + h();\n}\n", &[]);
+    assert!(out.status.success(), "{}", stderr_of(&out));
+}
+
+#[test]
+fn conversions_and_returns_are_checked() {
+    let out = compile(
+        "struct s { int a; };\nstruct s v;\nvoid f(void) {}\nint g(void) { return v; }\nint h(void) { return f(); }\nvoid k(void) { return 1; }\nint main() {\n  int *p = 5;\n  long l = p;\n  p = l;\n  return !v;\n}\n",
+        &[],
+    );
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr_of(&out);
+    assert!(
+        err.contains("error[E0401]: returning `struct s` from a function with incompatible result type `int`"),
+        "{err}"
+    );
+    assert!(err.contains("4 | int g(void) { return v; }\n  |                      ^"), "{err}");
+    assert!(err.contains("error[E0401]: returning `void` from a function with incompatible result type `int`"), "{err}");
+    assert!(err.contains("error[E0410]: void function should not return a value"), "{err}");
+    assert!(
+        err.contains("error[E0401]: incompatible integer to pointer conversion initializing `int *` with an expression of type `int`"),
+        "{err}"
+    );
+    assert!(
+        err.contains("error[E0401]: incompatible pointer to integer conversion initializing `long` with an expression of type `int *`"),
+        "{err}"
+    );
+    assert!(
+        err.contains("error[E0401]: incompatible integer to pointer conversion assigning to `int *` from type `long`"),
+        "{err}"
+    );
+    assert!(err.contains("error[E0401]: invalid operand to unary `!` (have `struct s`)"), "{err}");
+    assert!(err.contains("aborting due to 7 previous errors"), "{err}");
+
+    // a null pointer constant needs no cast, and `_Bool` tests a pointer
+    let out = compile(
+        "enum { NONE };\nint *f(int *p) {\n  _Bool b = p;\n  p = 0;\n  p = NONE;\n  p = (1 - 1);\n  return b ? 0 : (int *)1;\n}\nint main() {\n  return f(0) != 0;\n}\n",
+        &[],
+    );
+    assert!(out.status.success(), "{}", stderr_of(&out));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn enumerators_count_up_from_zero() {
+    assert_eq!(run("int main() { enum { A, B, C }; return A * 100 + B * 10 + C; }"), 12);
+    assert_eq!(run("int main() { enum color { RED, GREEN = 5, BLUE }; return BLUE; }"), 6);
+    assert_eq!(run("int main() { enum { A = -2, B, C, }; return C + 10; }"), 10);
+}
+
+#[test]
+fn enumerator_values_are_constant_expressions() {
+    let code = r#"
+        enum { SHIFT = 2, SIZE = 1 << SHIFT, MASK = SIZE - 1, BIG = SIZE * SIZE + (MASK ? 1 : 0) };
+        int main() { return BIG * 10 + MASK; }
+    "#;
+    assert_eq!(run(code), 173);
+}
+
+#[test]
+fn enum_types_hold_int_values() {
+    let code = r#"
+        enum color { RED, GREEN, BLUE };
+        enum color next(enum color c) { return c + 1; }
+        int main() {
+            enum color c = RED;
+            enum color d;
+            c = next(c);
+            d = next(c);
+            return c * 10 + d;
+        }
+    "#;
+    assert_eq!(run(code), 12);
+}
+
+#[test]
+fn enumerators_size_arrays() {
+    assert_eq!(run("int main() { enum { N = 4 }; int a[N * 2]; a[N * 2 - 1] = 3; return a[7]; }"), 3);
+}
+
+#[test]
+fn enumerators_follow_block_scope() {
+    let code = r#"
+        enum { X = 1 };
+        int main() {
+            int a = X;
+            {
+                enum { X = 10 };
+                a += X;
+            }
+            return a + X;
+        }
+    "#;
+    assert_eq!(run(code), 12);
+}
+
+#[test]
+fn forward_declared_enums_are_completed_by_their_body() {
+    let code = r#"
+        enum E;
+        enum E *current;
+        enum E { A, B = 7 };
+        int main() { enum E e = B; current = &e; return *current + A; }
+    "#;
+    assert_eq!(run(code), 7);
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::{run, run_stdout};
+
+#[test]
+fn globals_start_zeroed_and_keep_their_values() {
+    let code = r#"
+        int counter;
+        long history[4];
+        int bump() { counter = counter + 1; history[counter] = counter * 10; return counter; }
+        int main() { bump(); bump(); bump(); return counter + history[0] + history[3]; }
+    "#;
+    assert_eq!(run(code), 33);
+}
+
+#[test]
+fn globals_take_constant_initializers() {
+    let code = r#"
+        enum { N = 3 };
+        int limit = N * 2, *ptr;
+        char small = 300;
+        long big = 1L << 40;
+        _Bool flag = 42;
+        int main() {
+            ptr = &limit;
+            *ptr += 1;
+            return limit + small + (big >> 40) + flag;
+        }
+    "#;
+    assert_eq!(run(code), 7 + 44 + 1 + 1);
+}
+
+#[test]
+fn globals_take_string_initializers() {
+    let code = r#"
+        char msg[] = "hi!";
+        char padded[6] = "ab";
+        char *greeting = "hello";
+        unsigned short wide[] = u"\U0001F600";
+        int main() {
+            return sizeof_of_msg() + msg[2] + padded[1] + padded[5] + greeting[4] + (wide[1] == 0xDE00) + (wide[2] == 0);
+        }
+        int sizeof_of_msg() { return msg[3] == 0; }
+    "#;
+    assert_eq!(run(code), 1 + 33 + 98 + 111 + 1 + 1);
+}
+
+#[test]
+fn locals_take_string_initializers() {
+    let code = r#"
+        int printf();
+        int main() {
+            char buf[8] = "abc";
+            char name[] = "xyz";
+            buf[3] = 'd';
+            printf("%s %s", buf, name);
+            return buf[7];
+        }
+    "#;
+    assert_eq!(run_stdout(code), (0, "abcd xyz".to_string()));
+}
+
+#[test]
+fn tentative_definitions_share_one_object() {
+    let code = r#"
+        int x;
+        int x = 5;
+        int x;
+        int read() { return x; }
+        int main() { x = x + 1; return read(); }
+    "#;
+    assert_eq!(run(code), 6);
+}
+
+#[test]
+fn tentative_arrays_are_completed() {
+    let code = r#"
+        int a[];
+        int a[10];
+        extern int b[];
+        int b[3];
+        int c[];
+        int main() { a[9] = 2; c[0] = 1; return sizeof(a) + sizeof(b) + sizeof(c) + a[9] + c[0]; }
+    "#;
+    assert_eq!(run(code), 40 + 12 + 4 + 2 + 1);
+}
+
+#[test]
+fn locals_hide_globals() {
+    let code = r#"
+        int x = 10;
+        int global() { return x; }
+        int main() { int x = 1; { x = x + 1; } return x * 100 + global(); }
+    "#;
+    assert_eq!(run(code), 210);
+}
+
+#[test]
+fn const_globals_are_read_only_data() {
+    let code = r#"
+        const int answer = 42;
+        const char digits[] = "0123";
+        int main() { return answer + digits[3]; }
+    "#;
+    assert_eq!(run(code), 42 + '3' as i32);
+}
+
+#[test]
+fn struct_globals_are_zeroed() {
+    let code = r#"
+        struct point { char tag; long x, y; } origin, points[2];
+        int main() { points[1].y = 7; return origin.x + origin.tag + points[1].y + points[0].y; }
+    "#;
+    assert_eq!(run(code), 7);
+}
+
+#[test]
+fn globals_may_be_named_like_registers() {
+    let code = r#"
+        int rax = 5;
+        static int ss = 2;
+        _Thread_local int R12d = 3;
+        int *mod = &rax;
+        int rcx(int offset) { return offset * 10; }
+        int main(void) { int (*xmm0)(int) = rcx; return rax + ss + R12d + *mod + xmm0(rcx(1)); }
+    "#;
+    assert_eq!(run(code), 5 + 2 + 3 + 5 + 100);
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{run, run_stdout};
+
+#[test]
+fn array_initializers_zero_the_rest() {
+    let code = r#"
+        int main() {
+            int a[5] = {1, 2, 3};
+            long b[] = {10, 20, 30, 40,};
+            char c[4] = {};
+            return a[0] + a[2] + a[3] + a[4] + sizeof b / sizeof b[0] + b[3] + c[3];
+        }
+    "#;
+    assert_eq!(run(code), 1 + 3 + 4 + 40);
+}
+
+#[test]
+fn nested_braces_and_brace_elision() {
+    let code = r#"
+        int main() {
+            int m[2][3] = {{1, 2, 3}, {4, 5}};
+            int e[2][3] = {1, 2, 3, 4};
+            int inferred[][2] = {1, 2, 3};
+            return m[1][1] * 10 + m[1][2] + e[1][0] * 10 + e[1][1] + sizeof inferred / sizeof inferred[0];
+        }
+    "#;
+    assert_eq!(run(code), 50 + 40 + 2);
+}
+
+#[test]
+fn struct_and_union_initializers() {
+    let code = r#"
+        struct point { char tag; long x, y; };
+        struct line { struct point from, to; int width; };
+        union num { char c; int i; };
+        int main() {
+            struct point p = {'p', 3, 4};
+            struct line l = {p, {'q', 5}, 7};
+            struct line elided = {'a', 1, 2, 'b', 3, 4};
+            union num u = {-1};
+            return p.tag + l.from.y + l.to.x + l.to.y + l.width + elided.to.x * 10 + (u.i == 255) + u.c;
+        }
+    "#;
+    assert_eq!(run(code), 'p' as i32 + 4 + 5 + 7 + 30);
+}
+
+#[test]
+fn designated_initializers() {
+    let code = r#"
+        struct point { int x, y, z; };
+        struct box { int id; struct point corner[2]; };
+        int main() {
+            int a[6] = {[4] = 7, 8, [1] = 2};
+            int sparse[] = {[9] = 1};
+            struct point p = {.z = 3, .x = 1};
+            struct box b = {.corner[1].y = 5, 6, .id = 9};
+            union { char c; int i; } u = {.i = 0x0101};
+            return a[4] * 10 + a[5] + a[1] * 100 + sizeof sparse / sizeof sparse[0] + p.x + p.y + p.z
+                + b.corner[1].y + b.corner[1].z + b.id + u.i;
+        }
+    "#;
+    // a 78 + 200 + 10, p 4, b 20, u 257
+    assert_eq!(run(code), (78 + 200 + 10 + 4 + 20 + 257) % 256);
+}
+
+#[test]
+fn later_designators_override_earlier_ones() {
+    let code = r#"
+        int g[3] = {1, 2, 3, [0] = 5};
+        int main() {
+            int a[3] = {1, 2, 3, [1] = 9};
+            return g[0] * 10 + a[1];
+        }
+    "#;
+    assert_eq!(run(code), 59);
+}
+
+#[test]
+fn char_arrays_take_string_initializers() {
+    let code = r#"
+        int printf();
+        struct name { char first[8]; int len; };
+        int main() {
+            char braced[] = {"hi"};
+            char rows[2][4] = {"ab", "cde"};
+            struct name n = {"joe", 3};
+            printf("%s %s %s %s %d", braced, rows[0], rows[1], n.first, n.len);
+            return sizeof braced;
+        }
+    "#;
+    assert_eq!(run_stdout(code), (3, "hi ab cde joe 3".to_string()));
+}
+
+#[test]
+fn global_initializer_lists_are_emitted_as_data() {
+    let code = r#"
+        struct point { char tag; long x, y; };
+        struct point origin = {'o'};
+        struct point pts[] = {{'a', 1, 2}, [2] = {.y = 9}};
+        const short table[2][2] = {{1, 2}, {3}};
+        char *words[] = {"zero", "one"};
+        char grid[2][3] = {"ab", "c"};
+        int scalar = {42};
+        int main() {
+            static int counts[4] = {[3] = 4};
+            return origin.tag + origin.x + sizeof pts / sizeof pts[0] + pts[0].y + pts[2].y + pts[1].x
+                + table[1][0] + table[1][1] + words[1][2] + grid[1][0] + grid[0][2] + scalar + counts[3];
+        }
+    "#;
+    assert_eq!(run(code), ('o' as i32 + 3 + 2 + 9 + 3 + 'e' as i32 + 'c' as i32 + 42 + 4) % 256);
+}
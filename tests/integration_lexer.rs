@@ -0,0 +1,73 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_line_comments() {
+    assert_eq!(run("// leading\nmain(){ return 3; // trailing\n}\n// eof comment"), 3);
+    assert_eq!(run("main(){ return 8 // not a division\n/2; }"), 4);
+}
+
+#[test]
+fn test_block_comments() {
+    assert_eq!(run("/* header */ main(){ return /* inline */ 5; }"), 5);
+    assert_eq!(run("main(){ int a/**/=2; return a/*x*//a; }"), 1);
+    assert_eq!(run("main(){ /* spans\n several\n lines */ return 6; }"), 6);
+    assert_eq!(run("main(){ /* // nested line comment */ return 7; }"), 7);
+}
+
+#[test]
+fn test_line_splicing() {
+    assert_eq!(run("main(){ int ab = 4; return a\\\nb; }"), 4);
+    assert_eq!(run("main(){ return 1\\\n2; }"), 12);
+    assert_eq!(run("main(){ // comment continues \\\n return 1;\n return 9; }"), 9);
+    assert_eq!(run("main(){ return 2 <\\\r\n= 2; }"), 1);
+}
+
+#[test]
+fn test_integer_literal_radixes() {
+    assert_eq!(run("main(){ return 0x2a; }"), 42);
+    assert_eq!(run("main(){ return 0XfF; }"), 255);
+    assert_eq!(run("main(){ return 017; }"), 15);
+    assert_eq!(run("main(){ return 0; }"), 0);
+    assert_eq!(run("main(){ return 0b101010; }"), 42);
+    assert_eq!(run("main(){ return 0B11; }"), 3);
+}
+
+#[test]
+fn test_integer_literal_separators_and_suffixes() {
+    assert_eq!(run("main(){ return 1'000'000 == 1000000; }"), 1);
+    assert_eq!(run("main(){ return 0x7'f; }"), 127);
+    assert_eq!(run("main(){ return 10u + 10U + 1l + 1L + 1ll + 1LL; }"), 24);
+    assert_eq!(run("main(){ return 1ul + 1LU + 1ull + 1LLU + 1uLL; }"), 5);
+}
+
+#[test]
+fn test_large_integer_literals() {
+    assert_eq!(run("main(){ return 0xffffffffffffffff == -1; }"), 1);
+    assert_eq!(run("main(){ return 9223372036854775807 - 9223372036854775806; }"), 1);
+    assert_eq!(run("main(){ return (4294967296 >> 32) + (0x100000000 >> 31); }"), 3);
+}
+
+#[test]
+fn test_separator_does_not_eat_char_literal() {
+    assert_eq!(run("main(){ return 1+'a'; }"), 98);
+}
+
+#[test]
+fn test_identifiers_with_underscores() {
+    assert_eq!(run("main(){ int my_var=3; int _start=4; int __x__=5; return my_var+_start+__x__; }"), 12);
+    assert_eq!(run("_twice(x_1){ return x_1*2; } main(){ return _twice(21); }"), 42);
+}
+
+#[test]
+fn test_unicode_identifiers() {
+    assert_eq!(run("main(){ int été=10; int π=3; return été+π; }"), 13);
+    assert_eq!(run("main(){ int \\u00e9t\\u00E9=7; return été; }"), 7);
+    assert_eq!(run("main(){ int x\\U0001F600=9; return x😀; }"), 9);
+}
+
+#[test]
+fn test_keyword_prefixes_are_identifiers() {
+    assert_eq!(run("main(){ int integer=1; int do_it=2; int returns=3; int _Boolean=4; return integer+do_it+returns+_Boolean; }"), 10);
+}
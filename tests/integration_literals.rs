@@ -0,0 +1,66 @@
+mod common;
+
+use common::{run, run_stdout};
+
+#[test]
+fn test_char_literals() {
+    assert_eq!(run("main(){return 'a';}"), 97);
+    assert_eq!(run("main(){return '\\n';}"), 10);
+    assert_eq!(run("main(){return '\\x41';}"), 65);
+    assert_eq!(run("main(){return '\\101';}"), 65);
+    assert_eq!(run("main(){return '\\0';}"), 0);
+    assert_eq!(run("main(){return '\\'' + '\\\\';}"), 39 + 92);
+}
+
+#[test]
+fn test_char_literal_values() {
+    // plain char is signed on x86-64
+    assert_eq!(run("main(){return '\\xff' == -1;}"), 1);
+    assert_eq!(run("main(){return 'ab' == 24930;}"), 1);
+    assert_eq!(run("main(){return u'\\xffff' == 65535;}"), 1);
+    assert_eq!(run("main(){return U'\\U0001F600' == 128512;}"), 1);
+    assert_eq!(run("main(){return L'\\x1234' == 4660;}"), 1);
+}
+
+#[test]
+fn test_puts_string_literal() {
+    let (code, out) = run_stdout(r#"main(){ puts("hello"); return 0; }"#);
+    assert_eq!(code, 0);
+    assert_eq!(out, "hello\n");
+}
+
+#[test]
+fn test_string_escape_sequences() {
+    let (_, out) = run_stdout(r#"main(){ puts("a\tb\x41\102\"\\\?\'"); return 0; }"#);
+    assert_eq!(out, "a\tbAB\"\\?'\n");
+}
+
+#[test]
+fn test_adjacent_strings_are_concatenated() {
+    let (_, out) = run_stdout(r#"main(){ puts("foo" "bar"
+        "baz"); return 0; }"#);
+    assert_eq!(out, "foobarbaz\n");
+    // escapes are decoded before concatenation
+    let (_, out) = run_stdout(r#"main(){ puts("\x4" "1"); return 0; }"#);
+    assert_eq!(out, "\u{4}1\n");
+}
+
+#[test]
+fn test_string_literals_get_distinct_labels() {
+    let (_, out) = run_stdout(r#"main(){ puts("one"); puts("two"); puts("one"); return 0; }"#);
+    assert_eq!(out, "one\ntwo\none\n");
+}
+
+#[test]
+fn test_prefixed_string_literals() {
+    let (_, out) = run_stdout(r#"main(){ puts(u8"h\u00e9llo"); return 0; }"#);
+    assert_eq!(out, "héllo\n");
+    assert_eq!(run(r#"main(){ return wcslen(L"abc"); }"#), 3);
+    assert_eq!(run(r#"main(){ return memcmp(u"a\u00e9", "a\0\xe9\0", 4); }"#), 0);
+    assert_eq!(run(r#"main(){ return memcmp(U"a" "b", "a\0\0\0b\0\0\0", 8); }"#), 0);
+}
+
+#[test]
+fn test_string_with_embedded_nul() {
+    assert_eq!(run(r#"main(){ return strlen("ab\0cd"); }"#), 2);
+}
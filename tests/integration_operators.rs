@@ -0,0 +1,97 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn test_modulo() {
+    assert_eq!(run("main(){return 17%5;}"), 2);
+    assert_eq!(run("main(){return 2+17%5*3;}"), 8);
+}
+
+#[test]
+fn test_logical_not_and_bitwise_not() {
+    assert_eq!(run("main(){return !0;}"), 1);
+    assert_eq!(run("main(){return !7;}"), 0);
+    assert_eq!(run("main(){return ~-8;}"), 7);
+}
+
+#[test]
+fn test_bitwise_operators() {
+    assert_eq!(run("main(){return 12&10;}"), 8);
+    assert_eq!(run("main(){return 12|3;}"), 15);
+    assert_eq!(run("main(){return 12^10;}"), 6);
+    // `&` binds tighter than `^`, which binds tighter than `|`
+    assert_eq!(run("main(){return 1|6^3&2;}"), 5);
+}
+
+#[test]
+fn test_shifts() {
+    assert_eq!(run("main(){return 1<<5;}"), 32);
+    assert_eq!(run("main(){return 200>>3;}"), 25);
+    assert_eq!(run("main(){return -16>>2==-4;}"), 1);
+    assert_eq!(run("main(){return 1<<2+1;}"), 8);
+}
+
+#[test]
+fn test_logical_operators() {
+    assert_eq!(run("main(){return 2&&3;}"), 1);
+    assert_eq!(run("main(){return 2&&0;}"), 0);
+    assert_eq!(run("main(){return 0||0;}"), 0);
+    assert_eq!(run("main(){return 0||5;}"), 1);
+    assert_eq!(run("main(){return 1||0&&0;}"), 1);
+}
+
+#[test]
+fn test_short_circuit_skips_rhs() {
+    assert_eq!(run("main(){ int x=1; 0 && (x=5); return x; }"), 1);
+    assert_eq!(run("main(){ int x=1; 1 || (x=5); return x; }"), 1);
+    assert_eq!(run("main(){ int x=1; 1 && (x=5); return x; }"), 5);
+    assert_eq!(run("main(){ int x=1; 0 || (x=5); return x; }"), 5);
+}
+
+#[test]
+fn test_conditional_operator() {
+    assert_eq!(run("main(){return 1?2:3;}"), 2);
+    assert_eq!(run("main(){return 0?2:3;}"), 3);
+    assert_eq!(run("main(){return 0?1:0?2:4;}"), 4);
+    assert_eq!(run("main(){ int x=0; 1?(x=7):(x=9); return x; }"), 7);
+}
+
+#[test]
+fn test_comma_operator() {
+    assert_eq!(run("main(){return (1,2,3);}"), 3);
+    assert_eq!(run("main(){ int x=0; int y=(x=4, x+1); return x*10+y; }"), 45);
+    assert_eq!(run("main(){ int i=0; int j=0; for(i=0, j=10; i<5; i=i+1, j=j-1) ; return j; }"), 5);
+}
+
+#[test]
+fn test_increment_and_decrement() {
+    assert_eq!(run("main(){ int x=5; return ++x; }"), 6);
+    assert_eq!(run("main(){ int x=5; return x++; }"), 5);
+    assert_eq!(run("main(){ int x=5; x++; return x; }"), 6);
+    assert_eq!(run("main(){ int x=5; return --x; }"), 4);
+    assert_eq!(run("main(){ int x=5; int y=x--; return x*10+y; }"), 45);
+}
+
+#[test]
+fn test_compound_assignment() {
+    assert_eq!(run("main(){ int x=5; x+=3; return x; }"), 8);
+    assert_eq!(run("main(){ int x=5; x-=3; return x; }"), 2);
+    assert_eq!(run("main(){ int x=5; x*=3; return x; }"), 15);
+    assert_eq!(run("main(){ int x=17; x/=3; return x; }"), 5);
+    assert_eq!(run("main(){ int x=17; x%=3; return x; }"), 2);
+    assert_eq!(run("main(){ int x=12; x&=10; return x; }"), 8);
+    assert_eq!(run("main(){ int x=12; x|=3; return x; }"), 15);
+    assert_eq!(run("main(){ int x=12; x^=10; return x; }"), 6);
+    assert_eq!(run("main(){ int x=3; x<<=2; return x; }"), 12);
+    assert_eq!(run("main(){ int x=12; x>>=2; return x; }"), 3);
+    assert_eq!(run("main(){ int x=2; int y=3; x+=y*=2; return x*10+y; }"), 86);
+}
+
+#[test]
+fn test_compound_assignment_evaluates_lvalue_once() {
+    assert_eq!(
+        run("main(){ int x=1; int y=10; int *p=&x; *(p=&y)+=5; return y; }"),
+        15
+    );
+}
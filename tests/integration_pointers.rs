@@ -0,0 +1,60 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn pointer_declarators() {
+    assert_eq!(run("int main() { int x=3; int *p=&x; return *p; }"), 3);
+    assert_eq!(run("int main() { int x=3; int *p=&x; int **pp=&p; **pp=9; return x; }"), 9);
+    assert_eq!(run("int main() { int x=1, *p=&x, **pp=&p; return **pp + *p; }"), 2);
+    assert_eq!(run("int main() { int x=4; int *const p=&x; const int *q=p; int const *volatile r=q; return *r; }"), 4);
+    assert_eq!(run("int main() { int x=5; int * restrict p=&x; int *const *pp=&p; return **pp; }"), 5);
+}
+
+#[test]
+fn pointer_plus_integer_scales_by_pointee_size() {
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; return *(p+1); }"), 3);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; return *(1+p); }"), 3);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&x; return *(p-1); }"), 5);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; p=p+1; return *p; }"), 3);
+}
+
+#[test]
+fn pointer_difference_counts_elements() {
+    assert_eq!(run("int main() { int x; int y; return &x - &y; }"), 1);
+    assert_eq!(run("int main() { int x; int y; int z; int *p=&x; int *q=&z; return p-q; }"), 2);
+}
+
+#[test]
+fn pointer_compound_assignment_and_increment_scale() {
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; p+=1; return *p; }"), 3);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&x; p-=1; return *p; }"), 5);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; ++p; return *p; }"), 3);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; p++; return *p; }"), 3);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&x; p--; return *p; }"), 5);
+    assert_eq!(run("int main() { int x=3; int y=5; int *p=&y; int v=*p++; return v + *p; }"), 8);
+}
+
+#[test]
+fn pointer_parameters() {
+    let code = r#"
+        int swap(int *a, int *b) {
+            int t = *a;
+            *a = *b;
+            *b = t;
+            return 0;
+        }
+        int main() {
+            int x = 1;
+            int y = 2;
+            swap(&x, &y);
+            return x * 10 + y;
+        }
+    "#;
+    assert_eq!(run(code), 21);
+}
+
+#[test]
+fn string_literal_arithmetic_steps_bytes() {
+    assert_eq!(run("int main() { return *(\"abc\" + 1) & 255; }"), 98);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{compile_and_run_linked, run, run_linked};
+
+#[test]
+fn static_locals_keep_their_value_between_calls() {
+    let code = r#"
+        int next() { static int n; static int m = 10; n = n + 1; m = m + 1; return n * 100 + m; }
+        int main() { next(); next(); return next() - 300; }
+    "#;
+    assert_eq!(run(code), 13);
+}
+
+#[test]
+fn static_locals_with_the_same_name_are_distinct() {
+    let code = r#"
+        int a() { static int n = 1; n = n * 2; return n; }
+        int b() { static int n = 1; n = n * 3; return n; }
+        int main() { a(); b(); return a() * 10 + b(); }
+    "#;
+    assert_eq!(run(code), 49);
+}
+
+#[test]
+fn extern_declarations_refer_to_other_translation_units() {
+    let code = r#"
+        extern int shared;
+        extern int scale(int x);
+        int main() {
+            extern long counter;
+            counter = counter + 2;
+            return scale(shared) + counter;
+        }
+    "#;
+    let other = "int shared = 4; long counter = 1; int scale(int x) { return x * 10; }";
+    assert_eq!(run_linked(code, other), 43);
+}
+
+#[test]
+fn block_scope_declarations_link_to_later_definitions() {
+    let code = r#"
+        extern int table[4];
+        int table[];
+        int main() {
+            {
+                extern int later;
+                int twice(int);
+                later = twice(sizeof(table));
+            }
+            return read();
+        }
+        int later;
+        int twice(int x) { return x * 2; }
+        int read() { return later; }
+    "#;
+    assert_eq!(run(code), 32);
+}
+
+#[test]
+fn definitions_with_external_linkage_are_visible_to_other_units() {
+    let code = r#"
+        int total = 5;
+        int triple(int x) { return x * 3; }
+        int run_other(void);
+        int main() { return run_other(); }
+    "#;
+    let other = "extern int total; int triple(int); int run_other(void) { return triple(total) + 1; }";
+    assert_eq!(run_linked(code, other), 16);
+}
+
+#[test]
+fn static_definitions_are_private_to_their_unit() {
+    let code = r#"
+        static int hidden = 5;
+        static int helper(void) { return hidden; }
+        int main() { return helper(); }
+    "#;
+    // the other unit's own `hidden` and `helper` do not clash with ours
+    let other = "int hidden = 7; int helper(void) { return 9; }";
+    assert_eq!(run_linked(code, other), 5);
+    // and ours are not there to be referenced
+    let other = "extern int hidden; int *leak = &hidden;";
+    assert!(compile_and_run_linked(code, other).is_err());
+}
+
+#[test]
+fn other_storage_classes_are_accepted() {
+    let code = r#"
+        inline int twice(int x) { return x * 2; }
+        int main() { register int r = 3; auto int a = 4; return twice(r + a); }
+    "#;
+    assert_eq!(run(code), 14);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn thread_locals_live_in_tls() {
+    let code = r#"
+        _Thread_local int tls = 5;
+        _Thread_local long zeroed;
+        int bump() { static _Thread_local int calls; calls = calls + 1; return calls; }
+        int main() { bump(); zeroed = zeroed + 1; return tls + zeroed + bump(); }
+    "#;
+    assert_eq!(run(code), 8);
+}
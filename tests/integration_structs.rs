@@ -0,0 +1,151 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn member_access() {
+    assert_eq!(run("int main() { struct { int a; int b; } x; x.a = 1; x.b = 2; return x.a + x.b; }"), 3);
+    assert_eq!(run("int main() { struct { char a; int b; char c; } x; x.a = 1; x.b = 2; x.c = 3; return x.a * 100 + x.b * 10 + x.c; }"), 123);
+    assert_eq!(run("int main() { struct { int a[3]; int n; } x; x.n = 4; x.a[2] = 5; return x.a[2] + x.n; }"), 9);
+}
+
+#[test]
+fn member_access_through_pointers() {
+    let code = r#"
+        struct point { int x; int y; };
+        int sum(struct point *p) { return p->x + p->y; }
+        int main() {
+            struct point pt;
+            struct point *p = &pt;
+            p->x = 3;
+            (*p).y = 4;
+            p->y += 10;
+            return sum(&pt);
+        }
+    "#;
+    assert_eq!(run(code), 17);
+}
+
+#[test]
+fn members_are_padded_to_their_alignment() {
+    let code = r#"
+        struct s { char c; int i; char d; long l; short h; };
+        int main() {
+            struct s x[2];
+            char *base = &x[0];
+            char *i = &x[0].i;
+            char *d = &x[0].d;
+            char *l = &x[0].l;
+            char *h = &x[0].h;
+            char *next = &x[1];
+            // the size is rounded up to 32 so `l` stays aligned in arrays
+            return i - base == 4 && d - base == 8 && l - base == 16 && h - base == 24 && next - base == 32;
+        }
+    "#;
+    assert_eq!(run(code), 1);
+}
+
+#[test]
+fn unions_share_storage() {
+    let code = r#"
+        union u { int i; char c[4]; short s; };
+        int main() {
+            union u x;
+            union u y[2];
+            char *a = &y[0];
+            char *b = &y[1];
+            x.i = 0;
+            x.c[0] = 1;
+            x.c[1] = 2;
+            return x.s == 513 && b - a == 4;
+        }
+    "#;
+    assert_eq!(run(code), 1);
+}
+
+#[test]
+fn nested_and_anonymous_members() {
+    let code = r#"
+        struct inner { int a; int b; };
+        struct outer {
+            int tag;
+            struct inner in;
+            struct { char x; int y; };
+            union { int i; char c; };
+        };
+        int main() {
+            struct outer o;
+            o.tag = 1;
+            o.in.a = 2;
+            o.in.b = 3;
+            o.x = 4;
+            o.y = 5;
+            o.i = 6;
+            return o.tag + o.in.a + o.in.b + o.x + o.y + o.c;
+        }
+    "#;
+    assert_eq!(run(code), 21);
+}
+
+#[test]
+fn structs_are_copied_by_assignment() {
+    let code = r#"
+        struct pair { char a; long b; int c[2]; };
+        int main() {
+            struct pair x;
+            struct pair y;
+            x.a = 1; x.b = 2; x.c[0] = 3; x.c[1] = 4;
+            y = x;
+            x.a = 10;
+            struct pair z = y;
+            y.b = 20;
+            return z.a + z.b + z.c[0] + z.c[1] + x.a + y.b;
+        }
+    "#;
+    assert_eq!(run(code), 40);
+}
+
+#[test]
+fn forward_declared_and_self_referential_structs() {
+    let code = r#"
+        struct node;
+        int length(struct node *n);
+        struct node { int value; struct node *next; };
+        int length(struct node *n) {
+            int len = 0;
+            for (; n; n = n->next) len++;
+            return len;
+        }
+        int main() {
+            struct node a;
+            struct node b;
+            struct node c;
+            a.next = &b;
+            b.next = &c;
+            c.next = 0;
+            return length(&a);
+        }
+    "#;
+    assert_eq!(run(code), 3);
+}
+
+#[test]
+fn tags_follow_block_scope() {
+    let code = r#"
+        struct t { int a; };
+        int main() {
+            struct t x;
+            x.a = 1;
+            {
+                struct t { char b; int c; };
+                struct t y;
+                y.c = 10;
+                x.a += y.c;
+            }
+            struct t z;
+            z.a = 5;
+            return x.a + z.a;
+        }
+    "#;
+    assert_eq!(run(code), 16);
+}
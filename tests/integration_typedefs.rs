@@ -0,0 +1,85 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn typedef_names_stand_for_types() {
+    let code = r#"
+        typedef unsigned long size_t;
+        typedef int *IntPtr, Pair[2];
+        int main() {
+            size_t n = 3;
+            Pair p;
+            IntPtr q = p;
+            q[1] = 4;
+            return sizeof_check(n) + p[1];
+        }
+        int sizeof_check(size_t n) { return n * 10; }
+    "#;
+    assert_eq!(run(code), 34);
+}
+
+#[test]
+fn typedef_of_incomplete_struct_completed_later() {
+    let code = r#"
+        typedef struct node Node;
+        struct node { int val; Node *next; };
+        int main() {
+            Node a, b;
+            a.val = 1;
+            a.next = &b;
+            b.val = 2;
+            return a.val + a.next->val;
+        }
+    "#;
+    assert_eq!(run(code), 3);
+}
+
+#[test]
+fn typedef_combines_with_qualifiers() {
+    assert_eq!(run("typedef char byte_t; int main() { const byte_t c = 300; return c == 44; }"), 1);
+}
+
+#[test]
+fn variables_hide_typedef_names() {
+    // inside the block `T` is a variable, so `T * x` is a multiplication
+    let code = r#"
+        typedef int T;
+        int main() {
+            T x = 6;
+            {
+                int T = 7;
+                return T * x;
+            }
+        }
+    "#;
+    assert_eq!(run(code), 42);
+}
+
+#[test]
+fn parameters_hide_typedef_names() {
+    let code = r#"
+        typedef int T;
+        int twice(int T) { return T * 2; }
+        int main() { T x = 5; return twice(x); }
+    "#;
+    assert_eq!(run(code), 10);
+}
+
+#[test]
+fn typedefs_follow_block_scope() {
+    let code = r#"
+        typedef char T;
+        int main() {
+            {
+                typedef long T;
+                T big = 1;
+                big = big << 40;
+                if (big == 0) return 1;
+            }
+            T small = 256;
+            return small;
+        }
+    "#;
+    assert_eq!(run(code), 0);
+}
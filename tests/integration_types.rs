@@ -0,0 +1,92 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn integer_types_of_every_width() {
+    assert_eq!(run("int main() { char c = 7; short s = 8; long l = 9; long long ll = 10; return c + s + l + ll; }"), 34);
+    assert_eq!(run("int main() { unsigned char c = 200; unsigned short s = 50; return c + s - 240; }"), 10);
+    assert_eq!(run("int main() { signed x = 3; unsigned y = 4; long int z = 5; return x + y + z; }"), 12);
+    assert_eq!(run("int main() { _Bool b = 1; short int s = 2; unsigned long long u = 3; return b + s + u; }"), 6);
+}
+
+#[test]
+fn narrow_objects_do_not_overlap() {
+    let code = r#"
+        int main() {
+            char a = 1;
+            char b = 2;
+            short c = 3;
+            int d = 4;
+            char e = 5;
+            long f = 6;
+            a = 10; b = 20; c = 30; d = 40; e = 50;
+            return a + b + c + d + e + f;
+        }
+    "#;
+    assert_eq!(run(code), 156);
+}
+
+#[test]
+fn loads_sign_and_zero_extend() {
+    assert_eq!(run("int main() { char c = 255; return c == -1; }"), 1);
+    assert_eq!(run("int main() { unsigned char c = 255; return c == 255; }"), 1);
+    assert_eq!(run("int main() { short s = 65535; return s == -1; }"), 1);
+    assert_eq!(run("int main() { unsigned short s = 65535; return s == 65535; }"), 1);
+    assert_eq!(run("int main() { char a[2]; a[0] = -3; a[1] = 4; return a[0] + a[1] + 10; }"), 11);
+}
+
+#[test]
+fn stores_keep_only_the_low_bytes() {
+    assert_eq!(run("int main() { char c[2]; c[1] = 5; c[0] = 1025; return c[0] + c[1]; }"), 6);
+    assert_eq!(run("int main() { short s; char *p = &s; s = 0; *p = 7; return s; }"), 7);
+    assert_eq!(run("int main() { int x = 3; x += 256; char c = x; return c; }"), 3);
+}
+
+#[test]
+fn pointers_step_by_pointee_size() {
+    assert_eq!(run("int main() { char a[4]; return &a[3] - &a[0]; }"), 3);
+    assert_eq!(run("int main() { short a[4]; short *p = a; p++; p += 2; return p - a; }"), 3);
+    assert_eq!(run("int main() { long a[3]; a[2] = 42; long *p = a; return *(p + 2); }"), 42);
+    assert_eq!(run("int main() { short a[3]; char *p = &a[2]; char *q = &a[0]; return p - q; }"), 4);
+}
+
+#[test]
+fn functions_with_typed_returns_and_params() {
+    let code = r#"
+        char narrow(int x) { return x; }
+        void set(int *p, short v) { *p = v; return; }
+        unsigned char low_byte(unsigned char c) { return c; }
+        int main() {
+            int x;
+            set(&x, 7);
+            return narrow(256 + 3) + x + low_byte(300);
+        }
+    "#;
+    assert_eq!(run(code), 54);
+}
+
+#[test]
+fn void_function_falls_off_the_end() {
+    let code = r#"
+        void bump(int *p) { *p += 1; }
+        int main() { int x = 4; bump(&x); bump(&x); return x; }
+    "#;
+    assert_eq!(run(code), 6);
+}
+
+#[test]
+fn bool_increment_and_decrement_convert_to_bool() {
+    let code = r#"
+        int main() {
+            _Bool a = 1, b = 0, c = 1, d = 0;
+            int old = a++ * 100 + b-- * 50;
+            ++c;
+            --d;
+            _Bool e = 1;
+            int pre = --e;
+            return old + a * 10 + b + c + d + pre * 7;
+        }
+    "#;
+    assert_eq!(run(code), 100 + 10 + 1 + 1 + 1);
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{run, run_linked, run_stdout};
+
+#[test]
+fn variadic_functions_read_their_arguments() {
+    // the arguments past the sixth come from the stack
+    let code = r#"
+        int sum(int n, ...) {
+            va_list ap;
+            va_start(ap, n);
+            int total = 0;
+            while (n-- > 0)
+                total += va_arg(ap, int);
+            va_end(ap);
+            return total;
+        }
+        int main() { return sum(3, 1, 2, 3) + sum(9, 1, 2, 3, 4, 5, 6, 7, 8, 9); }
+    "#;
+    assert_eq!(run(code), 6 + 45);
+}
+
+#[test]
+fn va_arg_reads_each_argument_at_its_type() {
+    let code = r#"
+        long pick(char *kinds, ...) {
+            va_list ap;
+            va_start(ap, kinds);
+            long total = 0;
+            for (; *kinds; kinds++) {
+                if (*kinds == 'c') total += va_arg(ap, int);
+                if (*kinds == 'l') total += va_arg(ap, long) >> 32;
+                if (*kinds == 'p') total += *va_arg(ap, int *);
+            }
+            va_end(ap);
+            return total;
+        }
+        int main() {
+            int x = 40;
+            char c = 2;
+            return pick("clpllcpc", c, 3L << 32, &x, 5L << 32, 1L << 32, 7, &x, -1);
+        }
+    "#;
+    assert_eq!(run(code), 2 + 3 + 40 + 5 + 1 + 7 + 40 - 1);
+}
+
+#[test]
+fn va_list_is_passed_on_and_copied() {
+    let code = r#"
+        int first(va_list ap) { return va_arg(ap, int); }
+        int twice(int n, ...) {
+            va_list ap, again;
+            va_start(ap, n);
+            va_copy(again, ap);
+            int a = first(ap);
+            int b = first(ap);
+            int c = va_arg(again, int);
+            va_end(again);
+            va_end(ap);
+            return a * 100 + b * 10 + c;
+        }
+        int main() { return twice(2, 1, 2) - 100; }
+    "#;
+    assert_eq!(run(code), 21);
+}
+
+#[test]
+fn va_list_is_compatible_with_the_c_library() {
+    let code = r#"
+        int vsprintf();
+        int printf();
+        int format(char *buf, char *fmt, ...) {
+            va_list ap;
+            va_start(ap, fmt);
+            int n = vsprintf(buf, fmt, ap);
+            va_end(ap);
+            return n;
+        }
+        int main() {
+            char buf[64];
+            int n = format(buf, "%d-%s-%ld-%c-%d-%d", 12, "ab", 1L << 40, 'z', 7, -8);
+            printf("%s", buf);
+            return n;
+        }
+    "#;
+    assert_eq!(run_stdout(code), (26, "12-ab-1099511627776-z-7--8".to_string()));
+}
+
+#[test]
+fn variadic_functions_are_callable_from_c() {
+    let code = r#"
+        long mix(int n, ...) {
+            va_list ap;
+            va_start(ap, n);
+            long total = 0;
+            int i = 0;
+            while (i < n) {
+                total = total * 2 + va_arg(ap, long);
+                i++;
+            }
+            va_end(ap);
+            return total;
+        }
+    "#;
+    let other = r#"
+        long mix(int n, ...);
+        int main(void) { return mix(8, 1L, 0L, 1L, 1L, 0L, 0L, 1L, 1L) == 0xb3 ? 0 : 1; }
+    "#;
+    assert_eq!(run_linked(code, other), 0);
+}